@@ -1,2 +1,10 @@
 console.log("Launching WASM game");
-import("../pkg/index.js").catch(console.error);
+// To embed the game in a page, create a `GameHandle` instead, e.g.
+// const game = new wasm.GameHandle(new wasm.EngineConfig().selector("#game"));
+// game.on_game_over(scores => console.log(scores));
+// game.start();
+import("../pkg/index.js")
+  .then(wasm => {
+    window.game = wasm.main_js();
+  })
+  .catch(console.error);
@@ -0,0 +1,158 @@
+//! Frame animations.
+//!
+//! A clip is a sequence of frames, each shown for a number of ticks, played
+//! in a loop, once or back and forth. Frames can carry an event that is
+//! reported when an animation enters them, e.g. to splash on every step of a
+//! walk cycle. Clips are defined by the tags of an Aseprite atlas, see
+//! [Atlas](crate::engine::atlas::Atlas). Each entity keeps an [Animator]
+//! with its own progress through a clip.
+use crate::engine::atlas::FrameId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Mode {
+    Loop,
+    /// Stops on the last frame
+    Once,
+    /// Loops forwards and backwards
+    PingPong,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keyframe {
+    pub frame: FrameId,
+    pub ticks: u32,
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub frames: Vec<Keyframe>,
+    pub mode: Mode,
+}
+
+impl Clip {
+    pub fn new(frames: Vec<Keyframe>, mode: Mode) -> Self {
+        assert!(!frames.is_empty(), "a clip needs at least one frame");
+        Clip { frames, mode }
+    }
+
+    /// Ticks for one pass through the frames
+    pub fn length(&self) -> u32 {
+        self.frames.iter().map(|f| f.ticks).sum()
+    }
+
+    /// Frame shown `ticks` after the clip started
+    pub fn frame_at(&self, ticks: u32) -> FrameId {
+        self.frames[self.position(ticks).0].frame
+    }
+
+    /// Index of the frame shown `ticks` after the clip started, and the
+    /// ticks since the frame was entered
+    fn position(&self, ticks: u32) -> (usize, u32) {
+        let count = self.frames.len();
+        let back = match self.mode {
+            Mode::PingPong => 1..count.saturating_sub(1),
+            _ => 0..0,
+        };
+        let order = (0..count).chain(back.rev());
+        let cycle: u32 = order.clone().map(|i| self.frames[i].ticks).sum();
+        let mut remaining = match self.mode {
+            Mode::Once => ticks.min(cycle.saturating_sub(1)),
+            _ => ticks % cycle.max(1),
+        };
+        for i in order {
+            let duration = self.frames[i].ticks;
+            if remaining < duration {
+                return (i, remaining);
+            }
+            remaining -= duration;
+        }
+        (count - 1, 0)
+    }
+}
+
+/// Clips by name
+#[derive(Default)]
+pub struct Clips {
+    clips: HashMap<String, Clip>,
+}
+
+impl Clips {
+    pub fn get(&self, name: &str) -> Option<&Clip> {
+        self.clips.get(name)
+    }
+
+    pub fn insert(&mut self, name: &str, clip: Clip) {
+        self.clips.insert(String::from(name), clip);
+    }
+
+    pub fn len(&self) -> usize {
+        self.clips.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clips.is_empty()
+    }
+}
+
+/// Progress of an entity through a clip. Clips are referred to by name, so
+/// animators can be stored in snapshots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Animator {
+    clip: String,
+    ticks: u32,
+}
+
+impl Animator {
+    pub fn new(clip: &str) -> Self {
+        Animator {
+            clip: String::from(clip),
+            ticks: 0,
+        }
+    }
+
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    /// Switches to another clip, which starts from the beginning. Does
+    /// nothing if the clip is already playing.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.clip = String::from(clip);
+            self.ticks = 0;
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.ticks = 0;
+    }
+
+    /// Advances the animation by a tick. Returns the event of the frame
+    /// entered on this tick, if any.
+    pub fn update<'a>(&mut self, clips: &'a Clips) -> Option<&'a str> {
+        let clip = clips.get(&self.clip)?;
+        let finished = self.finished(clips);
+        let (index, since) = clip.position(self.ticks);
+        self.ticks = self.ticks.saturating_add(1);
+        if finished || since > 0 {
+            return None;
+        }
+        clip.frames[index].event.as_deref()
+    }
+
+    /// Frame to draw, or `None` if the clip doesn't exist
+    pub fn frame(&self, clips: &Clips) -> Option<FrameId> {
+        clips.get(&self.clip).map(|clip| clip.frame_at(self.ticks))
+    }
+
+    /// Whether a clip played once has reached its end
+    pub fn finished(&self, clips: &Clips) -> bool {
+        match clips.get(&self.clip) {
+            Some(clip) => clip.mode == Mode::Once && self.ticks >= clip.length(),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Sprite sheet atlases.
+//!
+//! An atlas describes where the named frames of a sprite sheet are, so the
+//! sheet can be rearranged without touching the code. Atlases use the JSON
+//! format exported by TexturePacker and Aseprite, with `frames` either as a
+//! hash keyed by name or as an array of entries with a `filename`. Frames may
+//! have a `pivot` relative to their size, which is the point drawn at the
+//! position given to the renderer. It defaults to the center of the frame.
+//! Rotated frames are not supported.
+//!
+//! The frame tags of an Aseprite export become animation clips, showing
+//! each frame for its `duration`. `pingpong` and `reverse` tags are played
+//! accordingly, and tags with a `repeat` count play once. Events are given in
+//! the user data of a tag as `frame:event` pairs separated by commas, with
+//! frames counted from the start of the tag, e.g. `"0:step,2:step"`. Tags
+//! need the array export, since the hash export loses the order of frames.
+use crate::engine::animation::{Clip, Clips, Keyframe, Mode};
+use crate::engine::json::from_js;
+use crate::engine::math::Vec2;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+use web_sys::HtmlImageElement;
+
+thread_local! {
+    static NAMES: RefCell<FrameNames> = RefCell::new(FrameNames::default());
+}
+
+#[derive(Default)]
+struct FrameNames {
+    ids: HashMap<String, u32>,
+    names: Vec<String>,
+}
+
+/// Handle to a named frame. Names are interned the first time they are used,
+/// so handles can be made before any atlas is loaded, e.g. when game data
+/// is deserialized. Handles are serialized as their name.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FrameId(u32);
+
+impl FrameId {
+    pub fn named(name: &str) -> FrameId {
+        NAMES.with(|names| {
+            let mut names = names.borrow_mut();
+            if let Some(id) = names.ids.get(name) {
+                return FrameId(*id);
+            }
+            let id = names.names.len() as u32;
+            names.names.push(String::from(name));
+            names.ids.insert(String::from(name), id);
+            FrameId(id)
+        })
+    }
+
+    pub fn name(self) -> String {
+        NAMES.with(|names| names.borrow().names[self.0 as usize].clone())
+    }
+}
+
+impl Serialize for FrameId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for FrameId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if name.is_empty() {
+            return Err(D::Error::custom("empty frame name"));
+        }
+        Ok(FrameId::named(&name))
+    }
+}
+
+/// Source rectangle of a frame in the sprite sheet, in pixels
+#[derive(Clone)]
+pub struct Frame {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    /// Offset of the pivot from the top left corner, in pixels
+    pub pivot: Vec2,
+}
+
+pub struct Atlas {
+    /// Path of the sprite sheet, relative to the atlas
+    pub image: String,
+    frames: HashMap<FrameId, Frame>,
+    pub clips: Clips,
+}
+
+/// Aseprite's default frame duration
+const DEFAULT_DURATION: f64 = 100.;
+
+const TICKS_PER_SECOND: f64 = 60.;
+
+#[derive(Deserialize)]
+struct AtlasJson {
+    frames: FramesJson,
+    meta: MetaJson,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FramesJson {
+    Hash(HashMap<String, FrameJson>),
+    Array(Vec<NamedFrameJson>),
+}
+
+#[derive(Deserialize)]
+struct NamedFrameJson {
+    filename: String,
+    #[serde(flatten)]
+    frame: FrameJson,
+}
+
+#[derive(Deserialize)]
+struct FrameJson {
+    frame: RectJson,
+    pivot: Option<PointJson>,
+    /// Milliseconds
+    duration: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct RectJson {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+#[derive(Deserialize)]
+struct PointJson {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Deserialize)]
+struct MetaJson {
+    image: String,
+    #[serde(default, rename = "frameTags")]
+    frame_tags: Vec<TagJson>,
+}
+
+#[derive(Deserialize)]
+struct TagJson {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    repeat: Option<serde_json::Value>,
+    data: Option<String>,
+}
+
+impl Atlas {
+    pub fn new_from_json(json: &JsValue) -> Self {
+        Atlas::from_json(from_js(json).unwrap())
+    }
+
+    pub fn frame(&self, id: FrameId) -> Option<&Frame> {
+        self.frames.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Path of the sprite sheet, given the path the atlas was loaded from
+    pub fn image_path(&self, atlas_path: &str) -> String {
+        match atlas_path.rfind('/') {
+            Some(slash) => format!("{}{}", &atlas_path[..=slash], self.image),
+            None => self.image.clone(),
+        }
+    }
+
+    fn from_json(atlas: AtlasJson) -> Self {
+        let (named, ordered): (Vec<(String, FrameJson)>, bool) = match atlas.frames {
+            FramesJson::Hash(frames) => (frames.into_iter().collect(), false),
+            FramesJson::Array(frames) => (
+                frames.into_iter().map(|f| (f.filename, f.frame)).collect(),
+                true,
+            ),
+        };
+
+        let timeline: Vec<(FrameId, u32)> = named
+            .iter()
+            .map(|(name, f)| {
+                let ms = f.duration.unwrap_or(DEFAULT_DURATION);
+                let ticks = (ms * TICKS_PER_SECOND / 1000.).round().max(1.);
+                (FrameId::named(name), ticks as u32)
+            })
+            .collect();
+        let mut clips = Clips::default();
+        if ordered {
+            for tag in atlas.meta.frame_tags.iter() {
+                if let Some(clip) = clip_from_tag(tag, &timeline) {
+                    clips.insert(&tag.name, clip);
+                }
+            }
+        }
+
+        let frames = named
+            .into_iter()
+            .map(|(name, f)| {
+                let pivot = f.pivot.unwrap_or(PointJson { x: 0.5, y: 0.5 });
+                let frame = Frame {
+                    x: f.frame.x,
+                    y: f.frame.y,
+                    w: f.frame.w,
+                    h: f.frame.h,
+                    pivot: Vec2::new(pivot.x * f.frame.w, pivot.y * f.frame.h),
+                };
+                (FrameId::named(&name), frame)
+            })
+            .collect();
+        Atlas {
+            image: atlas.meta.image,
+            frames,
+            clips,
+        }
+    }
+}
+
+fn clip_from_tag(tag: &TagJson, timeline: &[(FrameId, u32)]) -> Option<Clip> {
+    if tag.from > tag.to {
+        return None;
+    }
+    let mut frames: Vec<Keyframe> = timeline
+        .get(tag.from..=tag.to)?
+        .iter()
+        .map(|(frame, ticks)| Keyframe {
+            frame: *frame,
+            ticks: *ticks,
+            event: None,
+        })
+        .collect();
+    for pair in tag.data.iter().flat_map(|data| data.split(',')) {
+        let mut parts = pair.splitn(2, ':');
+        let index = parts.next().and_then(|i| i.trim().parse::<usize>().ok());
+        let event = parts.next().map(str::trim);
+        if let (Some(index), Some(event)) = (index, event) {
+            if let Some(frame) = frames.get_mut(index) {
+                frame.event = Some(String::from(event));
+            }
+        }
+    }
+
+    let once = match &tag.repeat {
+        Some(serde_json::Value::Number(n)) => n.as_u64() != Some(0),
+        Some(serde_json::Value::String(s)) => s != "0",
+        _ => false,
+    };
+    if tag.direction == "reverse" {
+        frames.reverse();
+    }
+    let mode = if tag.direction == "pingpong" {
+        Mode::PingPong
+    } else if once {
+        Mode::Once
+    } else {
+        Mode::Loop
+    };
+    Some(Clip::new(frames, mode))
+}
+
+impl FromStr for Atlas {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        Ok(Atlas::from_json(serde_json::from_str(json)?))
+    }
+}
+
+/// An atlas together with its loaded sprite sheet
+pub struct SpriteSheet {
+    pub atlas: Atlas,
+    pub image: HtmlImageElement,
+}
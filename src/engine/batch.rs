@@ -0,0 +1,184 @@
+//! Sprites collected into vertex data for the WebGL renderer.
+//!
+//! Every sprite becomes a quad of two triangles. The quads of a frame are
+//! sorted by their key, then runs of quads sharing a texture are merged so
+//! they can be drawn with a single call. Nothing here touches the browser,
+//! so the batching can be tested natively.
+use crate::engine::transform::Color;
+
+/// Index of a texture known to the renderer
+pub type TextureId = usize;
+
+/// Floats per vertex: position, texture coordinates, tint and alpha
+pub const FLOATS_PER_VERTEX: usize = 9;
+
+/// Vertices per quad, as two triangles
+pub const VERTICES_PER_QUAD: usize = 6;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vertex {
+    /// Position on screen in pixels
+    pub x: f32,
+    pub y: f32,
+    /// Position in the texture in pixels
+    pub u: f32,
+    pub v: f32,
+    /// Tint color from 0 to 1, with how strongly it replaces the texture
+    /// color in `tint[3]`
+    pub tint: [f32; 4],
+    pub alpha: f32,
+}
+
+/// Draw order of a quad. Quads of higher layers are drawn on top, quads of
+/// the same layer in the order they were added.
+pub fn sort_key(layer: u16, order: u32) -> u64 {
+    (u64::from(layer) << 32) | u64::from(order)
+}
+
+/// Vertices of the `src` rectangle `[x, y, w, h]` of a texture, mapped to the
+/// screen by an affine `matrix` as returned by
+/// [Transform::matrix](crate::engine::transform::Transform::matrix)
+pub fn quad(
+    src: [f64; 4],
+    matrix: [f64; 6],
+    alpha: f64,
+    tint: Option<Color>,
+) -> [Vertex; VERTICES_PER_QUAD] {
+    let [sx, sy, w, h] = src;
+    let [a, b, c, d, e, f] = matrix;
+    let tint = match tint {
+        Some(color) => [
+            f32::from(color.r) / 255.,
+            f32::from(color.g) / 255.,
+            f32::from(color.b) / 255.,
+            color.a as f32,
+        ],
+        None => [0.; 4],
+    };
+    let corner = |lx: f64, ly: f64| Vertex {
+        x: (a * lx + c * ly + e) as f32,
+        y: (b * lx + d * ly + f) as f32,
+        u: (sx + lx) as f32,
+        v: (sy + ly) as f32,
+        tint,
+        alpha: alpha as f32,
+    };
+    let top_left = corner(0., 0.);
+    let top_right = corner(w, 0.);
+    let bottom_left = corner(0., h);
+    let bottom_right = corner(w, h);
+    [
+        top_left,
+        top_right,
+        bottom_left,
+        bottom_left,
+        top_right,
+        bottom_right,
+    ]
+}
+
+/// Vertices drawn with one texture, as a range of the vertex buffer
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Batch {
+    pub texture: TextureId,
+    /// Index of the first vertex
+    pub first: usize,
+    /// Number of vertices
+    pub count: usize,
+}
+
+struct Quad {
+    key: u64,
+    texture: TextureId,
+    vertices: [Vertex; VERTICES_PER_QUAD],
+}
+
+/// The quads of a frame
+pub struct SpriteBatch {
+    layer: u16,
+    quads: Vec<Quad>,
+}
+
+impl Default for SpriteBatch {
+    fn default() -> Self {
+        SpriteBatch::new()
+    }
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        SpriteBatch {
+            layer: 0,
+            quads: Vec::new(),
+        }
+    }
+
+    /// Layer the following quads are added to
+    pub fn set_layer(&mut self, layer: u16) {
+        self.layer = layer;
+    }
+
+    pub fn layer(&self) -> u16 {
+        self.layer
+    }
+
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    pub fn push(
+        &mut self,
+        texture: TextureId,
+        src: [f64; 4],
+        matrix: [f64; 6],
+        alpha: f64,
+        tint: Option<Color>,
+    ) {
+        self.quads.push(Quad {
+            key: sort_key(self.layer, self.quads.len() as u32),
+            texture,
+            vertices: quad(src, matrix, alpha, tint),
+        });
+    }
+
+    /// Adds a filled rectangle, using a texture that is a single white pixel
+    pub fn push_rect(&mut self, white: TextureId, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        self.push(
+            white,
+            [0., 0., 1., 1.],
+            [w, 0., 0., h, x, y],
+            color.a,
+            Some(color.with_alpha(1.)),
+        );
+    }
+
+    /// Sorts the quads and returns their vertices, flattened into
+    /// [FLOATS_PER_VERTEX] floats each, with the batches to draw them in.
+    /// The sprite batch is empty afterwards.
+    pub fn finish(&mut self) -> (Vec<f32>, Vec<Batch>) {
+        self.quads.sort_by_key(|quad| quad.key);
+        let mut vertices =
+            Vec::with_capacity(self.quads.len() * VERTICES_PER_QUAD * FLOATS_PER_VERTEX);
+        let mut batches: Vec<Batch> = Vec::new();
+        for (i, quad) in self.quads.drain(..).enumerate() {
+            match batches.last_mut() {
+                Some(batch) if batch.texture == quad.texture => batch.count += VERTICES_PER_QUAD,
+                _ => batches.push(Batch {
+                    texture: quad.texture,
+                    first: i * VERTICES_PER_QUAD,
+                    count: VERTICES_PER_QUAD,
+                }),
+            }
+            for v in quad.vertices.iter() {
+                vertices.extend_from_slice(&[
+                    v.x, v.y, v.u, v.v, v.tint[0], v.tint[1], v.tint[2], v.tint[3], v.alpha,
+                ]);
+            }
+        }
+        (vertices, batches)
+    }
+}
@@ -0,0 +1,318 @@
+//! Bitmap fonts.
+//!
+//! A font is an image of its characters together with the metrics needed to
+//! lay them out, in the JSON flavour of the BMFont (AngelCode) format. Each
+//! entry of `chars` gives the rectangle of a glyph in the image, its offset
+//! from the pen position and how far the pen advances after it. `kernings`
+//! adjust the advance between pairs of characters, and `common.lineHeight`
+//! is the distance between lines. Only fonts with a single page are
+//! supported.
+//!
+//! A character missing from the font is drawn as its upper case version,
+//! else as `?`, and skipped if the font has neither.
+use crate::engine::error::EngineError;
+use crate::engine::json::from_js;
+use crate::engine::transform::Color;
+use serde::de::Error;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+use web_sys::HtmlImageElement;
+
+/// Metrics of a character, in pixels of the font image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Offset of the glyph from the pen position
+    pub x_offset: f64,
+    pub y_offset: f64,
+    /// How far the pen moves after the glyph
+    pub x_advance: f64,
+}
+
+pub struct BitmapFont {
+    /// Path of the font image, relative to the font
+    pub image: String,
+    pub line_height: f64,
+    glyphs: HashMap<char, Glyph>,
+    kernings: HashMap<(char, char), f64>,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How a text is laid out and drawn
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextStyle {
+    /// Screen pixels per pixel of the font image
+    pub scale: f64,
+    /// Which side of the text the position given is, for each line
+    pub align: Align,
+    /// Width in screen pixels at which lines are wrapped between words
+    pub max_width: Option<f64>,
+    pub tint: Option<Color>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            scale: 1.,
+            align: Align::Left,
+            max_width: None,
+            tint: None,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn scaled(scale: f64) -> Self {
+        TextStyle {
+            scale,
+            ..TextStyle::default()
+        }
+    }
+
+    pub fn align(self, align: Align) -> Self {
+        TextStyle { align, ..self }
+    }
+
+    pub fn centered(self) -> Self {
+        self.align(Align::Center)
+    }
+
+    pub fn wrapped(self, max_width: f64) -> Self {
+        TextStyle {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    pub fn tinted(self, tint: Color) -> Self {
+        TextStyle {
+            tint: Some(tint),
+            ..self
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FontJson {
+    pages: Vec<String>,
+    common: CommonJson,
+    chars: Vec<CharJson>,
+    #[serde(default)]
+    kernings: Vec<KerningJson>,
+}
+
+#[derive(Deserialize)]
+struct CommonJson {
+    #[serde(rename = "lineHeight")]
+    line_height: f64,
+}
+
+#[derive(Deserialize)]
+struct CharJson {
+    id: u32,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    xoffset: f64,
+    yoffset: f64,
+    xadvance: f64,
+}
+
+#[derive(Deserialize)]
+struct KerningJson {
+    first: u32,
+    second: u32,
+    amount: f64,
+}
+
+impl BitmapFont {
+    pub fn new_from_json(json: &JsValue) -> Result<Self, EngineError> {
+        Ok(BitmapFont::from_json(from_js(json)?)?)
+    }
+
+    /// Path of the font image, given the path the font was loaded from
+    pub fn image_path(&self, font_path: &str) -> String {
+        match font_path.rfind('/') {
+            Some(slash) => format!("{}{}", &font_path[..=slash], self.image),
+            None => self.image.clone(),
+        }
+    }
+
+    /// The glyph drawn for a character, and the character it belongs to
+    pub fn glyph(&self, c: char) -> Option<(char, &Glyph)> {
+        [c, c.to_ascii_uppercase(), '?']
+            .iter()
+            .find_map(|c| self.glyphs.get(c).map(|glyph| (*c, glyph)))
+    }
+
+    /// Extra advance between two characters
+    pub fn kerning(&self, first: char, second: char) -> f64 {
+        self.kernings.get(&(first, second)).copied().unwrap_or(0.)
+    }
+
+    /// Width and height of a text drawn with `style`, in screen pixels
+    pub fn measure(&self, text: &str, style: &TextStyle) -> (f64, f64) {
+        let lines = self.lines(text, style);
+        let width = lines
+            .iter()
+            .map(|line| self.line_width(line))
+            .fold(0., f64::max);
+        (
+            width * style.scale,
+            lines.len() as f64 * self.line_height * style.scale,
+        )
+    }
+
+    /// Lines of a text once wrapped to the width of `style`. Words are only
+    /// broken at spaces, so a word wider than a line gets a line of its own.
+    pub fn lines<'a>(&self, text: &'a str, style: &TextStyle) -> Vec<&'a str> {
+        let max_width = match style.max_width {
+            Some(max_width) => max_width / style.scale,
+            None => return text.split('\n').collect(),
+        };
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut start = 0;
+            let mut end = 0;
+            for (index, _) in paragraph
+                .match_indices(' ')
+                .chain(Some((paragraph.len(), "")))
+            {
+                if end > start && self.line_width(&paragraph[start..index]) > max_width {
+                    lines.push(&paragraph[start..end]);
+                    start = end + 1;
+                }
+                end = index;
+            }
+            lines.push(&paragraph[start..]);
+        }
+        lines
+    }
+
+    /// Where to draw the glyphs of a text with the given style at `x`, `y`,
+    /// as rectangles `[x, y, w, h]` in the font image and on screen
+    pub fn layout(
+        &self,
+        text: &str,
+        x: f64,
+        y: f64,
+        style: &TextStyle,
+    ) -> Vec<([f64; 4], [f64; 4])> {
+        let scale = style.scale;
+        let mut glyphs = Vec::new();
+        for (row, line) in self.lines(text, style).iter().enumerate() {
+            let width = self.line_width(line) * scale;
+            let mut pen = match style.align {
+                Align::Left => x,
+                Align::Center => x - width / 2.,
+                Align::Right => x - width,
+            };
+            let top = y + row as f64 * self.line_height * scale;
+            let mut previous = None;
+            for (c, glyph) in line.chars().filter_map(|c| self.glyph(c)) {
+                if let Some(previous) = previous {
+                    pen += self.kerning(previous, c) * scale;
+                }
+                if glyph.width > 0. && glyph.height > 0. {
+                    glyphs.push((
+                        [glyph.x, glyph.y, glyph.width, glyph.height],
+                        [
+                            pen + glyph.x_offset * scale,
+                            top + glyph.y_offset * scale,
+                            glyph.width * scale,
+                            glyph.height * scale,
+                        ],
+                    ));
+                }
+                pen += glyph.x_advance * scale;
+                previous = Some(c);
+            }
+        }
+        glyphs
+    }
+
+    /// Width of a line in pixels of the font image, without trailing spaces
+    fn line_width(&self, line: &str) -> f64 {
+        let mut width: f64 = 0.;
+        let mut pen = 0.;
+        let mut previous = None;
+        for (c, glyph) in line.chars().filter_map(|c| self.glyph(c)) {
+            if let Some(previous) = previous {
+                pen += self.kerning(previous, c);
+            }
+            if c != ' ' {
+                width = width.max(pen + f64::max(glyph.x_advance, glyph.x_offset + glyph.width));
+            }
+            pen += glyph.x_advance;
+            previous = Some(c);
+        }
+        width
+    }
+
+    fn from_json(font: FontJson) -> Result<Self, serde_json::Error> {
+        let image = font
+            .pages
+            .into_iter()
+            .next()
+            .ok_or_else(|| serde_json::Error::custom("font without pages"))?;
+        let glyphs = font
+            .chars
+            .into_iter()
+            .filter_map(|c| {
+                let glyph = Glyph {
+                    x: c.x,
+                    y: c.y,
+                    width: c.width,
+                    height: c.height,
+                    x_offset: c.xoffset,
+                    y_offset: c.yoffset,
+                    x_advance: c.xadvance,
+                };
+                std::char::from_u32(c.id).map(|id| (id, glyph))
+            })
+            .collect();
+        let kernings = font
+            .kernings
+            .into_iter()
+            .filter_map(|k| {
+                let first = std::char::from_u32(k.first)?;
+                let second = std::char::from_u32(k.second)?;
+                Some(((first, second), k.amount))
+            })
+            .collect();
+        Ok(BitmapFont {
+            image,
+            line_height: font.common.line_height,
+            glyphs,
+            kernings,
+        })
+    }
+}
+
+impl FromStr for BitmapFont {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        BitmapFont::from_json(serde_json::from_str(json)?)
+    }
+}
+
+/// A font together with its loaded image
+pub struct Font {
+    pub font: BitmapFont,
+    pub image: HtmlImageElement,
+}
@@ -0,0 +1,169 @@
+//! Where and how the engine draws.
+//!
+//! Built in Rust with the `with_*` methods, or from JavaScript:
+//!
+//! ```js
+//! const config = new EngineConfig().selector("#game").size(640, 360).background("#102030");
+//! launch(config);
+//! ```
+use crate::engine::error::EngineError;
+use crate::engine::transform::Color;
+use crate::engine::viewport::ScaleMode;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element, HtmlCanvasElement};
+
+/// Where the canvas comes from
+#[derive(Clone)]
+pub enum Mount {
+    /// A new canvas at the end of the page
+    Body,
+    /// The canvas matching a selector, or a new canvas inside the element
+    /// matching it
+    Selector(String),
+    Canvas(HtmlCanvasElement),
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct EngineConfig {
+    mount: Mount,
+    width: u32,
+    height: u32,
+    pixel_ratio: Option<f64>,
+    background: Color,
+    scale_mode: ScaleMode,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            mount: Mount::Body,
+            width: 960,
+            height: 540,
+            pixel_ratio: None,
+            background: Color::BLACK,
+            scale_mode: ScaleMode::Integer,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl EngineConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> EngineConfig {
+        EngineConfig::default()
+    }
+
+    /// Draws to an existing canvas
+    #[wasm_bindgen(js_name = canvas)]
+    pub fn with_canvas(self, canvas: HtmlCanvasElement) -> EngineConfig {
+        self.with_mount(Mount::Canvas(canvas))
+    }
+
+    /// Draws to the canvas matching `selector`, or to a new canvas added to
+    /// the element matching it
+    #[wasm_bindgen(js_name = selector)]
+    pub fn with_selector(self, selector: &str) -> EngineConfig {
+        self.with_mount(Mount::Selector(String::from(selector)))
+    }
+
+    /// Virtual resolution the game is drawn at
+    #[wasm_bindgen(js_name = size)]
+    pub fn with_size(self, width: u32, height: u32) -> EngineConfig {
+        EngineConfig {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    /// Device pixels per CSS pixel, instead of `window.devicePixelRatio`
+    #[wasm_bindgen(js_name = pixelRatio)]
+    pub fn with_pixel_ratio(self, pixel_ratio: f64) -> EngineConfig {
+        EngineConfig {
+            pixel_ratio: Some(pixel_ratio),
+            ..self
+        }
+    }
+
+    /// Color of the letterbox bars and behind the game, as a hex color
+    #[wasm_bindgen(js_name = background)]
+    pub fn with_background_css(self, css: &str) -> Result<EngineConfig, JsValue> {
+        match Color::from_css(css) {
+            Some(color) => Ok(self.with_background(color)),
+            None => Err(JsValue::from_str(&format!("unknown color {}", css))),
+        }
+    }
+
+    /// Scales by any factor instead of whole numbers only
+    #[wasm_bindgen(js_name = fit)]
+    pub fn with_fit(self) -> EngineConfig {
+        self.with_scale_mode(ScaleMode::Fit)
+    }
+}
+
+impl EngineConfig {
+    pub fn with_mount(self, mount: Mount) -> Self {
+        EngineConfig { mount, ..self }
+    }
+
+    pub fn with_background(self, background: Color) -> Self {
+        EngineConfig { background, ..self }
+    }
+
+    pub fn with_scale_mode(self, scale_mode: ScaleMode) -> Self {
+        EngineConfig { scale_mode, ..self }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Device pixels per CSS pixel, if not taken from the browser
+    pub fn pixel_ratio(&self) -> Option<f64> {
+        self.pixel_ratio
+    }
+
+    pub fn background(&self) -> Color {
+        self.background
+    }
+
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.scale_mode
+    }
+
+    /// Finds or creates the canvas to draw to, and tells whether it was
+    /// created
+    pub fn create_canvas(&self) -> Result<(HtmlCanvasElement, bool), EngineError> {
+        let document = window().unwrap().document().unwrap();
+        let parent: Element = match &self.mount {
+            Mount::Canvas(canvas) => return Ok((canvas.clone(), false)),
+            Mount::Body => document.body().unwrap().into(),
+            Mount::Selector(selector) => match document.query_selector(selector)? {
+                Some(element) => match element.dyn_into::<HtmlCanvasElement>() {
+                    Ok(canvas) => return Ok((canvas, false)),
+                    Err(element) => element,
+                },
+                None => {
+                    return Err(EngineError::Js(JsValue::from_str(&format!(
+                        "no element matches {}",
+                        selector
+                    ))))
+                }
+            },
+        };
+        let canvas = document
+            .create_element("canvas")?
+            .dyn_into::<HtmlCanvasElement>()
+            .unwrap();
+        canvas.set_width(self.width);
+        canvas.set_height(self.height);
+        parent.append_child(&canvas)?;
+        Ok((canvas, true))
+    }
+}
@@ -0,0 +1,135 @@
+//! Controlling a running engine from the outside.
+//!
+//! The game loop and the page embedding the game share a [Control]. The page
+//! queues commands and flips flags, the loop picks them up at the start of
+//! the next frame and passes the game's events back.
+use crate::game::events::GameEvent;
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::window;
+
+pub enum Command {
+    /// Starts a new game
+    Restart,
+    /// Replaces the map, given as Tiled JSON, and starts a new game on it
+    LoadLevel { name: String, map: JsValue },
+}
+
+type Listener = Box<dyn FnMut(&GameEvent)>;
+
+pub struct Control {
+    paused: Cell<bool>,
+    stopped: Cell<bool>,
+    volume: Cell<f64>,
+    commands: RefCell<Vec<Command>>,
+    listener: RefCell<Option<Listener>>,
+    /// Request id of the next frame
+    frame: Cell<Option<i32>>,
+    game_loop: RefCell<Option<Closure<dyn FnMut()>>>,
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Control {
+            paused: Cell::new(false),
+            stopped: Cell::new(false),
+            volume: Cell::new(1.),
+            commands: RefCell::new(Vec::new()),
+            listener: RefCell::new(None),
+            frame: Cell::new(None),
+            game_loop: RefCell::new(None),
+        }
+    }
+}
+
+impl Control {
+    pub fn new() -> Self {
+        Control::default()
+    }
+
+    /// While paused the game is drawn, but not updated
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.set(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.get()
+    }
+
+    /// Volume from 0 to 1
+    pub fn set_volume(&self, volume: f64) {
+        self.volume.set(volume.clamp(0., 1.));
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.volume.get()
+    }
+
+    pub fn send(&self, command: Command) {
+        self.commands.borrow_mut().push(command);
+    }
+
+    pub fn take_commands(&self) -> Vec<Command> {
+        std::mem::take(&mut *self.commands.borrow_mut())
+    }
+
+    /// Called with every event the game reports
+    pub fn set_listener(&self, listener: Listener) {
+        self.listener.replace(Some(listener));
+    }
+
+    pub fn emit(&self, event: &GameEvent) {
+        if let Some(listener) = self.listener.borrow_mut().as_mut() {
+            listener(event);
+        }
+    }
+
+    /// Runs `game_loop` on every animation frame until stopped. The closure
+    /// is kept here, so it can be released when the engine stops.
+    pub fn start_loop(&self, game_loop: Closure<dyn FnMut()>) {
+        if self.is_stopped() {
+            return;
+        }
+        self.game_loop.replace(Some(game_loop));
+        self.request_frame();
+    }
+
+    /// Asks for the loop to run on the next animation frame
+    pub fn request_frame(&self) {
+        if self.is_stopped() {
+            return;
+        }
+        if let Some(game_loop) = self.game_loop.borrow().as_ref() {
+            let id = window()
+                .unwrap()
+                .request_animation_frame(game_loop.as_ref().unchecked_ref())
+                .expect("should register `requestAnimationFrame` OK");
+            self.frame.set(Some(id));
+        }
+    }
+
+    /// Cancels the next frame and releases the game loop, with the game and
+    /// everything it holds. The loop may be the one calling this, so it is
+    /// only dropped once it has returned.
+    pub fn stop(&self) {
+        self.stopped.set(true);
+        if let Some(id) = self.frame.take() {
+            window().unwrap().cancel_animation_frame(id).unwrap();
+        }
+        let game_loop = self.game_loop.borrow_mut().take();
+        // a listener stopping the engine is still running, and goes with
+        // the control instead
+        if let Ok(mut listener) = self.listener.try_borrow_mut() {
+            listener.take();
+        }
+        if game_loop.is_some() {
+            spawn_local(async move { drop(game_loop) });
+        }
+    }
+}
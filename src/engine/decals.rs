@@ -0,0 +1,201 @@
+//! Decals: marks left on the floor, like blood, scorch marks and corpses.
+//!
+//! Decals stay until there are more than the cap allows. Then the oldest
+//! quarter fades out together and is removed. Renderers stamp decals once
+//! into a surface the size of the screen, an offscreen canvas for Canvas 2D
+//! and a texture for WebGL, which is copied to the screen every frame. The
+//! surface is only redrawn when decals are removed. Fading decals are drawn
+//! on their own every frame.
+use crate::engine::atlas::{FrameId, SpriteSheet};
+use crate::engine::batch::TextureId;
+use crate::engine::math::Vec2;
+use crate::engine::renderer::{CanvasRenderer, Renderer};
+use crate::engine::transform::{Color, Transform};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decal {
+    pub frame: FrameId,
+    pub pos: Vec2,
+    /// Clockwise, in radians
+    pub rotation: f64,
+    pub tint: Option<Color>,
+    pub alpha: f64,
+}
+
+impl Decal {
+    pub fn new(frame: FrameId, pos: &Vec2) -> Self {
+        Decal {
+            frame,
+            pos: pos.clone(),
+            rotation: 0.,
+            tint: None,
+            alpha: 1.,
+        }
+    }
+
+    pub fn draw<R: Renderer + ?Sized>(&self, renderer: &R, sheet: &SpriteSheet, fade: f64) {
+        let transform = Transform {
+            rotation: self.rotation,
+            tint: self.tint,
+            alpha: self.alpha * fade,
+            ..Transform::default()
+        };
+        renderer.draw_frame_transformed(sheet, self.frame, self.pos.x, self.pos.y, &transform);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+pub struct DecalConfig {
+    /// Most decals kept
+    pub cap: usize,
+    /// Ticks the oldest decals take to fade out once there are too many
+    pub fade: u32,
+}
+
+impl Default for DecalConfig {
+    fn default() -> Self {
+        DecalConfig { cap: 256, fade: 60 }
+    }
+}
+
+pub struct DecalLayer {
+    pub config: DecalConfig,
+    decals: VecDeque<Decal>,
+    /// Decals on their way out, with the ticks left
+    fading: Vec<(Decal, u32)>,
+    /// Number of decals at the front that are already on the surface
+    stamped: Cell<usize>,
+    /// Whether the surface has decals that were removed since
+    dirty: Cell<bool>,
+    surface: RefCell<Option<CanvasRenderer>>,
+    /// Texture of the WebGL renderer the decals were stamped into
+    texture: Cell<Option<TextureId>>,
+}
+
+impl Default for DecalLayer {
+    fn default() -> Self {
+        DecalLayer::new(DecalConfig::default())
+    }
+}
+
+impl DecalLayer {
+    pub fn new(config: DecalConfig) -> Self {
+        DecalLayer {
+            config,
+            decals: VecDeque::new(),
+            fading: Vec::new(),
+            stamped: Cell::new(0),
+            dirty: Cell::new(false),
+            surface: RefCell::new(None),
+            texture: Cell::new(None),
+        }
+    }
+
+    pub fn add(&mut self, decal: Decal) {
+        self.decals.push_back(decal);
+        if self.decals.len() > self.config.cap {
+            let count = usize::max(1, self.config.cap / 4).min(self.decals.len());
+            for decal in self.decals.drain(..count) {
+                if self.config.fade > 0 {
+                    self.fading.push((decal, self.config.fade));
+                }
+            }
+            self.dirty.set(true);
+        }
+    }
+
+    /// Fades the decals on their way out
+    pub fn update(&mut self) {
+        for (_, remaining) in self.fading.iter_mut() {
+            *remaining -= 1;
+        }
+        self.fading.retain(|(_, remaining)| *remaining > 0);
+    }
+
+    pub fn clear(&mut self) {
+        self.decals.clear();
+        self.fading.clear();
+        self.dirty.set(true);
+    }
+
+    /// Replaces the decals, e.g. with the ones of a snapshot
+    pub fn restore(&mut self, decals: Vec<Decal>) {
+        self.clear();
+        self.decals = decals.into();
+    }
+
+    pub fn len(&self) -> usize {
+        self.decals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decals.is_empty()
+    }
+
+    /// Decals that stay, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Decal> {
+        self.decals.iter()
+    }
+
+    /// Decals on their way out, with how much of them is left from 1 to 0
+    pub fn fading(&self) -> impl Iterator<Item = (&Decal, f64)> {
+        let fade = self.config.fade.max(1) as f64;
+        self.fading
+            .iter()
+            .map(move |(decal, remaining)| (decal, *remaining as f64 / fade))
+    }
+
+    /// Returns whether the surface has to be cleared and which decals to
+    /// stamp on it, and counts them as stamped
+    pub fn take_unstamped(&self) -> (bool, Range<usize>) {
+        let clear = self.dirty.replace(false);
+        let start = if clear { 0 } else { self.stamped.get() };
+        self.stamped.set(self.decals.len());
+        (clear, start.min(self.decals.len())..self.decals.len())
+    }
+
+    /// Like [DecalLayer::take_unstamped], for a surface texture kept by the
+    /// renderer. The first time, the texture is cleared and every decal is
+    /// stamped, since it may hold the decals of another layer.
+    pub(crate) fn take_unstamped_into(&self, texture: TextureId) -> (bool, Range<usize>) {
+        if self.texture.replace(Some(texture)) != Some(texture) {
+            self.dirty.set(true);
+        }
+        self.take_unstamped()
+    }
+
+    /// Draws every decal, for renderers without a surface
+    pub fn draw<R: Renderer + ?Sized>(&self, renderer: &R, sheet: &SpriteSheet) {
+        for decal in self.decals.iter() {
+            decal.draw(renderer, sheet, 1.);
+        }
+        for (decal, fade) in self.fading() {
+            decal.draw(renderer, sheet, fade);
+        }
+    }
+
+    /// Stamps the new decals on the surface, creating it the first time,
+    /// and draws the surface and the fading decals
+    pub(crate) fn draw_with_surface(&self, renderer: &CanvasRenderer, sheet: &SpriteSheet) {
+        let mut surface = self.surface.borrow_mut();
+        let surface = surface.get_or_insert_with(|| {
+            self.dirty.set(true);
+            CanvasRenderer::offscreen(renderer.width(), renderer.height())
+        });
+        let (clear, unstamped) = self.take_unstamped();
+        if clear {
+            surface.clear();
+        }
+        for index in unstamped {
+            self.decals[index].draw(surface, sheet, 1.);
+        }
+        renderer.draw_canvas(surface.canvas(), 0., 0.);
+        for (decal, fade) in self.fading() {
+            decal.draw(renderer, sheet, fade);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use wasm_bindgen::prelude::*;
 pub enum EngineError {
     IO(std::io::Error),
     Js(JsValue),
+    Json(serde_json::Error),
 }
 
 impl From<JsValue> for EngineError {
@@ -12,11 +13,18 @@ impl From<JsValue> for EngineError {
     }
 }
 
+impl From<serde_json::Error> for EngineError {
+    fn from(e: serde_json::Error) -> Self {
+        EngineError::Json(e)
+    }
+}
+
 impl From<EngineError> for JsValue {
     fn from(e: EngineError) -> Self {
         match e {
             EngineError::Js(e) => e,
             EngineError::IO(e) => JsValue::from_str(&e.to_string()),
+            EngineError::Json(e) => JsValue::from_str(&e.to_string()),
         }
     }
 }
@@ -0,0 +1,68 @@
+//! Time spent on each frame.
+//!
+//! Keeps the durations of the last frames, so the cost of a change can be
+//! compared before and after. Press F3 in the game to log the numbers to the
+//! console.
+
+/// Frames the statistics are taken over
+pub const FRAME_SAMPLES: usize = 120;
+
+pub struct FrameTimer {
+    samples: Vec<f64>,
+    next: usize,
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        FrameTimer::new()
+    }
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        FrameTimer {
+            samples: Vec::with_capacity(FRAME_SAMPLES),
+            next: 0,
+        }
+    }
+
+    /// Records how long a frame took, in milliseconds
+    pub fn record(&mut self, ms: f64) {
+        if self.samples.len() < FRAME_SAMPLES {
+            self.samples.push(ms);
+        } else {
+            self.samples[self.next] = ms;
+        }
+        self.next = (self.next + 1) % FRAME_SAMPLES;
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            None
+        } else {
+            Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+        }
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.samples
+            .iter()
+            .cloned()
+            .fold(None, |max, ms| match max {
+                Some(max) if max >= ms => Some(max),
+                _ => Some(ms),
+            })
+    }
+
+    pub fn summary(&self) -> String {
+        match (self.average(), self.max()) {
+            (Some(average), Some(max)) => format!(
+                "update + render over {} frames: {:.2} ms average, {:.2} ms max",
+                self.samples.len(),
+                average,
+                max
+            ),
+            _ => String::from("no frames recorded"),
+        }
+    }
+}
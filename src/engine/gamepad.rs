@@ -1,9 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+// buttons of the standard gamepad mapping
+// https://w3c.github.io/gamepad/#remapping
+pub const SOUTH_BUTTON: u32 = 0;
+pub const EAST_BUTTON: u32 = 1;
+pub const RIGHT_SHOULDER: u32 = 5;
+pub const RIGHT_TRIGGER: u32 = 7;
+pub const START_BUTTON: u32 = 9;
+pub const DPAD_UP: u32 = 12;
+pub const DPAD_DOWN: u32 = 13;
+pub const DPAD_LEFT: u32 = 14;
+pub const DPAD_RIGHT: u32 = 15;
+
 pub struct TwinStick {
     pub move_x_axis: f64,
     pub move_y_axis: f64,
     pub aim_x_axis: f64,
     pub aim_y_axis: f64,
     pub shoot: bool,
+    pub next_weapon: bool,
+
+    // menu buttons, which can't be remapped
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    pub start: bool,
+
+    /// Lowest button held down, for remapping
+    pub button: Option<u32>,
+}
+
+impl Default for TwinStick {
+    fn default() -> Self {
+        TwinStick::new()
+    }
 }
 
 impl TwinStick {
@@ -14,6 +47,45 @@ impl TwinStick {
             aim_x_axis: 0.,
             aim_y_axis: 0.,
             shoot: false,
+            next_weapon: false,
+            up: false,
+            down: false,
+            left: false,
+            right: false,
+            confirm: false,
+            back: false,
+            start: false,
+            button: None,
+        }
+    }
+}
+
+/// Which gamepad buttons the game's actions are on, chosen by the players
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bindings {
+    pub shoot: u32,
+    pub next_weapon: u32,
+    /// Sticks tilted less than this count as centered
+    pub dead_zone: f64,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            shoot: RIGHT_TRIGGER,
+            next_weapon: RIGHT_SHOULDER,
+            dead_zone: 0.15,
         }
     }
 }
+
+/// Centers a stick tilted less than `dead_zone`, and rescales the rest so
+/// the stick still goes from no tilt to full tilt
+pub fn apply_dead_zone(x: f64, y: f64, dead_zone: f64) -> (f64, f64) {
+    let length = (x * x + y * y).sqrt();
+    if length <= dead_zone || dead_zone >= 1. {
+        return (0., 0.);
+    }
+    let scale = f64::min(1., (length - dead_zone) / (1. - dead_zone)) / length;
+    (x * scale, y * scale)
+}
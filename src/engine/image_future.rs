@@ -1,5 +1,5 @@
-use futures::task::{Context, Poll};
-use std::cell::Cell;
+use futures::task::{Context, Poll, Waker};
+use std::cell::{Cell, RefCell};
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
@@ -11,7 +11,7 @@ use web_sys::HtmlImageElement;
 /// that will resolve when the image has fully loaded.
 ///
 /// Example:
-/// ```rust
+/// ```ignore
 /// let image = ImageFuture::new("assets/sprite_sheet.png").await;
 /// ```
 ///
@@ -24,56 +24,86 @@ use web_sys::HtmlImageElement;
 ///  img.src = src;
 /// })
 /// ```
+///
+/// The `onload` and `onerror` handlers are set once and removed again when
+/// the future resolves or is dropped.
 pub struct ImageFuture {
-    image: Option<HtmlImageElement>,
+    image: HtmlImageElement,
+    done: bool,
     load_failed: Rc<Cell<bool>>,
+    /// Waker of the latest poll
+    waker: Rc<RefCell<Option<Waker>>>,
+    _on_load: Closure<dyn FnMut()>,
+    _on_error: Closure<dyn FnMut()>,
 }
 
 impl ImageFuture {
     pub fn new(path: &str) -> Self {
         let image = HtmlImageElement::new().unwrap();
+        let load_failed = Rc::new(Cell::new(false));
+        let waker: Rc<RefCell<Option<Waker>>> = Rc::new(RefCell::new(None));
+
+        let load_waker = waker.clone();
+        let on_load = Closure::wrap(Box::new(move || {
+            if let Some(waker) = load_waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }) as Box<dyn FnMut()>);
+        image.set_onload(Some(on_load.as_ref().unchecked_ref()));
+
+        let error_waker = waker.clone();
+        let failed_flag = load_failed.clone();
+        let on_error = Closure::wrap(Box::new(move || {
+            failed_flag.set(true);
+            if let Some(waker) = error_waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }) as Box<dyn FnMut()>);
+        image.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
         image.set_src(path);
         ImageFuture {
-            image: Some(image),
-            load_failed: Rc::new(Cell::new(false)),
+            image,
+            done: false,
+            load_failed,
+            waker,
+            _on_load: on_load,
+            _on_error: on_error,
         }
     }
+
+    fn detach(&self) {
+        self.image.set_onload(None);
+        self.image.set_onerror(None);
+    }
 }
 
 impl Future for ImageFuture {
     type Output = Result<HtmlImageElement, ()>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match &self.image {
-            Some(image) if image.complete() => {
-                let failed = self.load_failed.get();
-                if failed {
-                    Poll::Ready(Err(()))
-                } else {
-                    let image = self.image.take().unwrap();
-                    Poll::Ready(Ok(image))
-                }
+        if self.done {
+            return Poll::Ready(Err(()));
+        }
+        if self.image.complete() || self.load_failed.get() {
+            self.done = true;
+            self.detach();
+            if self.load_failed.get() {
+                Poll::Ready(Err(()))
+            } else {
+                Poll::Ready(Ok(self.image.clone()))
             }
-            Some(image) => {
-                let waker = cx.waker().clone();
-                let on_load_closure = Closure::wrap(Box::new(move || {
-                    waker.wake_by_ref();
-                }) as Box<dyn FnMut()>);
-                image.set_onload(Some(on_load_closure.as_ref().unchecked_ref()));
-                on_load_closure.forget();
-
-                let waker = cx.waker().clone();
-                let failed_flag = self.load_failed.clone();
-                let on_error_closure = Closure::wrap(Box::new(move || {
-                    failed_flag.set(true);
-                    waker.wake_by_ref();
-                }) as Box<dyn FnMut()>);
-                image.set_onerror(Some(on_error_closure.as_ref().unchecked_ref()));
-                on_error_closure.forget();
+        } else {
+            self.waker.replace(Some(cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
 
-                Poll::Pending
-            }
-            _ => Poll::Ready(Err(())),
+impl Drop for ImageFuture {
+    fn drop(&mut self) {
+        if !self.done {
+            self.detach();
         }
     }
 }
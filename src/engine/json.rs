@@ -1,4 +1,5 @@
 use crate::engine::error::EngineError;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -30,3 +31,10 @@ pub async fn load_json(url: &str) -> Result<JsValue, EngineError> {
     let json = JsFuture::from(resp.json()?).await?;
     Ok(json)
 }
+
+/// Reads a value loaded with [load_json] into a Rust type, by way of its
+/// JSON text
+pub fn from_js<T: DeserializeOwned>(json: &JsValue) -> Result<T, EngineError> {
+    let text = String::from(js_sys::JSON::stringify(json)?);
+    Ok(serde_json::from_str(&text)?)
+}
@@ -0,0 +1,45 @@
+use crate::engine::listener::EventListener;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use web_sys::{window, KeyboardEvent};
+
+/// Keeps track of which keys are held down. Keys are identified by their
+/// [code](https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/code),
+/// e.g. `"KeyA"` or `"F2"`. Stops listening when dropped.
+pub struct Keyboard {
+    pressed: Rc<RefCell<HashSet<String>>>,
+    _listeners: [EventListener; 2],
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Keyboard::new()
+    }
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        let pressed = Rc::new(RefCell::new(HashSet::new()));
+        let window = window().unwrap();
+
+        let keys = pressed.clone();
+        let on_key_down = EventListener::new(&window, "keydown", move |event: KeyboardEvent| {
+            keys.borrow_mut().insert(event.code());
+        });
+
+        let keys = pressed.clone();
+        let on_key_up = EventListener::new(&window, "keyup", move |event: KeyboardEvent| {
+            keys.borrow_mut().remove(&event.code());
+        });
+
+        Keyboard {
+            pressed,
+            _listeners: [on_key_down, on_key_up],
+        }
+    }
+
+    pub fn is_pressed(&self, code: &str) -> bool {
+        self.pressed.borrow().contains(code)
+    }
+}
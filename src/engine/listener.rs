@@ -0,0 +1,74 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, Event, EventTarget, ResizeObserver};
+
+/// An event listener that is removed again when dropped, together with the
+/// closure it calls. Hold on to it for as long as the events are wanted.
+pub struct EventListener {
+    target: EventTarget,
+    name: String,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+impl EventListener {
+    /// Calls `handler` with the events called `name` on `target`, cast to
+    /// the type of event expected
+    pub fn new<E, F>(target: &EventTarget, name: &str, mut handler: F) -> Self
+    where
+        E: JsCast,
+        F: FnMut(E) + 'static,
+    {
+        let closure =
+            Closure::wrap(
+                Box::new(move |event: Event| handler(event.unchecked_into::<E>()))
+                    as Box<dyn FnMut(Event)>,
+            );
+        target
+            .add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+            .unwrap();
+        EventListener {
+            target: target.clone(),
+            name: String::from(name),
+            closure,
+        }
+    }
+}
+
+impl Drop for EventListener {
+    fn drop(&mut self) {
+        self.target
+            .remove_event_listener_with_callback(&self.name, self.closure.as_ref().unchecked_ref())
+            .unwrap();
+    }
+}
+
+/// Watches the size of an element on the page, which can change without the
+/// window being resized, e.g. when the layout around it changes. Stops
+/// watching when dropped.
+pub struct ResizeListener {
+    observer: ResizeObserver,
+    _closure: Closure<dyn FnMut()>,
+}
+
+impl ResizeListener {
+    /// Calls `handler` whenever the size of `target` changes, and once when
+    /// it starts watching
+    pub fn new<F>(target: &Element, handler: F) -> Self
+    where
+        F: FnMut() + 'static,
+    {
+        let closure = Closure::wrap(Box::new(handler) as Box<dyn FnMut()>);
+        let observer = ResizeObserver::new(closure.as_ref().unchecked_ref()).unwrap();
+        observer.observe(target);
+        ResizeListener {
+            observer,
+            _closure: closure,
+        }
+    }
+}
+
+impl Drop for ResizeListener {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
@@ -28,6 +30,13 @@ impl Vec2 {
         self.x *= f;
         self.y *= f;
     }
+
+    pub fn rotate(&mut self, angle: f64) {
+        let (sin, cos) = angle.sin_cos();
+        let x = self.x * cos - self.y * sin;
+        self.y = self.x * sin + self.y * cos;
+        self.x = x;
+    }
 }
 
 pub fn vec2_distance(v1: &Vec2, v2: &Vec2) -> f64 {
@@ -1,27 +1,63 @@
+pub mod animation;
+pub mod atlas;
+pub mod batch;
+pub mod bitmap_font;
+pub mod config;
+pub mod control;
+pub mod decals;
 pub mod error;
+pub mod frame_timer;
 pub mod gamepad;
 pub mod image_future;
 pub mod json;
+pub mod keyboard;
+pub mod listener;
 pub mod math;
+pub mod particles;
+pub mod pointer;
 pub mod preloader;
+pub mod random;
 pub mod renderer;
+pub mod storage;
+pub mod tile_cache;
 pub mod tiled;
+pub mod transform;
+pub mod ui;
+pub mod viewport;
+pub mod webgl_renderer;
 
+use crate::engine::atlas::{Atlas, SpriteSheet};
+use crate::engine::bitmap_font::{BitmapFont, Font};
+use crate::engine::config::EngineConfig;
+use crate::engine::control::{Command, Control};
+use crate::engine::error::EngineError;
+use crate::engine::frame_timer::FrameTimer;
 use crate::engine::image_future::ImageFuture;
 use crate::engine::json::load_jsons;
 use crate::engine::preloader::{Preloader, Resources};
-use crate::engine::renderer::CanvasRenderer;
+use crate::engine::renderer::{CanvasRenderer, Renderer};
+use crate::engine::viewport::Viewport;
+use crate::engine::webgl_renderer::WebGlRenderer;
 use crate::game::MyGame;
 
-use crate::engine::gamepad::TwinStick;
+use crate::engine::gamepad::{
+    apply_dead_zone, Bindings, TwinStick, DPAD_DOWN, DPAD_LEFT, DPAD_RIGHT, DPAD_UP, EAST_BUTTON,
+    SOUTH_BUTTON, START_BUTTON,
+};
+use crate::engine::keyboard::Keyboard;
+use crate::engine::listener::{EventListener, ResizeListener};
+use crate::engine::pointer::Pointer;
 use futures::future::join_all;
-use std::cell::RefCell;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
-use web_sys::{window, GamepadButton, HtmlImageElement};
+use web_sys::{
+    console, window, Event, Gamepad, GamepadButton, HtmlCanvasElement, HtmlImageElement,
+    KeyboardEvent,
+};
 
 // mappings for PS4 Dual Shock in Firefox on Windows
 // need to be changed depending on controller, browser and operating system
@@ -29,105 +65,315 @@ const LEFT_STICK_X_AXIS: u32 = 0;
 const LEFT_STICK_Y_AXIS: u32 = 1;
 const RIGHT_STICK_X_AXIS: u32 = 2;
 const RIGHT_STICK_Y_AXIS: u32 = 3;
-const RIGHT_TRIGGER_0: u32 = 7;
 
+/// Logs the frame times to the console
+const STATS_KEY: &str = "F3";
+
+/// Toggles fullscreen, as does double clicking the game
+const FULLSCREEN_KEY: &str = "F9";
+
+/// A running game
 pub struct Engine {
-    renderer: Rc<CanvasRenderer>,
-    preloader: Preloader,
-    tick: u64,
+    control: Rc<Control>,
+    /// The canvas, if the engine added it to the page
+    created_canvas: Option<HtmlCanvasElement>,
 }
 
 impl Engine {
-    pub fn launch(mut game: MyGame) {
+    /// Loads the game's assets and runs it until stopped. Fails if the
+    /// canvas given by the config can't be found.
+    pub fn launch(mut game: MyGame, config: EngineConfig) -> Result<Engine, EngineError> {
+        let (mut runner, created) = Runner::new(config)?;
+        let engine = Engine {
+            control: Rc::new(Control::new()),
+            created_canvas: Some(runner.canvas.clone()).filter(|_| created),
+        };
+        let control = engine.control.clone();
         spawn_local(async move {
-            let mut engine = Engine::new();
-            game.preload(&mut engine.preloader);
+            random::seed((js_sys::Math::random() * u64::MAX as f64) as u64);
+            game.preload(&mut runner.preloader);
 
-            let images = load_images(&engine.preloader.image_paths).await;
-            let jsons = load_jsons(&engine.preloader.json_paths).await;
-            game.init(Resources { images, jsons });
-            engine.game_loop(game);
+            let atlases = load_jsons(&runner.preloader.atlas_paths).await;
+            let atlases: Vec<(String, Atlas)> = atlases
+                .into_iter()
+                .map(|(path, json)| (path, Atlas::new_from_json(&json)))
+                .collect();
+            for (path, atlas) in atlases.iter() {
+                runner.preloader.load_image(atlas.image_path(path));
+            }
+            let fonts = load_jsons(&runner.preloader.font_paths).await;
+            let fonts: Vec<(String, BitmapFont)> = fonts
+                .into_iter()
+                .filter_map(|(path, json)| match BitmapFont::new_from_json(&json) {
+                    Ok(font) => Some((path, font)),
+                    Err(e) => {
+                        console::error_2(&format!("Could not read {}", path).into(), &e.into());
+                        None
+                    }
+                })
+                .collect();
+            for (path, font) in fonts.iter() {
+                runner.preloader.load_image(font.image_path(path));
+            }
+
+            let mut images = load_images(&runner.preloader.image_paths).await;
+            let jsons = load_jsons(&runner.preloader.json_paths).await;
+            if control.is_stopped() {
+                return;
+            }
+            let fonts = fonts
+                .into_iter()
+                .filter_map(|(path, font)| {
+                    let image = images.get(&font.image_path(&path))?.clone();
+                    Some((path, Font { font, image }))
+                })
+                .collect();
+            let mut sprite_sheets = HashMap::new();
+            for (path, atlas) in atlases {
+                if let Some(image) = images.remove(&atlas.image_path(&path)) {
+                    sprite_sheets.insert(path, SpriteSheet { atlas, image });
+                }
+            }
+            game.init(Resources {
+                images,
+                jsons,
+                sprite_sheets,
+                fonts,
+            });
+            runner.game_loop(game, control);
         });
+        Ok(engine)
     }
 
-    pub fn new() -> Self {
-        Engine {
-            renderer: Rc::new(CanvasRenderer::new(960, 540)),
+    pub fn control(&self) -> &Rc<Control> {
+        &self.control
+    }
+
+    /// Ends the game loop and removes the canvas, if the engine added it.
+    /// The game, the renderer and the event listeners are released once the
+    /// current frame or asset load is done.
+    pub fn stop(&self) {
+        self.control.stop();
+        if let Some(canvas) = &self.created_canvas {
+            canvas.remove();
+        }
+    }
+}
+
+/// What the game loop runs with
+struct Runner {
+    config: EngineConfig,
+    canvas: HtmlCanvasElement,
+    renderer: Rc<dyn Renderer>,
+    preloader: Preloader,
+    tick: u64,
+}
+
+impl Runner {
+    /// Also tells whether the canvas was created
+    fn new(config: EngineConfig) -> Result<(Self, bool), EngineError> {
+        let (canvas, created) = config.create_canvas()?;
+        let width = config.width() as f64;
+        let height = config.height() as f64;
+        let renderer: Rc<dyn Renderer> =
+            match WebGlRenderer::new(&canvas, width, height, config.background()) {
+                Some(renderer) => Rc::new(renderer),
+                None => {
+                    console::log_1(&"WebGL 2 is not available, drawing with Canvas 2D".into());
+                    if canvas.get_context("2d")?.is_none() {
+                        return Err(JsValue::from_str("the canvas has no 2D context").into());
+                    }
+                    Rc::new(CanvasRenderer::new(
+                        canvas.clone(),
+                        width,
+                        height,
+                        config.background(),
+                    ))
+                }
+            };
+        let runner = Runner {
+            config,
+            canvas,
+            renderer,
             preloader: Preloader::new(),
             tick: 0,
-        }
+        };
+        Ok((runner, created))
     }
 
-    fn game_loop(&self, mut game: MyGame) {
-        let f = Rc::new(RefCell::new(None));
-        let g = f.clone();
-        let renderer = self.renderer.clone();
+    /// Runs the game on every animation frame. The loop owns the game, the
+    /// renderer and all event listeners, so stopping it releases them all.
+    fn game_loop(self, mut game: MyGame, control: Rc<Control>) {
+        let resized = Rc::new(Cell::new(true));
+        let listeners = self.listen(resized.clone());
+        let Runner {
+            config,
+            canvas,
+            renderer,
+            tick,
+            ..
+        } = self;
         let mut context = GameContext {
-            window_width: self.renderer.width,
-            window_height: self.renderer.height,
+            window_width: renderer.width(),
+            window_height: renderer.height(),
+            viewport: fit_canvas(&canvas, &config),
+            pointer: Pointer::new(&canvas),
             gamepad_1: TwinStick::new(),
             gamepad_2: TwinStick::new(),
-            tick: self.tick,
+            keyboard: Keyboard::new(),
+            volume: control.volume(),
+            tick,
         };
         let window = window().unwrap();
-        *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+        let performance = window.performance().unwrap();
+        let mut frame_timer = FrameTimer::new();
+        let mut stats_held = false;
+        let looping = control.clone();
+        let game_loop = Closure::wrap(Box::new(move || {
+            let _listeners = &listeners;
+            let start = performance.now();
             if let Ok(gamepads) = window.navigator().get_gamepads() {
-                if gamepads.length() > 0 {
-                    if let Ok(gamepad) = gamepads.get(0).dyn_into::<web_sys::Gamepad>() {
-                        context.gamepad_1.move_x_axis =
-                            gamepad.axes().get(LEFT_STICK_X_AXIS).as_f64().unwrap();
-                        context.gamepad_1.move_y_axis =
-                            gamepad.axes().get(LEFT_STICK_Y_AXIS).as_f64().unwrap();
-
-                        context.gamepad_1.aim_x_axis =
-                            gamepad.axes().get(RIGHT_STICK_X_AXIS).as_f64().unwrap();
-                        context.gamepad_1.aim_y_axis =
-                            gamepad.axes().get(RIGHT_STICK_Y_AXIS).as_f64().unwrap();
-
-                        context.gamepad_1.shoot = gamepad
-                            .buttons()
-                            .get(RIGHT_TRIGGER_0)
-                            .dyn_into::<GamepadButton>()
-                            .unwrap()
-                            .pressed();
+                let bindings = game.bindings();
+                let mut sticks = [&mut context.gamepad_1, &mut context.gamepad_2];
+                for (index, stick) in sticks.iter_mut().enumerate() {
+                    if let Ok(gamepad) = gamepads.get(index as u32).dyn_into::<Gamepad>() {
+                        read_gamepad(&gamepad, &bindings, stick);
                     }
                 }
-                if gamepads.length() > 1 {
-                    if let Ok(gamepad) = gamepads.get(1).dyn_into::<web_sys::Gamepad>() {
-                        context.gamepad_2.move_x_axis =
-                            gamepad.axes().get(LEFT_STICK_X_AXIS).as_f64().unwrap();
-                        context.gamepad_2.move_y_axis =
-                            gamepad.axes().get(LEFT_STICK_Y_AXIS).as_f64().unwrap();
-
-                        context.gamepad_2.aim_x_axis =
-                            gamepad.axes().get(RIGHT_STICK_X_AXIS).as_f64().unwrap();
-                        context.gamepad_2.aim_y_axis =
-                            gamepad.axes().get(RIGHT_STICK_Y_AXIS).as_f64().unwrap();
-
-                        context.gamepad_2.shoot = gamepad
-                            .buttons()
-                            .get(RIGHT_TRIGGER_0)
-                            .dyn_into::<GamepadButton>()
-                            .unwrap()
-                            .pressed();
-                    }
+            }
+            if resized.replace(false) {
+                context.viewport = fit_canvas(&canvas, &config);
+                renderer.resize(&context.viewport);
+                game.on_resize(&context.viewport);
+            }
+            for command in control.take_commands() {
+                match command {
+                    Command::Restart => game.restart(),
+                    Command::LoadLevel { name, map } => game.load_level(&name, &map),
                 }
             }
-            context.tick += 1;
-            game.update(&context);
+            context.volume = control.volume();
+            if !control.is_paused() {
+                context.tick += 1;
+                game.update(&context);
+            }
             game.render(renderer.as_ref(), &context);
-            request_animation_frame(f.borrow().as_ref().unwrap());
-        }) as Box<dyn FnMut() + 'static>));
+            renderer.present();
+            frame_timer.record(performance.now() - start);
+
+            let stats = context.keyboard.is_pressed(STATS_KEY);
+            if stats && !stats_held {
+                console::log_1(&frame_timer.summary().into());
+            }
+            stats_held = stats;
 
-        request_animation_frame(g.borrow().as_ref().unwrap());
+            for event in game.take_events() {
+                control.emit(&event);
+            }
+            control.request_frame();
+        }) as Box<dyn FnMut() + 'static>);
+
+        looping.start_loop(game_loop);
+    }
+
+    /// Listens for resizing, setting `resized`, and for toggling fullscreen.
+    /// The canvas is watched as well as the window, as the page around an
+    /// embedded canvas can resize it. Fullscreen can only be requested while
+    /// handling an input event, so it is toggled from the listeners instead
+    /// of the game loop.
+    fn listen(&self, resized: Rc<Cell<bool>>) -> (Vec<EventListener>, ResizeListener) {
+        let window = window().unwrap();
+        let canvas_resized = resized.clone();
+        let on_canvas_resize = ResizeListener::new(&self.canvas, move || canvas_resized.set(true));
+        let on_resize = EventListener::new(&window, "resize", move |_: Event| resized.set(true));
+
+        let canvas = self.canvas.clone();
+        let on_key_down = EventListener::new(&window, "keydown", move |event: KeyboardEvent| {
+            if event.code() == FULLSCREEN_KEY && !event.repeat() {
+                toggle_fullscreen(&canvas);
+            }
+        });
+
+        let canvas = self.canvas.clone();
+        let on_double_click = EventListener::new(&self.canvas, "dblclick", move |_: Event| {
+            toggle_fullscreen(&canvas)
+        });
+
+        (
+            vec![on_resize, on_key_down, on_double_click],
+            on_canvas_resize,
+        )
+    }
+}
+
+/// Reads the sticks and buttons of a gamepad into a twin stick
+fn read_gamepad(gamepad: &Gamepad, bindings: &Bindings, stick: &mut TwinStick) {
+    let axes = gamepad.axes();
+    let axis = |index| axes.get(index).as_f64().unwrap_or(0.);
+    let (x, y) = apply_dead_zone(
+        axis(LEFT_STICK_X_AXIS),
+        axis(LEFT_STICK_Y_AXIS),
+        bindings.dead_zone,
+    );
+    stick.move_x_axis = x;
+    stick.move_y_axis = y;
+    let (x, y) = apply_dead_zone(
+        axis(RIGHT_STICK_X_AXIS),
+        axis(RIGHT_STICK_Y_AXIS),
+        bindings.dead_zone,
+    );
+    stick.aim_x_axis = x;
+    stick.aim_y_axis = y;
+
+    let buttons = gamepad.buttons();
+    let pressed = |index| {
+        buttons
+            .get(index)
+            .dyn_into::<GamepadButton>()
+            .map(|button| button.pressed())
+            .unwrap_or(false)
+    };
+    stick.shoot = pressed(bindings.shoot);
+    stick.next_weapon = pressed(bindings.next_weapon);
+    stick.up = pressed(DPAD_UP);
+    stick.down = pressed(DPAD_DOWN);
+    stick.left = pressed(DPAD_LEFT);
+    stick.right = pressed(DPAD_RIGHT);
+    stick.confirm = pressed(SOUTH_BUTTON);
+    stick.back = pressed(EAST_BUTTON);
+    stick.start = pressed(START_BUTTON);
+    stick.button = (0..buttons.length()).find(|index| pressed(*index));
+}
+
+fn toggle_fullscreen(canvas: &HtmlCanvasElement) {
+    let document = window().unwrap().document().unwrap();
+    if document.fullscreen_element().is_some() {
+        document.exit_fullscreen();
+    } else if let Err(err) = canvas.request_fullscreen() {
+        console::log_2(&"Fullscreen is not available".into(), &err);
     }
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut()>) {
-    window()
-        .unwrap()
-        .request_animation_frame(f.as_ref().unchecked_ref())
-        .expect("should register `requestAnimationFrame` OK");
+/// Sizes the canvas to cover its space on the page in device pixels and
+/// places the virtual resolution on it
+fn fit_canvas(canvas: &HtmlCanvasElement, config: &EngineConfig) -> Viewport {
+    let pixel_ratio = config
+        .pixel_ratio()
+        .unwrap_or_else(|| window().unwrap().device_pixel_ratio());
+    let width = (canvas.client_width() as f64 * pixel_ratio).round();
+    let height = (canvas.client_height() as f64 * pixel_ratio).round();
+    if width > 0. && height > 0. {
+        canvas.set_width(width as u32);
+        canvas.set_height(height as u32);
+    }
+    Viewport::new(
+        config.width() as f64,
+        config.height() as f64,
+        canvas.width() as f64,
+        canvas.height() as f64,
+        pixel_ratio,
+        config.scale_mode(),
+    )
 }
 
 pub struct GameContext {
@@ -136,9 +382,15 @@ pub struct GameContext {
     pub window_height: f64,
     pub gamepad_1: TwinStick,
     pub gamepad_2: TwinStick,
+    pub keyboard: Keyboard,
+    /// Volume from 0 to 1 set by the page embedding the game
+    pub volume: f64,
+    /// Where the game is placed on the canvas
+    pub viewport: Viewport,
+    pub pointer: Pointer,
 }
 
-async fn load_images(image_paths: &Vec<String>) -> HashMap<String, HtmlImageElement> {
+async fn load_images(image_paths: &[String]) -> HashMap<String, HtmlImageElement> {
     let image_futures: Vec<ImageFuture> = image_paths
         .iter()
         .map(|path| ImageFuture::new(path))
@@ -148,7 +400,7 @@ async fn load_images(image_paths: &Vec<String>) -> HashMap<String, HtmlImageElem
 
     let images: HashMap<String, HtmlImageElement> = image_paths
         .iter()
-        .zip(future.into_iter())
+        .zip(future)
         .filter(|(_key, value)| (*value).is_ok())
         .map(|(key, value)| (key.clone(), value.unwrap()))
         .collect();
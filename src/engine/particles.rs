@@ -0,0 +1,403 @@
+//! Particles.
+//!
+//! Emitters are defined in data: how many particles they emit at once and
+//! per tick, the cone and speed they fly out with, how gravity and drag slow
+//! them down, and how their sprite, color and opacity change over their
+//! life. [Emitters] holds the definitions, [Particles] the particles in
+//! flight, which can be stored in snapshots.
+//!
+//! Particles can land when they die, leaving a decal on the floor, see
+//! [DecalLayer].
+//!
+//! Particles are pooled: the pool is allocated once for the most particles
+//! allowed at the same time, and particles emitted while it is full are
+//! dropped.
+use crate::engine::animation::Clips;
+use crate::engine::atlas::{FrameId, SpriteSheet};
+use crate::engine::decals::{Decal, DecalConfig, DecalLayer};
+use crate::engine::json::from_js;
+use crate::engine::math::Vec2;
+use crate::engine::random::random;
+use crate::engine::renderer::Renderer;
+use crate::engine::transform::{Color, Transform};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+/// How the clip of a particle is played over its life
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Playback {
+    /// Plays at its own speed so it ends when the particle dies
+    #[default]
+    Ending,
+    /// Stretched over the life of the particle
+    OverLife,
+    /// Plays at its own speed from the start, looping
+    Loop,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Emitter {
+    /// Particles emitted at once
+    #[serde(default)]
+    pub burst: u32,
+    /// Particles emitted per tick after the burst, for `duration` ticks
+    #[serde(default)]
+    pub rate: f64,
+    #[serde(default)]
+    pub duration: u32,
+    /// Shortest and longest life of a particle, in ticks
+    pub lifetime: (u32, u32),
+    /// Slowest and fastest start, in pixels per tick
+    pub speed: (f64, f64),
+    /// Middle of the cone particles fly out in, in degrees clockwise from
+    /// the right
+    #[serde(default)]
+    pub direction: f64,
+    /// Width of the cone in degrees
+    #[serde(default = "full_circle")]
+    pub spread: f64,
+    /// Acceleration in pixels per tick per tick
+    #[serde(default)]
+    pub gravity: (f64, f64),
+    /// Part of its speed a particle loses every tick
+    #[serde(default)]
+    pub drag: f64,
+    /// Clip of the particle. Particles without one are squares of `size`
+    /// pixels.
+    #[serde(default)]
+    pub clip: Option<String>,
+    #[serde(default)]
+    pub playback: Playback,
+    #[serde(default = "default_size")]
+    pub size: f64,
+    /// Colors over the life of a particle, evenly spread out. Squares are
+    /// filled with them, sprites tinted.
+    #[serde(default)]
+    pub colors: Vec<Color>,
+    /// Opacity over the life of a particle, evenly spread out
+    #[serde(default)]
+    pub alpha: Vec<f64>,
+    /// What a particle leaves on the floor when it dies
+    #[serde(default)]
+    pub decal: Option<Stamp>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Stamp {
+    /// Frames of the decal, one picked at random
+    pub frames: Vec<FrameId>,
+    /// Chance that a particle leaves a decal
+    #[serde(default = "certain")]
+    pub chance: f64,
+    #[serde(default)]
+    pub tint: Option<Color>,
+    #[serde(default = "certain")]
+    pub alpha: f64,
+    /// Turns the decal by a random angle
+    #[serde(default)]
+    pub rotate: bool,
+}
+
+fn certain() -> f64 {
+    1.
+}
+
+fn full_circle() -> f64 {
+    360.
+}
+
+fn default_size() -> f64 {
+    2.
+}
+
+#[derive(Deserialize)]
+struct EmittersJson {
+    max_particles: usize,
+    #[serde(default)]
+    decals: DecalConfig,
+    emitters: HashMap<String, Emitter>,
+}
+
+/// Emitters by name
+#[derive(Debug, Default)]
+pub struct Emitters {
+    /// Most particles alive at the same time
+    pub max_particles: usize,
+    /// How many decals the particles can leave
+    pub decals: DecalConfig,
+    emitters: Vec<Emitter>,
+    indices: HashMap<String, usize>,
+}
+
+impl Emitters {
+    pub fn new_from_json(json: &JsValue) -> Self {
+        Emitters::from_json(from_js(json).unwrap())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Emitter> {
+        self.index(name).map(|index| &self.emitters[index])
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    fn from_json(json: EmittersJson) -> Self {
+        // sorted, so particles in snapshots refer to the same emitters
+        // every time the definitions are loaded
+        let mut named: Vec<(String, Emitter)> = json.emitters.into_iter().collect();
+        named.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut emitters = Vec::new();
+        let mut indices = HashMap::new();
+        for (name, emitter) in named {
+            indices.insert(name, emitters.len());
+            emitters.push(emitter);
+        }
+        Emitters {
+            max_particles: json.max_particles,
+            decals: json.decals,
+            emitters,
+            indices,
+        }
+    }
+}
+
+impl FromStr for Emitters {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        Ok(Emitters::from_json(serde_json::from_str(json)?))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Particle {
+    /// Index of the emitter, in [Emitters]
+    emitter: usize,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub age: u32,
+    pub lifetime: u32,
+}
+
+/// An emitter emitting particles every tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Emission {
+    emitter: usize,
+    pos: Vec2,
+    /// Ticks left
+    remaining: u32,
+    /// Particles owed from earlier ticks, for rates below one per tick
+    owed: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    emissions: Vec<Emission>,
+}
+
+impl Particles {
+    /// Emits the burst of an emitter at `pos`, and keeps emitting at its
+    /// rate if it has one
+    pub fn emit(&mut self, emitters: &Emitters, name: &str, pos: &Vec2) {
+        let index = match emitters.index(name) {
+            Some(index) => index,
+            None => return,
+        };
+        let emitter = &emitters.emitters[index];
+        self.spawn(emitters, index, pos, emitter.burst);
+        if emitter.rate > 0. && emitter.duration > 0 {
+            self.emissions.push(Emission {
+                emitter: index,
+                pos: pos.clone(),
+                remaining: emitter.duration,
+                owed: 0.,
+            });
+        }
+    }
+
+    /// Emits `count` particles of an emitter at `pos` at once, instead of
+    /// its burst
+    pub fn burst(&mut self, emitters: &Emitters, name: &str, pos: &Vec2, count: u32) {
+        if let Some(index) = emitters.index(name) {
+            self.spawn(emitters, index, pos, count);
+        }
+    }
+
+    /// Moves the particles and removes the ones that died, stamping their
+    /// decals into `decals`
+    pub fn update(&mut self, emitters: &Emitters, decals: &mut DecalLayer) {
+        // particles restored from a snapshot made with other emitters are
+        // dropped
+        let count = emitters.emitters.len();
+        self.particles.retain(|p| p.emitter < count);
+        self.emissions.retain(|e| e.emitter < count);
+
+        let mut emissions = std::mem::take(&mut self.emissions);
+        for emission in emissions.iter_mut() {
+            emission.owed += emitters.emitters[emission.emitter].rate;
+            let count = emission.owed.floor();
+            emission.owed -= count;
+            emission.remaining -= 1;
+            self.spawn(emitters, emission.emitter, &emission.pos, count as u32);
+        }
+        emissions.retain(|e| e.remaining > 0);
+        self.emissions = emissions;
+
+        for particle in self.particles.iter_mut() {
+            let emitter = &emitters.emitters[particle.emitter];
+            particle.vel.x += emitter.gravity.0;
+            particle.vel.y += emitter.gravity.1;
+            particle.vel.scale(1. - emitter.drag);
+            particle.pos.add(&particle.vel);
+            particle.age += 1;
+            if particle.age >= particle.lifetime {
+                if let Some(stamp) = &emitter.decal {
+                    land(particle, stamp, decals);
+                }
+            }
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+    }
+
+    pub fn draw(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        clips: &Clips,
+        emitters: &Emitters,
+    ) {
+        for particle in self.particles.iter() {
+            let emitter = match emitters.emitters.get(particle.emitter) {
+                Some(emitter) => emitter,
+                None => continue,
+            };
+            let life = particle.age as f64 / particle.lifetime.max(1) as f64;
+            let color = color_at(&emitter.colors, life);
+            let alpha = value_at(&emitter.alpha, life).unwrap_or(1.);
+            let (x, y) = (particle.pos.x, particle.pos.y);
+            match emitter.clip.as_ref().and_then(|clip| clips.get(clip)) {
+                Some(clip) => {
+                    let ticks = match emitter.playback {
+                        Playback::Ending => {
+                            let remaining = particle.lifetime - particle.age;
+                            clip.length().saturating_sub(remaining)
+                        }
+                        Playback::OverLife => (life * clip.length() as f64) as u32,
+                        Playback::Loop => particle.age,
+                    };
+                    let transform = Transform {
+                        alpha,
+                        tint: color,
+                        ..Transform::default()
+                    };
+                    renderer.draw_frame_transformed(sheet, clip.frame_at(ticks), x, y, &transform);
+                }
+                None => {
+                    let color = color.unwrap_or(Color::WHITE);
+                    let color = color.with_alpha(color.a * alpha);
+                    let size = emitter.size;
+                    renderer.draw_rect(&color.to_css(), x - size / 2., y - size / 2., size, size);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.emissions.clear();
+    }
+
+    fn spawn(&mut self, emitters: &Emitters, index: usize, pos: &Vec2, count: u32) {
+        let max = emitters.max_particles;
+        if self.particles.capacity() < max {
+            self.particles
+                .reserve_exact(max.saturating_sub(self.particles.len()));
+        }
+        let emitter = &emitters.emitters[index];
+        let room = max.saturating_sub(self.particles.len());
+        for _ in 0..(count as usize).min(room) {
+            let angle = (emitter.direction + emitter.spread * (random() - 0.5)).to_radians();
+            let speed = emitter.speed.0 + (emitter.speed.1 - emitter.speed.0) * random();
+            let (shortest, longest) = emitter.lifetime;
+            let span = longest.saturating_sub(shortest) + 1;
+            let lifetime = shortest + (span as f64 * random()) as u32;
+            self.particles.push(Particle {
+                emitter: index,
+                pos: pos.clone(),
+                vel: Vec2::new(speed * angle.cos(), speed * angle.sin()),
+                age: 0,
+                lifetime: lifetime.max(1),
+            });
+        }
+    }
+}
+
+/// Leaves the decal of a dying particle, if it gets one
+fn land(particle: &Particle, stamp: &Stamp, decals: &mut DecalLayer) {
+    if stamp.frames.is_empty() || random() >= stamp.chance {
+        return;
+    }
+    let index = ((stamp.frames.len() as f64 * random()) as usize).min(stamp.frames.len() - 1);
+    decals.add(Decal {
+        frame: stamp.frames[index],
+        pos: particle.pos.clone(),
+        rotation: if stamp.rotate {
+            2. * std::f64::consts::PI * random()
+        } else {
+            0.
+        },
+        tint: stamp.tint,
+        alpha: stamp.alpha,
+    });
+}
+
+/// Where `life`, from 0 to 1, falls between keys spread evenly over a life:
+/// the index of the key before and how far it is towards the next one
+fn key_at(count: usize, life: f64) -> (usize, f64) {
+    let position = life.clamp(0., 1.) * (count - 1) as f64;
+    let index = (position.floor() as usize).min(count - 1);
+    (index, position - index as f64)
+}
+
+/// A value interpolated between keys spread evenly over a life
+pub fn value_at(keys: &[f64], life: f64) -> Option<f64> {
+    if keys.is_empty() {
+        return None;
+    }
+    let (index, t) = key_at(keys.len(), life);
+    let next = keys.get(index + 1).unwrap_or(&keys[index]);
+    Some(keys[index] + (next - keys[index]) * t)
+}
+
+/// A color interpolated between colors spread evenly over a life
+pub fn color_at(keys: &[Color], life: f64) -> Option<Color> {
+    if keys.is_empty() {
+        return None;
+    }
+    let (index, t) = key_at(keys.len(), life);
+    let (a, b) = (keys[index], *keys.get(index + 1).unwrap_or(&keys[index]));
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Some(Color {
+        r: mix(a.r, b.r),
+        g: mix(a.g, b.g),
+        b: mix(a.b, b.b),
+        a: a.a + (b.a - a.a) * t,
+    })
+}
@@ -0,0 +1,84 @@
+use crate::engine::listener::EventListener;
+use crate::engine::math::Vec2;
+use crate::engine::viewport::Viewport;
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::{HtmlCanvasElement, MouseEvent, TouchEvent};
+
+#[derive(Default)]
+struct PointerState {
+    /// Position in CSS pixels relative to the canvas
+    position: Option<(f64, f64)>,
+    pressed: bool,
+}
+
+/// Keeps track of the mouse, or the first finger touching the canvas. Stops
+/// listening when dropped.
+pub struct Pointer {
+    state: Rc<RefCell<PointerState>>,
+    _listeners: Vec<EventListener>,
+}
+
+impl Pointer {
+    pub fn new(canvas: &HtmlCanvasElement) -> Self {
+        let state = Rc::new(RefCell::new(PointerState::default()));
+        let mut listeners = Vec::new();
+
+        for name in ["mousemove", "mousedown", "mouseup"].iter() {
+            let state = state.clone();
+            listeners.push(EventListener::new(
+                canvas,
+                name,
+                move |event: MouseEvent| {
+                    let mut state = state.borrow_mut();
+                    state.position = Some((event.offset_x() as f64, event.offset_y() as f64));
+                    match event.type_().as_str() {
+                        "mousedown" => state.pressed = event.button() == 0,
+                        "mouseup" => state.pressed = false,
+                        _ => {}
+                    }
+                },
+            ));
+        }
+
+        for name in ["touchstart", "touchmove", "touchend", "touchcancel"].iter() {
+            let state = state.clone();
+            let element = canvas.clone();
+            listeners.push(EventListener::new(
+                canvas,
+                name,
+                move |event: TouchEvent| {
+                    event.prevent_default();
+                    let mut state = state.borrow_mut();
+                    match event.touches().get(0) {
+                        Some(touch) => {
+                            let rect = element.get_bounding_client_rect();
+                            state.position = Some((
+                                touch.client_x() as f64 - rect.left(),
+                                touch.client_y() as f64 - rect.top(),
+                            ));
+                            state.pressed = true;
+                        }
+                        None => state.pressed = false,
+                    }
+                },
+            ));
+        }
+
+        Pointer {
+            state,
+            _listeners: listeners,
+        }
+    }
+
+    /// Position in virtual coordinates, if the pointer is over the game
+    pub fn position(&self, viewport: &Viewport) -> Option<Vec2> {
+        let (x, y) = self.state.borrow().position?;
+        viewport.to_virtual(x, y)
+    }
+
+    /// Whether the left mouse button is held or the canvas is touched
+    pub fn is_pressed(&self) -> bool {
+        self.state.borrow().pressed
+    }
+}
@@ -1,3 +1,5 @@
+use crate::engine::atlas::SpriteSheet;
+use crate::engine::bitmap_font::Font;
 use std::collections::HashMap;
 use wasm_bindgen::JsValue;
 use web_sys::HtmlImageElement;
@@ -5,6 +7,14 @@ use web_sys::HtmlImageElement;
 pub struct Preloader {
     pub image_paths: Vec<String>,
     pub json_paths: Vec<String>,
+    pub atlas_paths: Vec<String>,
+    pub font_paths: Vec<String>,
+}
+
+impl Default for Preloader {
+    fn default() -> Self {
+        Preloader::new()
+    }
 }
 
 impl Preloader {
@@ -12,19 +22,36 @@ impl Preloader {
         Preloader {
             image_paths: Vec::new(),
             json_paths: Vec::new(),
+            atlas_paths: Vec::new(),
+            font_paths: Vec::new(),
         }
     }
 
+    /// Loads an image, unless it is already being loaded
     pub fn load_image(&mut self, path: String) {
-        self.image_paths.push(path);
+        if !self.image_paths.contains(&path) {
+            self.image_paths.push(path);
+        }
     }
 
     pub fn load_json(&mut self, path: String) {
         self.json_paths.push(path);
     }
+
+    /// Loads an atlas and the sprite sheet it refers to
+    pub fn load_atlas(&mut self, path: String) {
+        self.atlas_paths.push(path);
+    }
+
+    /// Loads a bitmap font and its image
+    pub fn load_font(&mut self, path: String) {
+        self.font_paths.push(path);
+    }
 }
 
 pub struct Resources {
     pub images: HashMap<String, HtmlImageElement>,
     pub jsons: HashMap<String, JsValue>,
+    pub sprite_sheets: HashMap<String, SpriteSheet>,
+    pub fonts: HashMap<String, Font>,
 }
@@ -0,0 +1,35 @@
+//! Seedable random numbers.
+//!
+//! A drop-in replacement for `Math.random` whose whole state is a single
+//! `u64`, so it can be stored in snapshots and restored to replay the exact
+//! same game. Uses the SplitMix64 generator.
+use std::cell::Cell;
+
+thread_local! {
+    static STATE: Cell<u64> = const { Cell::new(0x853c_49e6_748f_ea9b) };
+}
+
+pub fn seed(seed: u64) {
+    set_state(seed);
+}
+
+pub fn state() -> u64 {
+    STATE.with(|s| s.get())
+}
+
+pub fn set_state(state: u64) {
+    STATE.with(|s| s.set(state));
+}
+
+/// Returns a number in the range `[0, 1)`
+pub fn random() -> f64 {
+    let mut z = STATE.with(|s| {
+        let state = s.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        s.set(state);
+        state
+    });
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -1,194 +1,370 @@
+use crate::engine::atlas::{FrameId, SpriteSheet};
+use crate::engine::bitmap_font::{Font, TextStyle};
+use crate::engine::decals::DecalLayer;
+use crate::engine::tile_cache::TileLayerCache;
 use crate::engine::tiled::TileMap;
-use std::cmp::min;
+use crate::engine::transform::{Color, Transform};
+use crate::engine::viewport::{ScaleMode, Viewport};
+use std::cell::Cell;
 use wasm_bindgen::JsCast;
 use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
 
+/// Size of a sprite sheet pixel on the canvas
+const SPRITE_SCALE: f64 = 2.;
+
+/// What the game draws with. Backends only implement the primitives, the
+/// sprite and text helpers are built on top of them.
+pub trait Renderer {
+    /// Virtual resolution the game draws at
+    fn width(&self) -> f64;
+    fn height(&self) -> f64;
+
+    /// Places the game on the canvas after the canvas was resized
+    fn resize(&self, viewport: &Viewport);
+
+    /// Clears the canvas, including the letterbox bars
+    fn clear(&self);
+
+    /// Draws the `src` rectangle `[x, y, w, h]` of an image, mapped to the
+    /// screen by an affine `matrix` as returned by [Transform::matrix]
+    fn draw_image(
+        &self,
+        image: &HtmlImageElement,
+        src: [f64; 4],
+        matrix: [f64; 6],
+        alpha: f64,
+        tint: Option<Color>,
+    );
+
+    fn draw_rect(&self, fill_style: &str, x: f64, y: f64, w: f64, h: f64);
+
+    /// Draws a cached tile layer, rendering the chunks that changed first
+    fn draw_tile_layer(&self, cache: &TileLayerCache, map: &TileMap, tileset: &HtmlImageElement);
+
+    /// Draws the decals on the floor, above the tiles and below everything
+    /// else
+    fn draw_decals(&self, decals: &DecalLayer, sheet: &SpriteSheet) {
+        decals.draw(self, sheet);
+    }
+
+    /// Puts what was drawn since [Renderer::clear] on screen, for backends
+    /// that defer drawing
+    fn present(&self) {}
+
+    /// Draws the `src` rectangle of an image into the `dst` rectangle
+    fn draw_region(&self, image: &HtmlImageElement, src: [f64; 4], dst: [f64; 4]) {
+        let [dx, dy, dw, dh] = dst;
+        let matrix = [dw / src[2], 0., 0., dh / src[3], dx, dy];
+        self.draw_image(image, src, matrix, 1., None);
+    }
+
+    /// Draws a frame of a sprite sheet with its pivot at `x`, `y`. Frames
+    /// missing from the atlas are skipped.
+    fn draw_frame(&self, sheet: &SpriteSheet, id: FrameId, x: f64, y: f64) {
+        self.draw_frame_transformed(sheet, id, x, y, &Transform::default());
+    }
+
+    /// Draws a frame flipped, scaled and rotated around its pivot, which ends
+    /// up at `x`, `y`
+    fn draw_frame_transformed(
+        &self,
+        sheet: &SpriteSheet,
+        id: FrameId,
+        x: f64,
+        y: f64,
+        transform: &Transform,
+    ) {
+        let frame = match sheet.atlas.frame(id) {
+            Some(frame) => frame,
+            None => return,
+        };
+        let src = [frame.x, frame.y, frame.w, frame.h];
+        if transform.is_identity() {
+            self.draw_region(
+                &sheet.image,
+                src,
+                [
+                    (x - SPRITE_SCALE * frame.pivot.x).round(),
+                    (y - SPRITE_SCALE * frame.pivot.y).round(),
+                    SPRITE_SCALE * frame.w,
+                    SPRITE_SCALE * frame.h,
+                ],
+            );
+            return;
+        }
+
+        let [a, b, c, d, e, f] = transform.matrix(x, y, &frame.pivot, SPRITE_SCALE);
+        self.draw_image(
+            &sheet.image,
+            src,
+            [a, b, c, d, e.round(), f.round()],
+            transform.alpha,
+            transform.tint,
+        );
+    }
+
+    /// Draws a text with its first line at `y`. Where it goes horizontally
+    /// depends on the alignment of `style`.
+    fn draw_text(&self, font: &Font, x: f64, y: f64, text: &str, style: &TextStyle) {
+        for (src, [dx, dy, dw, dh]) in font.font.layout(text, x, y, style) {
+            let matrix = [dw / src[2], 0., 0., dh / src[3], dx, dy];
+            self.draw_image(&font.image, src, matrix, 1., style.tint);
+        }
+    }
+
+    fn draw_hearts(&self, numbers: &HtmlImageElement, x: f64, y: f64, count: i32) {
+        let mut remainder = count;
+        let mut i = 0;
+
+        while remainder > 0 {
+            let heart = if remainder > 5 { 0 } else { 5 - remainder };
+
+            self.draw_region(
+                numbers,
+                [0. + (heart as f64) * 6., 5., 6., 5.],
+                [x + 12. * i as f64, y, 12., 10.],
+            );
+
+            i += 1;
+            remainder -= 5;
+        }
+    }
+
+    fn draw_ammo(&self, numbers: &HtmlImageElement, x: f64, y: f64) {
+        self.draw_region(numbers, [0., 10., 6., 5.], [x, y, 12., 10.]);
+    }
+}
+
 pub struct CanvasRenderer {
     canvas: HtmlCanvasElement,
     ctx: CanvasRenderingContext2d,
-    pub width: f64,
-    pub height: f64,
+    /// Offscreen canvas where tinted sprites are composed
+    tint_canvas: HtmlCanvasElement,
+    tint_ctx: CanvasRenderingContext2d,
+    width: f64,
+    height: f64,
+    background: String,
+    viewport: Cell<Viewport>,
 }
 
 impl CanvasRenderer {
-    pub fn new(width: u32, height: u32) -> Self {
-        let window = window().unwrap();
-        let document = window.document().unwrap();
+    /// Draws at a virtual resolution of `width` by `height`
+    pub fn new(canvas: HtmlCanvasElement, width: f64, height: f64, background: Color) -> Self {
+        let ctx = canvas
+            .get_context("2d")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<CanvasRenderingContext2d>()
+            .unwrap();
+        ctx.set_image_smoothing_enabled(false);
 
-        let canvas = document
+        ctx.set_fill_style(&"#fff".into());
+
+        let tint_canvas = window()
+            .unwrap()
+            .document()
+            .unwrap()
             .create_element("canvas")
             .unwrap()
             .dyn_into::<HtmlCanvasElement>()
             .unwrap();
-        canvas.set_width(width as u32);
-        canvas.set_height(height as u32);
-        document.body().unwrap().append_child(&canvas).unwrap();
-
-        let ctx = canvas
+        let tint_ctx = tint_canvas
             .get_context("2d")
             .unwrap()
             .unwrap()
             .dyn_into::<CanvasRenderingContext2d>()
             .unwrap();
-        ctx.set_image_smoothing_enabled(false);
-
-        ctx.set_fill_style(&"#fff".into());
 
         CanvasRenderer {
+            width,
+            height,
+            background: background.to_css(),
+            viewport: Cell::new(Viewport::new(
+                width,
+                height,
+                width,
+                height,
+                1.,
+                ScaleMode::Integer,
+            )),
             canvas,
             ctx,
-            width: width as f64,
-            height: height as f64,
+            tint_canvas,
+            tint_ctx,
         }
     }
 
-    pub fn clear(&self) {
-        self.ctx.set_fill_style(&"#000".into());
-        self.ctx.fill_rect(
-            0.,
-            0.,
-            self.canvas.width() as f64,
-            self.canvas.height() as f64,
-        );
+    /// A renderer drawing into a new offscreen canvas of `width` by
+    /// `height`, which is cleared to transparent
+    pub fn offscreen(width: f64, height: f64) -> Self {
+        let canvas = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("canvas")
+            .unwrap()
+            .dyn_into::<HtmlCanvasElement>()
+            .unwrap();
+        canvas.set_width(width.ceil() as u32);
+        canvas.set_height(height.ceil() as u32);
+        CanvasRenderer::new(canvas, width, height, Color::BLACK.with_alpha(0.))
     }
 
-    pub fn draw_sprite(&self, image: &HtmlImageElement, idx: u8, x: f64, y: f64) {
-        self.ctx
-            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                &image,
-                8. * f64::from((idx) % 8),
-                8. * f64::from((idx) / 8),
-                8.,
-                8.,
-                x.round(),
-                y.round(),
-                16.,
-                16.,
-            )
-            .unwrap();
+    pub fn canvas(&self) -> &HtmlCanvasElement {
+        &self.canvas
     }
 
-    pub fn draw_block(&self, image: &HtmlImageElement, idx: u8, x: f64, y: f64, w: u8, h: u8) {
+    /// Draws a whole canvas with its top left at `x`, `y`
+    pub fn draw_canvas(&self, canvas: &HtmlCanvasElement, x: f64, y: f64) {
         self.ctx
-            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                &image,
-                8. * f64::from((idx) % 8),
-                8. * f64::from((idx) / 8),
-                8. * w as f64,
-                8. * h as f64,
-                x.round(),
-                y.round(),
-                16. * w as f64,
-                16. * h as f64,
-            )
+            .draw_image_with_html_canvas_element(canvas, x, y)
             .unwrap();
     }
 
-    pub fn draw_map(&self, map: &TileMap, tileset: &HtmlImageElement) {
-        let tx_max = min(self.width as usize / 8, map.width as usize);
-        let ty_max = min(self.height as usize / 8, map.height as usize);
-        let data = &map.layers[0].data;
-        for tx in 0..tx_max {
-            for ty in 0..ty_max {
-                self.ctx
-                    .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                        &tileset,
-                        8. * f64::from((data[tx + ty * 60] - 1) % 8),
-                        8. * f64::from((data[tx + ty * 60] - 1) / 8),
-                        8.,
-                        8.,
-                        16. * (tx as f64),
-                        16. * (ty as f64),
-                        16.,
-                        16.,
-                    )
-                    .unwrap();
-            }
+    /// Copies a part of an image to the top left of the tint canvas and
+    /// colors its opaque pixels
+    fn compose_tinted(
+        &self,
+        image: &HtmlImageElement,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        tint: &Color,
+    ) {
+        if (self.tint_canvas.width() as f64) < w {
+            self.tint_canvas.set_width(w.ceil() as u32);
+        }
+        if (self.tint_canvas.height() as f64) < h {
+            self.tint_canvas.set_height(h.ceil() as u32);
         }
+        let ctx = &self.tint_ctx;
+        ctx.set_global_composite_operation("copy").unwrap();
+        ctx.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            image, x, y, w, h, 0., 0., w, h,
+        )
+        .unwrap();
+        ctx.set_global_composite_operation("source-atop").unwrap();
+        ctx.set_fill_style(&tint.to_css().into());
+        ctx.fill_rect(0., 0., w, h);
     }
+}
 
-    pub fn draw_rect(&self, fill_style: &str, x: f64, y: f64, w: f64, h: f64) {
-        self.ctx.set_fill_style(&fill_style.into());
-        self.ctx.fill_rect(x, y, w, h);
+impl Renderer for CanvasRenderer {
+    fn width(&self) -> f64 {
+        self.width
     }
 
-    pub fn draw_big_text(&self, image: &HtmlImageElement, x: f64, y: f64, text: &str) {
-        let mut offset = 0.;
+    fn height(&self) -> f64 {
+        self.height
+    }
 
-        text.chars().for_each(|c| {
-            let cx = ((c as i8 - 65 + 32) % 8) * 16;
-            let cy = ((c as i8 - 65 + 32) / 8) * 16;
-            self.ctx
-                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                    image,
-                    cx as f64,
-                    cy as f64,
-                    16.,
-                    16.,
-                    x + offset,
-                    y,
-                    32.,
-                    32.,
-                )
-                .unwrap();
-            offset += 32.;
-        })
+    /// Resizing the canvas resets the context, so smoothing is turned off
+    /// again
+    fn resize(&self, viewport: &Viewport) {
+        self.viewport.set(*viewport);
+        self.ctx.set_image_smoothing_enabled(false);
     }
 
-    pub fn draw_numbers(&self, image: &HtmlImageElement, x: f64, y: f64, text: &str) {
-        let mut offset = 0.;
+    /// Clears before filling with the background, which may be transparent
+    fn clear(&self) {
+        let viewport = self.viewport.get();
+        let (width, height) = (self.canvas.width() as f64, self.canvas.height() as f64);
+        self.ctx.set_transform(1., 0., 0., 1., 0., 0.).unwrap();
+        self.ctx.clear_rect(0., 0., width, height);
+        self.ctx.set_fill_style(&self.background.as_str().into());
+        self.ctx.fill_rect(
+            0.,
+            0.,
+            self.canvas.width() as f64,
+            self.canvas.height() as f64,
+        );
+        self.ctx
+            .set_transform(
+                viewport.scale,
+                0.,
+                0.,
+                viewport.scale,
+                viewport.x,
+                viewport.y,
+            )
+            .unwrap();
+    }
 
-        text.chars().for_each(|c| {
-            let cx = ((c as i8 - 48) % 18) * 3;
-            let cy = ((c as i8 - 48) / 18) * 5;
+    /// Images that are only moved and stretched are drawn directly, the
+    /// others through the context transform. Tinted images are colored on an
+    /// offscreen canvas first.
+    fn draw_image(
+        &self,
+        image: &HtmlImageElement,
+        src: [f64; 4],
+        matrix: [f64; 6],
+        alpha: f64,
+        tint: Option<Color>,
+    ) {
+        let [sx, sy, sw, sh] = src;
+        let [a, b, c, d, e, f] = matrix;
+        if b == 0. && c == 0. && a > 0. && d > 0. && alpha == 1. && tint.is_none() {
             self.ctx
                 .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
                     image,
-                    cx as f64,
-                    cy as f64,
-                    3.,
-                    5.,
-                    x + offset,
-                    y,
-                    6.,
-                    10.,
+                    sx,
+                    sy,
+                    sw,
+                    sh,
+                    e,
+                    f,
+                    a * sw,
+                    d * sh,
                 )
                 .unwrap();
-            offset += 8.;
-        });
-    }
-
-    pub fn draw_hearts(&self, numbers: &HtmlImageElement, x: f64, y: f64, count: i32) {
-        let mut remainder = count;
-        let mut i = 0;
-
-        while remainder > 0 {
-            let heart = if remainder > 5 { 0 } else { 5 - remainder };
+            return;
+        }
 
-            self.ctx
+        self.ctx.save();
+        self.ctx.set_global_alpha(alpha);
+        self.ctx.transform(a, b, c, d, e, f).unwrap();
+        match tint {
+            Some(tint) => {
+                self.compose_tinted(image, sx, sy, sw, sh, &tint);
+                self.ctx
+                    .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                        &self.tint_canvas,
+                        0.,
+                        0.,
+                        sw,
+                        sh,
+                        0.,
+                        0.,
+                        sw,
+                        sh,
+                    )
+                    .unwrap();
+            }
+            None => self
+                .ctx
                 .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                    numbers,
-                    0. + (heart as f64) * 6.,
-                    5.,
-                    6.,
-                    5.,
-                    x + 12. * i as f64,
-                    y,
-                    12.,
-                    10.,
+                    image, sx, sy, sw, sh, 0., 0., sw, sh,
                 )
-                .unwrap();
+                .unwrap(),
+        }
+        self.ctx.restore();
+    }
 
-            i += 1;
-            remainder -= 5;
+    fn draw_rect(&self, fill_style: &str, x: f64, y: f64, w: f64, h: f64) {
+        self.ctx.set_fill_style(&fill_style.into());
+        self.ctx.fill_rect(x, y, w, h);
+    }
+
+    fn draw_tile_layer(&self, cache: &TileLayerCache, map: &TileMap, tileset: &HtmlImageElement) {
+        cache.redraw(map, tileset);
+        for (canvas, x, y) in cache.visible(self.width, self.height) {
+            self.draw_canvas(canvas, x, y);
         }
     }
 
-    pub fn draw_ammo(&self, numbers: &HtmlImageElement, x: f64, y: f64) {
-        self.ctx
-            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                numbers, 0., 10., 6., 5., x, y, 12., 10.,
-            )
-            .unwrap();
+    /// Decals are stamped into an offscreen surface as they are added
+    fn draw_decals(&self, decals: &DecalLayer, sheet: &SpriteSheet) {
+        decals.draw_with_surface(self, sheet);
     }
 }
@@ -0,0 +1,75 @@
+//! Persistent key/value storage.
+//!
+//! The browser build keeps its data in `localStorage`. Natively the values
+//! are written to files in a directory instead, which is what the tests use.
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use web_sys::window;
+
+pub trait Storage {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str);
+}
+
+/// Storage backed by the `localStorage` of the browser. Does nothing if the
+/// browser doesn't allow it, e.g. in private mode.
+pub struct LocalStorage {
+    storage: Option<web_sys::Storage>,
+}
+
+impl LocalStorage {
+    pub fn new() -> Self {
+        let storage = window().and_then(|w| w.local_storage().ok().flatten());
+        LocalStorage { storage }
+    }
+}
+
+impl Default for LocalStorage {
+    fn default() -> Self {
+        LocalStorage::new()
+    }
+}
+
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.storage.as_ref()?.get_item(key).ok().flatten()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        if let Some(storage) = &self.storage {
+            let _ = storage.set_item(key, value);
+        }
+    }
+}
+
+/// Storage keeping every key in its own file in `dir`
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        FileStorage { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.path(key)).ok()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        if fs::create_dir_all(&self.dir).is_ok() {
+            let _ = fs::write(self.path(key), value);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Tile layers pre-rendered into offscreen canvases.
+//!
+//! Drawing a map tile by tile takes thousands of `drawImage` calls per frame,
+//! although the tiles hardly ever change. Instead a layer is split into
+//! square chunks, each rendered once into its own canvas and then copied to
+//! the screen with a single call. Changing a tile only redraws its chunk.
+use crate::engine::tiled::TileMap;
+use std::cell::{OnceCell, RefCell};
+use std::ops::Range;
+use wasm_bindgen::JsCast;
+use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+/// Width and height of a chunk in tiles
+pub const CHUNK_TILES: usize = 16;
+
+/// Size of a tile in the tileset
+const TILE_SIZE: f64 = 8.;
+
+/// Size of a tile on screen
+const SCREEN_TILE_SIZE: f64 = 16.;
+
+/// Tiles per row of the tileset
+const TILESET_COLUMNS: u8 = 8;
+
+/// How a layer is divided into chunks, and which of them need redrawing
+pub struct Chunks {
+    /// Size of the layer in tiles
+    pub width: usize,
+    pub height: usize,
+    /// Size of a chunk in tiles
+    pub size: usize,
+    dirty: Vec<bool>,
+}
+
+impl Chunks {
+    /// All chunks start out dirty
+    pub fn new(width: usize, height: usize, size: usize) -> Self {
+        let mut chunks = Chunks {
+            width,
+            height,
+            size,
+            dirty: Vec::new(),
+        };
+        chunks.dirty = vec![true; chunks.columns() * chunks.rows()];
+        chunks
+    }
+
+    pub fn columns(&self) -> usize {
+        chunk_count(self.width, self.size)
+    }
+
+    pub fn rows(&self) -> usize {
+        chunk_count(self.height, self.size)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dirty.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirty.is_empty()
+    }
+
+    /// Chunk containing a tile
+    pub fn chunk_at(&self, tx: usize, ty: usize) -> Option<usize> {
+        if tx < self.width && ty < self.height {
+            Some(tx / self.size + (ty / self.size) * self.columns())
+        } else {
+            None
+        }
+    }
+
+    /// Columns and rows of the tiles in a chunk
+    pub fn tiles(&self, chunk: usize) -> (Range<usize>, Range<usize>) {
+        let x = (chunk % self.columns()) * self.size;
+        let y = (chunk / self.columns()) * self.size;
+        (
+            x..usize::min(x + self.size, self.width),
+            y..usize::min(y + self.size, self.height),
+        )
+    }
+
+    /// Chunks overlapping a rectangle given in tiles
+    pub fn overlapping(&self, x: Range<usize>, y: Range<usize>) -> Vec<usize> {
+        let columns =
+            x.start / self.size..usize::min(self.columns(), chunk_count(x.end, self.size));
+        let rows = y.start / self.size..usize::min(self.rows(), chunk_count(y.end, self.size));
+        rows.flat_map(|row| {
+            columns
+                .clone()
+                .map(move |column| column + row * self.columns())
+        })
+        .collect()
+    }
+
+    pub fn invalidate(&mut self, tx: usize, ty: usize) {
+        if let Some(chunk) = self.chunk_at(tx, ty) {
+            self.dirty[chunk] = true;
+        }
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.dirty.iter_mut().for_each(|d| *d = true);
+    }
+
+    /// Returns the chunks needing a redraw and marks them as clean
+    pub fn take_dirty(&mut self) -> Vec<usize> {
+        let dirty = self
+            .dirty
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| **d)
+            .map(|(i, _)| i)
+            .collect();
+        self.dirty.iter_mut().for_each(|d| *d = false);
+        dirty
+    }
+}
+
+/// Number of chunks needed to cover `tiles`
+fn chunk_count(tiles: usize, size: usize) -> usize {
+    tiles / size + if tiles.is_multiple_of(size) { 0 } else { 1 }
+}
+
+/// Rectangle `[x, y, w, h]` of a tile in the tileset. Tile 0 is empty.
+fn tile_source(tile: u8) -> [f64; 4] {
+    [
+        TILE_SIZE * f64::from((tile - 1) % TILESET_COLUMNS),
+        TILE_SIZE * f64::from((tile - 1) / TILESET_COLUMNS),
+        TILE_SIZE,
+        TILE_SIZE,
+    ]
+}
+
+/// Tiles of a layer visible in a view of `width` by `height` pixels, as
+/// rectangles `[x, y, w, h]` in the tileset and on screen. For renderers
+/// that draw tiles directly instead of caching them.
+pub fn visible_tiles(
+    map: &TileMap,
+    layer: usize,
+    width: f64,
+    height: f64,
+) -> Vec<([f64; 4], [f64; 4])> {
+    let data = &map.layers[layer].data;
+    let columns = usize::min(
+        map.width as usize,
+        (width / SCREEN_TILE_SIZE).ceil() as usize,
+    );
+    let rows = usize::min(
+        map.height as usize,
+        (height / SCREEN_TILE_SIZE).ceil() as usize,
+    );
+    (0..rows)
+        .flat_map(|ty| (0..columns).map(move |tx| (tx, ty)))
+        .filter_map(|(tx, ty)| match data[tx + ty * map.width as usize] {
+            0 => None,
+            tile => Some((
+                tile_source(tile),
+                [
+                    SCREEN_TILE_SIZE * tx as f64,
+                    SCREEN_TILE_SIZE * ty as f64,
+                    SCREEN_TILE_SIZE,
+                    SCREEN_TILE_SIZE,
+                ],
+            )),
+        })
+        .collect()
+}
+
+/// A tile layer of a map, cached in one canvas per chunk. The canvases are
+/// only created once the layer is first drawn by a renderer using the cache.
+pub struct TileLayerCache {
+    layer: usize,
+    chunks: RefCell<Chunks>,
+    canvases: OnceCell<Vec<(HtmlCanvasElement, CanvasRenderingContext2d)>>,
+}
+
+impl TileLayerCache {
+    pub fn new(map: &TileMap, layer: usize) -> Self {
+        TileLayerCache {
+            layer,
+            chunks: RefCell::new(Chunks::new(
+                map.width as usize,
+                map.height as usize,
+                CHUNK_TILES,
+            )),
+            canvases: OnceCell::new(),
+        }
+    }
+
+    /// Index of the cached layer in the map
+    pub fn layer(&self) -> usize {
+        self.layer
+    }
+
+    /// Redraws the chunk of a tile the next time the layer is drawn. Call
+    /// after changing the tile in the map.
+    pub fn invalidate(&mut self, tx: usize, ty: usize) {
+        self.chunks.borrow_mut().invalidate(tx, ty);
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.chunks.borrow_mut().invalidate_all();
+    }
+
+    /// Renders the chunks that changed since the last call
+    pub fn redraw(&self, map: &TileMap, tileset: &HtmlImageElement) {
+        let data = &map.layers[self.layer].data;
+        let mut chunks = self.chunks.borrow_mut();
+        let canvases = self.canvases.get_or_init(|| create_canvases(chunks.len()));
+        for chunk in chunks.take_dirty() {
+            let (columns, rows) = chunks.tiles(chunk);
+            let (canvas, ctx) = &canvases[chunk];
+            ctx.clear_rect(0., 0., canvas.width() as f64, canvas.height() as f64);
+            for ty in rows.clone() {
+                for tx in columns.clone() {
+                    let tile = data[tx + ty * chunks.width];
+                    if tile == 0 {
+                        continue;
+                    }
+                    let [sx, sy, sw, sh] = tile_source(tile);
+                    ctx.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                        tileset,
+                        sx,
+                        sy,
+                        sw,
+                        sh,
+                        SCREEN_TILE_SIZE * (tx - columns.start) as f64,
+                        SCREEN_TILE_SIZE * (ty - rows.start) as f64,
+                        SCREEN_TILE_SIZE,
+                        SCREEN_TILE_SIZE,
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Canvases of the chunks visible in a view of `width` by `height`
+    /// pixels, with their positions on screen. Empty until the first
+    /// [TileLayerCache::redraw].
+    pub fn visible(&self, width: f64, height: f64) -> Vec<(&HtmlCanvasElement, f64, f64)> {
+        let canvases = match self.canvases.get() {
+            Some(canvases) => canvases,
+            None => return Vec::new(),
+        };
+        let chunks = self.chunks.borrow();
+        let columns = 0..(width / SCREEN_TILE_SIZE).ceil() as usize;
+        let rows = 0..(height / SCREEN_TILE_SIZE).ceil() as usize;
+        chunks
+            .overlapping(columns, rows)
+            .into_iter()
+            .map(|chunk| {
+                let (columns, rows) = chunks.tiles(chunk);
+                (
+                    &canvases[chunk].0,
+                    SCREEN_TILE_SIZE * columns.start as f64,
+                    SCREEN_TILE_SIZE * rows.start as f64,
+                )
+            })
+            .collect()
+    }
+}
+
+/// One offscreen canvas per chunk
+fn create_canvases(count: usize) -> Vec<(HtmlCanvasElement, CanvasRenderingContext2d)> {
+    let document = window().unwrap().document().unwrap();
+    let size = (CHUNK_TILES as f64 * SCREEN_TILE_SIZE) as u32;
+    (0..count)
+        .map(|_| {
+            let canvas = document
+                .create_element("canvas")
+                .unwrap()
+                .dyn_into::<HtmlCanvasElement>()
+                .unwrap();
+            canvas.set_width(size);
+            canvas.set_height(size);
+            let ctx = canvas
+                .get_context("2d")
+                .unwrap()
+                .unwrap()
+                .dyn_into::<CanvasRenderingContext2d>()
+                .unwrap();
+            ctx.set_image_smoothing_enabled(false);
+            (canvas, ctx)
+        })
+        .collect()
+}
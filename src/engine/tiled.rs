@@ -1,5 +1,7 @@
 //! Support for maps created with https://www.mapeditor.org/
+use crate::engine::json::from_js;
 use serde::Deserialize;
+use std::str::FromStr;
 use wasm_bindgen::JsValue;
 
 #[derive(Debug, Deserialize)]
@@ -18,6 +20,24 @@ pub struct TileLayer {
 
 impl TileMap {
     pub fn new_from_json(json: &JsValue) -> TileMap {
-        json.into_serde().unwrap()
+        from_js(json).unwrap()
+    }
+
+    pub fn tile(&self, layer: usize, tx: usize, ty: usize) -> u8 {
+        self.layers[layer].data[tx + ty * self.width as usize]
+    }
+
+    /// Changes a tile. Cached layers need to be told with
+    /// [TileLayerCache::invalidate](crate::engine::tile_cache::TileLayerCache::invalidate).
+    pub fn set_tile(&mut self, layer: usize, tx: usize, ty: usize, tile: u8) {
+        self.layers[layer].data[tx + ty * self.width as usize] = tile;
+    }
+}
+
+impl FromStr for TileMap {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
     }
 }
@@ -0,0 +1,133 @@
+//! How a sprite is placed on screen.
+//!
+//! A [Transform] flips, scales and rotates a frame around its pivot, and can
+//! fade or tint it. The math is kept separate from the renderers, so every
+//! backend places sprites the same way.
+use crate::engine::math::Vec2;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Opacity from 0 to 1. For tints, how strongly the color replaces the
+    /// colors of the sprite.
+    pub a: f64,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const RED: Color = Color::rgb(255, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 1. }
+    }
+
+    pub fn with_alpha(self, a: f64) -> Color {
+        Color { a, ..self }
+    }
+
+    pub fn to_css(self) -> String {
+        format!("rgba({},{},{},{})", self.r, self.g, self.b, self.a)
+    }
+
+    /// Reads the colors used as fill styles: a few names, and hex colors
+    /// with three, four, six or eight digits
+    pub fn from_css(css: &str) -> Option<Color> {
+        match css {
+            "black" => return Some(Color::BLACK),
+            "white" => return Some(Color::WHITE),
+            "red" => return Some(Color::RED),
+            "lime" => return Some(Color::rgb(0, 255, 0)),
+            "yellow" => return Some(Color::rgb(255, 255, 0)),
+            _ => {}
+        }
+        let hex = css.strip_prefix('#')?;
+        let digits = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()?;
+        let channels: Vec<u8> = match digits.len() {
+            3 | 4 => digits.iter().map(|d| d * 17).collect(),
+            6 | 8 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+            _ => return None,
+        };
+        let a = channels.get(3).map(|a| f64::from(*a) / 255.).unwrap_or(1.);
+        Some(Color::rgb(channels[0], channels[1], channels[2]).with_alpha(a))
+    }
+}
+
+/// Colors are stored as hex colors with alpha
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let a = (self.a.clamp(0., 1.) * 255.).round() as u8;
+        let hex = format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, a);
+        serializer.serialize_str(&hex)
+    }
+}
+
+/// Colors in data files are written like fill styles
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let css = String::deserialize(deserializer)?;
+        Color::from_css(&css).ok_or_else(|| D::Error::custom(format!("invalid color {}", css)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform {
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Clockwise, in radians
+    pub rotation: f64,
+    pub scale: Vec2,
+    pub alpha: f64,
+    pub tint: Option<Color>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            flip_x: false,
+            flip_y: false,
+            rotation: 0.,
+            scale: Vec2::new(1., 1.),
+            alpha: 1.,
+            tint: None,
+        }
+    }
+}
+
+impl Transform {
+    pub fn rotated(rotation: f64) -> Self {
+        Transform {
+            rotation,
+            ..Transform::default()
+        }
+    }
+
+    /// Whether sprites are drawn just as they are in the sprite sheet
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::default()
+    }
+
+    /// Affine matrix `[a, b, c, d, e, f]`, as used by `setTransform`, that
+    /// maps a point of a frame in sprite sheet pixels to the screen. The
+    /// frame `pivot` ends up at `x`, `y` and each sprite sheet pixel becomes
+    /// `pixel_scale` screen pixels before scaling.
+    pub fn matrix(&self, x: f64, y: f64, pivot: &Vec2, pixel_scale: f64) -> [f64; 6] {
+        let sx = pixel_scale * self.scale.x * if self.flip_x { -1. } else { 1. };
+        let sy = pixel_scale * self.scale.y * if self.flip_y { -1. } else { 1. };
+        let (sin, cos) = self.rotation.sin_cos();
+        let a = cos * sx;
+        let b = sin * sx;
+        let c = -sin * sy;
+        let d = cos * sy;
+        let e = x - (a * pivot.x + c * pivot.y);
+        let f = y - (b * pivot.x + d * pivot.y);
+        [a, b, c, d, e, f]
+    }
+}
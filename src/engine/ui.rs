@@ -0,0 +1,579 @@
+//! Immediate mode menus.
+//!
+//! Menus are code instead of data. Every update the game calls the widgets
+//! of the menu it shows, in order, and acts on what they return:
+//!
+//! ```ignore
+//! ui.begin("pause", MenuInput::read(ctx));
+//! ui.begin_panel(480., 120., 320.);
+//! if ui.button("RESUME") {
+//!     self.screen = Screen::Playing;
+//! }
+//! ui.slider("DEAD ZONE", &mut dead_zone, 0., 0.5, 0.05);
+//! ui.end_panel();
+//! ui.end();
+//! ```
+//!
+//! Widgets are stacked in a column and record what to draw, which is drawn
+//! by [Ui::draw] when the game renders. Focus moves through the widgets
+//! that can take it in the order they are called, with up and down on the
+//! d-pad, the stick or the keyboard, or by pointing at them. Left and right
+//! change the focused widget and confirm activates it. Clicking or tapping
+//! a widget does both.
+use crate::engine::atlas::{FrameId, SpriteSheet};
+use crate::engine::bitmap_font::{Align, Font, TextStyle};
+use crate::engine::math::Vec2;
+use crate::engine::renderer::Renderer;
+use crate::engine::transform::Color;
+use crate::engine::GameContext;
+
+/// Ticks a direction is held before it repeats
+const REPEAT_DELAY: u32 = 20;
+
+/// Ticks between repeats of a held direction
+const REPEAT_INTERVAL: u32 = 6;
+
+/// How far a stick has to be tilted to navigate
+const STICK_THRESHOLD: f64 = 0.5;
+
+/// Buttons held down for navigating menus, on any of the input devices
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MenuInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    /// Position of the mouse or finger in virtual coordinates
+    pub pointer: Option<Vec2>,
+    pub pointer_pressed: bool,
+}
+
+impl MenuInput {
+    /// Combines the gamepads, the arrow keys or WASD with enter or space to
+    /// confirm and escape to go back, and the pointer
+    pub fn read(ctx: &GameContext) -> Self {
+        let key = |codes: &[&str]| codes.iter().any(|code| ctx.keyboard.is_pressed(code));
+        let mut input = MenuInput {
+            up: key(&["ArrowUp", "KeyW"]),
+            down: key(&["ArrowDown", "KeyS"]),
+            left: key(&["ArrowLeft", "KeyA"]),
+            right: key(&["ArrowRight", "KeyD"]),
+            confirm: key(&["Enter", "Space"]),
+            back: key(&["Escape", "Backspace"]),
+            pointer: ctx.pointer.position(&ctx.viewport),
+            pointer_pressed: ctx.pointer.is_pressed(),
+        };
+        for pad in [&ctx.gamepad_1, &ctx.gamepad_2].iter() {
+            input.up |= pad.up || pad.move_y_axis < -STICK_THRESHOLD;
+            input.down |= pad.down || pad.move_y_axis > STICK_THRESHOLD;
+            input.left |= pad.left || pad.move_x_axis < -STICK_THRESHOLD;
+            input.right |= pad.right || pad.move_x_axis > STICK_THRESHOLD;
+            input.confirm |= pad.confirm || pad.shoot;
+            input.back |= pad.back;
+        }
+        input
+    }
+}
+
+/// Looks of the menus. The frames come from the game's sprite sheet.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Drawn left of the focused widget
+    pub cursor: FrameId,
+    /// Marks the value of a slider
+    pub knob: FrameId,
+    pub panel: Color,
+    pub border: Color,
+    /// Background of the focused widget
+    pub highlight: Color,
+    /// Tint of the text of the focused widget
+    pub focus: Color,
+    /// Tint of values that are switched off
+    pub off: Color,
+    pub track: Color,
+    pub fill: Color,
+    pub heading_scale: f64,
+    pub text_scale: f64,
+    pub digit_scale: f64,
+    pub row_height: f64,
+    /// Height of the rows of [Ui::columns]
+    pub cell_height: f64,
+    pub padding: f64,
+}
+
+/// Fonts and sprites the menus are drawn with. Digits have a font of their
+/// own.
+pub struct Skin<'a> {
+    pub sheet: &'a SpriteSheet,
+    pub text: &'a Font,
+    pub digits: &'a Font,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Face {
+    Text,
+    Digits,
+}
+
+enum Draw {
+    Rect {
+        rect: [f64; 4],
+        color: Color,
+    },
+    /// Text vertically centered on `middle`
+    Text {
+        face: Face,
+        x: f64,
+        middle: f64,
+        text: String,
+        style: TextStyle,
+    },
+    Frame {
+        frame: FrameId,
+        x: f64,
+        y: f64,
+    },
+}
+
+/// Ticks a direction has been held, to repeat it
+#[derive(Default)]
+struct Repeat(u32);
+
+impl Repeat {
+    fn update(&mut self, held: bool) -> bool {
+        if !held {
+            self.0 = 0;
+            return false;
+        }
+        self.0 += 1;
+        self.0 == 1
+            || (self.0 > REPEAT_DELAY && (self.0 - REPEAT_DELAY).is_multiple_of(REPEAT_INTERVAL))
+    }
+}
+
+/// A column of widgets, with the top of the next one at `y`
+struct Column {
+    x: f64,
+    y: f64,
+    width: f64,
+    /// Index of the panel behind the column in the draw list
+    panel: Option<usize>,
+    top: f64,
+}
+
+pub struct Ui {
+    pub theme: Theme,
+    menu: String,
+    focus: usize,
+    /// Widgets that took focus in the last update
+    focusable: usize,
+    count: usize,
+
+    input: MenuInput,
+    up: Repeat,
+    down: Repeat,
+    left: Repeat,
+    right: Repeat,
+    /// -1 for left and 1 for right
+    sideways: i64,
+    confirm: bool,
+    back: bool,
+    pointer_moved: bool,
+    clicked: bool,
+
+    column: Column,
+    draws: Vec<Draw>,
+}
+
+impl Ui {
+    pub fn new(theme: Theme) -> Self {
+        Ui {
+            theme,
+            menu: String::new(),
+            focus: 0,
+            focusable: 0,
+            count: 0,
+            input: MenuInput::default(),
+            up: Repeat::default(),
+            down: Repeat::default(),
+            left: Repeat::default(),
+            right: Repeat::default(),
+            sideways: 0,
+            confirm: false,
+            back: false,
+            pointer_moved: false,
+            clicked: false,
+            column: Column {
+                x: 0.,
+                y: 0.,
+                width: 0.,
+                panel: None,
+                top: 0.,
+            },
+            draws: Vec::new(),
+        }
+    }
+
+    /// Starts laying out a menu. Focus goes to the first widget when the
+    /// menu is a different one than in the last update.
+    pub fn begin(&mut self, menu: &str, input: MenuInput) {
+        if menu != self.menu {
+            self.menu = String::from(menu);
+            self.focus = 0;
+            self.focusable = 0;
+            // What was held to get here doesn't count as pressed
+            self.input = MenuInput {
+                pointer: None,
+                ..input.clone()
+            };
+        }
+        let up = self.up.update(input.up);
+        let down = self.down.update(input.down);
+        if self.focusable > 0 {
+            if up {
+                self.focus = (self.focus + self.focusable - 1) % self.focusable;
+            }
+            if down {
+                self.focus = (self.focus + 1) % self.focusable;
+            }
+        }
+        let left = self.left.update(input.left);
+        let right = self.right.update(input.right);
+        self.sideways = right as i64 - left as i64;
+        self.confirm = input.confirm && !self.input.confirm;
+        self.back = input.back && !self.input.back;
+        self.pointer_moved = input.pointer.is_some() && input.pointer != self.input.pointer;
+        self.clicked = input.pointer_pressed && !self.input.pointer_pressed;
+        self.input = input;
+        self.count = 0;
+        self.draws.clear();
+    }
+
+    /// Forgets the menu shown, once the game goes on without menus
+    pub fn close(&mut self) {
+        self.menu.clear();
+        self.draws.clear();
+    }
+
+    /// Ends the menu, keeping the focus on one of its widgets
+    pub fn end(&mut self) {
+        self.focusable = self.count;
+        if self.focus >= self.focusable {
+            self.focus = self.focusable.saturating_sub(1);
+        }
+    }
+
+    /// Whether going back was pressed, e.g. to close the menu
+    pub fn back_pressed(&self) -> bool {
+        self.back
+    }
+
+    /// Index of the focused widget, counting those that can take focus
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+
+    /// Puts the widgets that follow in a column of `width` centered on `x`,
+    /// starting at `y`
+    pub fn column(&mut self, x: f64, y: f64, width: f64) {
+        self.column = Column {
+            x: x - width / 2.,
+            y,
+            width,
+            panel: None,
+            top: y,
+        };
+    }
+
+    /// Like [Ui::column], on a panel that grows with the widgets up to
+    /// [Ui::end_panel]
+    pub fn begin_panel(&mut self, x: f64, y: f64, width: f64) {
+        let padding = self.theme.padding;
+        self.column(x, y + padding, width - 2. * padding);
+        self.column.top = y;
+        self.column.panel = Some(self.draws.len());
+        let rect = [x - width / 2., y, width, 0.];
+        self.draws.push(Draw::Rect {
+            rect,
+            color: self.theme.border,
+        });
+        self.draws.push(Draw::Rect {
+            rect,
+            color: self.theme.panel,
+        });
+    }
+
+    pub fn end_panel(&mut self) {
+        if let Some(index) = self.column.panel.take() {
+            let height = self.column.y + self.theme.padding - self.column.top;
+            if let Some(Draw::Rect { rect, .. }) = self.draws.get_mut(index) {
+                *rect = [rect[0] - 2., rect[1] - 2., rect[2] + 4., height + 4.];
+            }
+            if let Some(Draw::Rect { rect, .. }) = self.draws.get_mut(index + 1) {
+                rect[3] = height;
+            }
+        }
+    }
+
+    /// Leaves an empty gap
+    pub fn space(&mut self, height: f64) {
+        self.column.y += height;
+    }
+
+    /// Big text, centered
+    pub fn heading(&mut self, text: &str) {
+        let scale = self.theme.heading_scale;
+        let rect = self.row(self.theme.row_height * scale / self.theme.text_scale);
+        self.text(Face::Text, rect, Align::Center, text, scale, None);
+    }
+
+    /// Text centered in the column
+    pub fn label(&mut self, text: &str) {
+        let rect = self.row(self.theme.row_height);
+        let scale = self.theme.text_scale;
+        self.text(Face::Text, rect, Align::Center, text, scale, None);
+    }
+
+    /// A row of numbers and text, each centered in an equal share of the
+    /// row. Cells starting with a digit are drawn with the digits font.
+    pub fn columns(&mut self, cells: &[&str]) {
+        let [x, y, w, h] = self.row(self.theme.cell_height);
+        let width = w / cells.len().max(1) as f64;
+        for (i, cell) in cells.iter().enumerate() {
+            let rect = [x + i as f64 * width, y, width, h];
+            let (face, scale) = self.face(cell);
+            self.text(face, rect, Align::Center, cell, scale, None);
+        }
+    }
+
+    /// A button showing a value on the right. Returns whether it was
+    /// pressed.
+    pub fn setting(&mut self, text: &str, value: &str) -> bool {
+        let rect = self.row(self.theme.row_height);
+        let (focused, clicked) = self.focusable(rect);
+        self.option(rect, text, focused);
+        let (face, scale) = self.face(value);
+        let tint = self.tint(focused);
+        self.text(face, rect, Align::Right, value, scale, tint);
+        clicked || (focused && self.confirm)
+    }
+
+    /// Returns whether the button was pressed
+    pub fn button(&mut self, text: &str) -> bool {
+        let rect = self.row(self.theme.row_height);
+        let (focused, clicked) = self.focusable(rect);
+        let tint = self.tint(focused);
+        let scale = self.theme.text_scale;
+        self.text(Face::Text, rect, Align::Center, text, scale, tint);
+        clicked || (focused && self.confirm)
+    }
+
+    /// Switches `value` on confirm, left or right. Returns whether it
+    /// changed.
+    pub fn toggle(&mut self, text: &str, value: &mut bool) -> bool {
+        let rect = self.row(self.theme.row_height);
+        let (focused, clicked) = self.focusable(rect);
+        let changed = clicked || (focused && (self.confirm || self.sideways != 0));
+        if changed {
+            *value = !*value;
+        }
+        self.option(rect, text, focused);
+        let scale = self.theme.text_scale;
+        let (state, tint) = if *value {
+            ("ON", self.tint(focused))
+        } else {
+            ("OFF", Some(self.theme.off))
+        };
+        self.text(Face::Text, rect, Align::Right, state, scale, tint);
+        changed
+    }
+
+    /// Picks one of `options` by cycling through them with left and right,
+    /// or forwards on confirm. Returns whether `selected` changed.
+    pub fn list(&mut self, text: &str, options: &[&str], selected: &mut usize) -> bool {
+        let rect = self.row(self.theme.row_height);
+        let (focused, clicked) = self.focusable(rect);
+        let count = options.len();
+        let before = *selected;
+        if count > 0 {
+            let step = if clicked || (focused && self.confirm) {
+                1
+            } else if focused {
+                self.sideways
+            } else {
+                0
+            };
+            *selected = (*selected as i64 + step).rem_euclid(count as i64) as usize;
+        }
+        self.option(rect, text, focused);
+        if let Some(option) = options.get(*selected) {
+            let tint = self.tint(focused);
+            let scale = self.theme.text_scale;
+            self.text(Face::Text, rect, Align::Right, option, scale, tint);
+        }
+        *selected != before
+    }
+
+    /// Moves `value` between `min` and `max` in steps of `step` with left
+    /// and right, or to where the slider is clicked or dragged. Returns
+    /// whether it changed.
+    pub fn slider(&mut self, text: &str, value: &mut f64, min: f64, max: f64, step: f64) -> bool {
+        let rect = self.row(self.theme.row_height);
+        let (focused, _) = self.focusable(rect);
+        let before = *value;
+        let [x, y, w, h] = rect;
+        let track = [x + w / 2., y + h / 2. - 2., w / 2. - 8., 4.];
+        if focused {
+            *value += self.sideways as f64 * step;
+        }
+        if let (true, Some(pointer)) = (self.input.pointer_pressed, &self.input.pointer) {
+            if contains(&rect, pointer) && pointer.x >= track[0] - 8. {
+                let fraction = (pointer.x - track[0]) / track[2];
+                *value = min + fraction * (max - min);
+            }
+        }
+        if step > 0. {
+            *value = min + ((*value - min) / step).round() * step;
+        }
+        *value = value.max(min).min(max);
+
+        self.option(rect, text, focused);
+        let fraction = if max > min {
+            (*value - min) / (max - min)
+        } else {
+            0.
+        };
+        self.draws.push(Draw::Rect {
+            rect: track,
+            color: self.theme.track,
+        });
+        self.draws.push(Draw::Rect {
+            rect: [track[0], track[1], track[2] * fraction, track[3]],
+            color: self.theme.fill,
+        });
+        self.draws.push(Draw::Frame {
+            frame: self.theme.knob,
+            x: track[0] + track[2] * fraction,
+            y: y + h / 2.,
+        });
+        (*value - before).abs() > f64::EPSILON
+    }
+
+    /// Draws what the widgets of the last update recorded
+    pub fn draw(&self, renderer: &dyn Renderer, skin: &Skin) {
+        for draw in self.draws.iter() {
+            match draw {
+                Draw::Rect { rect, color } => {
+                    let [x, y, w, h] = *rect;
+                    renderer.draw_rect(&color.to_css(), x, y, w, h);
+                }
+                Draw::Text {
+                    face,
+                    x,
+                    middle,
+                    text,
+                    style,
+                } => {
+                    let font = match face {
+                        Face::Text => skin.text,
+                        Face::Digits => skin.digits,
+                    };
+                    let y = middle - font.font.line_height * style.scale / 2.;
+                    renderer.draw_text(font, *x, y.round(), text, style);
+                }
+                Draw::Frame { frame, x, y } => renderer.draw_frame(skin.sheet, *frame, *x, *y),
+            }
+        }
+    }
+
+    /// Takes the next row of the column
+    fn row(&mut self, height: f64) -> [f64; 4] {
+        let rect = [self.column.x, self.column.y, self.column.width, height];
+        self.column.y += height;
+        rect
+    }
+
+    /// Counts a widget that can take focus. Returns whether it has focus
+    /// and whether it was clicked.
+    fn focusable(&mut self, rect: [f64; 4]) -> (bool, bool) {
+        let id = self.count;
+        self.count += 1;
+        let hovered = self
+            .input
+            .pointer
+            .as_ref()
+            .filter(|pointer| contains(&rect, pointer))
+            .is_some();
+        let clicked = hovered && self.clicked;
+        if hovered && (self.pointer_moved || clicked) {
+            self.focus = id;
+        }
+        let focused = self.focus == id;
+        if focused {
+            let [x, y, w, h] = rect;
+            self.draws.push(Draw::Rect {
+                rect: [x, y + 2., w, h - 4.],
+                color: self.theme.highlight,
+            });
+            self.draws.push(Draw::Frame {
+                frame: self.theme.cursor,
+                x: x - self.theme.padding / 2.,
+                y: y + h / 2.,
+            });
+        }
+        (focused, clicked)
+    }
+
+    /// Numbers are drawn with the digits font
+    fn face(&self, text: &str) -> (Face, f64) {
+        if text.starts_with(|c: char| c.is_ascii_digit()) {
+            (Face::Digits, self.theme.digit_scale)
+        } else {
+            (Face::Text, self.theme.text_scale)
+        }
+    }
+
+    fn tint(&self, focused: bool) -> Option<Color> {
+        Some(self.theme.focus).filter(|_| focused)
+    }
+
+    /// Label of an option, on the left of the row
+    fn option(&mut self, rect: [f64; 4], text: &str, focused: bool) {
+        let tint = self.tint(focused);
+        let scale = self.theme.text_scale;
+        self.text(Face::Text, rect, Align::Left, text, scale, tint);
+    }
+
+    fn text(
+        &mut self,
+        face: Face,
+        rect: [f64; 4],
+        align: Align,
+        text: &str,
+        scale: f64,
+        tint: Option<Color>,
+    ) {
+        let [x, y, w, h] = rect;
+        let x = match align {
+            Align::Left => x + 8.,
+            Align::Center => x + w / 2.,
+            Align::Right => x + w - 8.,
+        };
+        let mut style = TextStyle::scaled(scale).align(align);
+        style.tint = tint;
+        self.draws.push(Draw::Text {
+            face,
+            x,
+            middle: y + h / 2.,
+            text: String::from(text),
+            style,
+        });
+    }
+}
+
+fn contains(rect: &[f64; 4], point: &Vec2) -> bool {
+    let [x, y, w, h] = *rect;
+    point.x >= x && point.x < x + w && point.y >= y && point.y < y + h
+}
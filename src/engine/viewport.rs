@@ -0,0 +1,74 @@
+//! Where the game ends up on the canvas.
+//!
+//! The game always draws at a fixed virtual resolution. The viewport scales
+//! that up to the canvas, centered, and leaves letterbox bars where the
+//! aspect ratios differ.
+use crate::engine::math::Vec2;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScaleMode {
+    /// Scales by whole numbers only, so every virtual pixel is the same size
+    /// on screen. Screens smaller than the virtual resolution are fitted.
+    Integer,
+    /// Scales as large as the screen allows
+    Fit,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    /// Virtual resolution the game draws at
+    pub width: f64,
+    pub height: f64,
+    /// Size of the canvas in device pixels
+    pub screen_width: f64,
+    pub screen_height: f64,
+    /// Device pixels per CSS pixel
+    pub pixel_ratio: f64,
+    /// Device pixels per virtual pixel
+    pub scale: f64,
+    /// Top left corner of the game on the canvas in device pixels
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Viewport {
+    /// Places a virtual resolution of `width` by `height` on a canvas of
+    /// `screen_width` by `screen_height` device pixels
+    pub fn new(
+        width: f64,
+        height: f64,
+        screen_width: f64,
+        screen_height: f64,
+        pixel_ratio: f64,
+        mode: ScaleMode,
+    ) -> Self {
+        let fit = f64::min(screen_width / width, screen_height / height);
+        let scale = match mode {
+            ScaleMode::Integer if fit >= 1. => fit.floor(),
+            _ => fit,
+        };
+        Viewport {
+            width,
+            height,
+            screen_width,
+            screen_height,
+            pixel_ratio,
+            scale,
+            x: ((screen_width - width * scale) / 2.).floor(),
+            y: ((screen_height - height * scale) / 2.).floor(),
+        }
+    }
+
+    /// Converts a point in CSS pixels, relative to the top left of the
+    /// canvas, to virtual coordinates. Points on the letterbox bars are
+    /// outside the game and give `None`.
+    pub fn to_virtual(&self, x: f64, y: f64) -> Option<Vec2> {
+        let vx = (x * self.pixel_ratio - self.x) / self.scale;
+        let vy = (y * self.pixel_ratio - self.y) / self.scale;
+        if vx >= 0. && vx < self.width && vy >= 0. && vy < self.height {
+            Some(Vec2::new(vx, vy))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,540 @@
+//! Renderer drawing with WebGL 2.
+//!
+//! Draw calls only collect quads in a [SpriteBatch]. When the frame is
+//! presented, all vertices are uploaded into one buffer and drawn with one
+//! call per run of quads sharing a texture, instead of one call per sprite.
+//!
+//! Decals are stamped into a texture the size of the screen as they are
+//! added, which is drawn as a single quad every frame.
+use crate::engine::atlas::SpriteSheet;
+use crate::engine::batch::{Batch, SpriteBatch, TextureId, FLOATS_PER_VERTEX};
+use crate::engine::decals::DecalLayer;
+use crate::engine::renderer::Renderer;
+use crate::engine::tile_cache::{visible_tiles, TileLayerCache};
+use crate::engine::tiled::TileMap;
+use crate::engine::transform::Color;
+use crate::engine::viewport::Viewport;
+use std::cell::{Cell, RefCell, RefMut};
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    window, HtmlCanvasElement, HtmlImageElement, WebGl2RenderingContext as Gl, WebGlBuffer,
+    WebGlFramebuffer, WebGlProgram, WebGlShader, WebGlTexture, WebGlUniformLocation,
+    WebglLoseContext,
+};
+
+const VERTEX_SHADER: &str = r#"#version 300 es
+uniform vec2 u_resolution;
+uniform vec2 u_texture_size;
+in vec2 a_position;
+in vec2 a_uv;
+in vec4 a_tint;
+in float a_alpha;
+out vec2 v_uv;
+out vec4 v_tint;
+out float v_alpha;
+
+void main() {
+    vec2 clip = a_position / u_resolution * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+    v_uv = a_uv / u_texture_size;
+    v_tint = a_tint;
+    v_alpha = a_alpha;
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+uniform sampler2D u_texture;
+in vec2 v_uv;
+in vec4 v_tint;
+in float v_alpha;
+out vec4 color;
+
+void main() {
+    vec4 texel = texture(u_texture, v_uv);
+    color = vec4(mix(texel.rgb, v_tint.rgb, v_tint.a), texel.a * v_alpha);
+}
+"#;
+
+/// Layer of tile layers, below everything else
+const TILE_LAYER: u16 = 0;
+
+/// Layer of decals, on the floor below the sprites
+const DECAL_LAYER: u16 = 1;
+
+/// Layer of sprites, text and rectangles
+const SPRITE_LAYER: u16 = 2;
+
+struct Texture {
+    texture: WebGlTexture,
+    width: f32,
+    height: f32,
+}
+
+/// Texture the decals are stamped into. Its colors are premultiplied by
+/// alpha, so it is blended differently than images.
+struct Surface {
+    framebuffer: WebGlFramebuffer,
+    texture: TextureId,
+}
+
+pub struct WebGlRenderer {
+    gl: Gl,
+    buffer: WebGlBuffer,
+    resolution: WebGlUniformLocation,
+    texture_size: WebGlUniformLocation,
+    /// Textures by id, the first one being a single white pixel
+    textures: RefCell<Vec<Texture>>,
+    /// Ids of the textures uploaded for images, by image source
+    images: RefCell<HashMap<String, TextureId>>,
+    batch: RefCell<SpriteBatch>,
+    /// `None` if the browser can't render into textures, in which case the
+    /// decals are batched every frame
+    surface: Option<Surface>,
+    /// Decals to stamp into the surface when the frame is presented
+    stamps: RefCell<SpriteBatch>,
+    /// Whether draw calls go to the stamps instead of the frame
+    stamping: Cell<bool>,
+    /// Whether the surface is cleared before stamping
+    clear_surface: Cell<bool>,
+    /// Part of the canvas the game is drawn in, as `[x, y, width, height]`
+    viewport: Cell<[i32; 4]>,
+    width: f64,
+    height: f64,
+    background: Color,
+}
+
+/// The texture used for filled rectangles
+const WHITE: TextureId = 0;
+
+impl WebGlRenderer {
+    /// Draws at a virtual resolution of `width` by `height`. Returns `None`
+    /// if the browser does not support WebGL 2, in which case the canvas can
+    /// still be used by the [CanvasRenderer](crate::engine::renderer::CanvasRenderer).
+    pub fn new(
+        canvas: &HtmlCanvasElement,
+        width: f64,
+        height: f64,
+        background: Color,
+    ) -> Option<Self> {
+        if !WebGlRenderer::supported() {
+            return None;
+        }
+        WebGlRenderer::create(canvas, width, height, background)
+    }
+
+    /// Whether the browser supports WebGL 2. Probed on a canvas of its own,
+    /// because a canvas that handed out a WebGL context can't hand out a 2D
+    /// context anymore. The probe's context is lost right away, as browsers
+    /// only keep a few contexts alive.
+    pub fn supported() -> bool {
+        let scratch = window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.create_element("canvas").ok())
+            .and_then(|e| e.dyn_into::<HtmlCanvasElement>().ok());
+        let gl = match scratch.and_then(|c| c.get_context("webgl2").ok().flatten()) {
+            Some(gl) => gl.unchecked_into::<Gl>(),
+            None => return false,
+        };
+        if let Ok(Some(extension)) = gl.get_extension("WEBGL_lose_context") {
+            extension
+                .unchecked_into::<WebglLoseContext>()
+                .lose_context();
+        }
+        true
+    }
+
+    fn create(
+        canvas: &HtmlCanvasElement,
+        width: f64,
+        height: f64,
+        background: Color,
+    ) -> Option<Self> {
+        let gl = canvas.get_context("webgl2").ok()??.dyn_into::<Gl>().ok()?;
+
+        let program = link_program(&gl)?;
+        gl.use_program(Some(&program));
+
+        let buffer = gl.create_buffer()?;
+        let vertex_array = gl.create_vertex_array()?;
+        gl.bind_vertex_array(Some(&vertex_array));
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&buffer));
+        let stride = (FLOATS_PER_VERTEX * 4) as i32;
+        let attributes = [
+            ("a_position", 2, 0),
+            ("a_uv", 2, 2),
+            ("a_tint", 4, 4),
+            ("a_alpha", 1, 8),
+        ];
+        for (name, size, offset) in attributes.iter() {
+            let location = gl.get_attrib_location(&program, name);
+            if location < 0 {
+                return None;
+            }
+            gl.enable_vertex_attrib_array(location as u32);
+            gl.vertex_attrib_pointer_with_i32(
+                location as u32,
+                *size,
+                Gl::FLOAT,
+                false,
+                stride,
+                offset * 4,
+            );
+        }
+
+        let resolution = gl.get_uniform_location(&program, "u_resolution")?;
+        let texture_size = gl.get_uniform_location(&program, "u_texture_size")?;
+        gl.uniform2f(Some(&resolution), width as f32, height as f32);
+        gl.viewport(0, 0, width as i32, height as i32);
+        gl.enable(Gl::BLEND);
+        blend_images(&gl);
+
+        let white = create_texture(&gl)?;
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D,
+            0,
+            Gl::RGBA as i32,
+            1,
+            1,
+            0,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(&[255, 255, 255, 255]),
+        )
+        .ok()?;
+
+        let mut textures = vec![Texture {
+            texture: white,
+            width: 1.,
+            height: 1.,
+        }];
+        let surface =
+            create_surface(&gl, width as i32, height as i32).map(|(framebuffer, texture)| {
+                textures.push(Texture {
+                    texture,
+                    width: width as f32,
+                    height: height as f32,
+                });
+                Surface {
+                    framebuffer,
+                    texture: textures.len() - 1,
+                }
+            });
+
+        let mut batch = SpriteBatch::new();
+        batch.set_layer(SPRITE_LAYER);
+
+        Some(WebGlRenderer {
+            gl,
+            buffer,
+            resolution,
+            texture_size,
+            textures: RefCell::new(textures),
+            images: RefCell::new(HashMap::new()),
+            batch: RefCell::new(batch),
+            surface,
+            stamps: RefCell::new(SpriteBatch::new()),
+            stamping: Cell::new(false),
+            clear_surface: Cell::new(false),
+            viewport: Cell::new([0, 0, width as i32, height as i32]),
+            width,
+            height,
+            background,
+        })
+    }
+
+    /// Batch the draw calls go to
+    fn batch(&self) -> RefMut<'_, SpriteBatch> {
+        if self.stamping.get() {
+            self.stamps.borrow_mut()
+        } else {
+            self.batch.borrow_mut()
+        }
+    }
+
+    /// Stamps the decals drawn since the last frame into the surface,
+    /// clearing it first if decals were removed
+    fn stamp(&self, surface: &Surface) {
+        let clear = self.clear_surface.replace(false);
+        let (vertices, batches) = self.stamps.borrow_mut().finish();
+        if !clear && batches.is_empty() {
+            return;
+        }
+        let gl = &self.gl;
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&surface.framebuffer));
+        gl.viewport(0, 0, self.width as i32, self.height as i32);
+        if clear {
+            gl.clear_color(0., 0., 0., 0.);
+            gl.clear(Gl::COLOR_BUFFER_BIT);
+        }
+        self.draw_batches(&vertices, &batches);
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        let [x, y, width, height] = self.viewport.get();
+        gl.viewport(x, y, width, height);
+    }
+
+    /// Uploads the vertices and draws them, one call per batch
+    fn draw_batches(&self, vertices: &[f32], batches: &[Batch]) {
+        if batches.is_empty() {
+            return;
+        }
+        let gl = &self.gl;
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.buffer));
+        gl.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &js_sys::Float32Array::from(vertices),
+            Gl::STREAM_DRAW,
+        );
+        let surface = self.surface.as_ref().map(|surface| surface.texture);
+        let textures = self.textures.borrow();
+        for batch in batches {
+            let texture = &textures[batch.texture];
+            gl.bind_texture(Gl::TEXTURE_2D, Some(&texture.texture));
+            gl.uniform2f(Some(&self.texture_size), texture.width, texture.height);
+            if surface == Some(batch.texture) {
+                gl.blend_func(Gl::ONE, Gl::ONE_MINUS_SRC_ALPHA);
+            }
+            gl.draw_arrays(Gl::TRIANGLES, batch.first as i32, batch.count as i32);
+            if surface == Some(batch.texture) {
+                blend_images(gl);
+            }
+        }
+    }
+
+    /// Texture of an image, uploaded the first time the image is drawn.
+    /// Returns `None` if the image can't be uploaded, e.g. because the
+    /// context was lost.
+    fn texture(&self, image: &HtmlImageElement) -> Option<TextureId> {
+        let src = image.src();
+        if let Some(id) = self.images.borrow().get(&src) {
+            return Some(*id);
+        }
+        let texture = create_texture(&self.gl)?;
+        self.gl
+            .tex_image_2d_with_u32_and_u32_and_html_image_element(
+                Gl::TEXTURE_2D,
+                0,
+                Gl::RGBA as i32,
+                Gl::RGBA,
+                Gl::UNSIGNED_BYTE,
+                image,
+            )
+            .ok()?;
+        let mut textures = self.textures.borrow_mut();
+        textures.push(Texture {
+            texture,
+            width: image.natural_width() as f32,
+            height: image.natural_height() as f32,
+        });
+        let id = textures.len() - 1;
+        self.images.borrow_mut().insert(src, id);
+        Some(id)
+    }
+}
+
+impl Renderer for WebGlRenderer {
+    fn width(&self) -> f64 {
+        self.width
+    }
+
+    fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// Draws into the part of the canvas the game is placed in. Clearing
+    /// still covers the whole canvas.
+    fn resize(&self, viewport: &Viewport) {
+        let [x, y, width, height] = [
+            viewport.x as i32,
+            (viewport.screen_height - viewport.y - viewport.height * viewport.scale) as i32,
+            (viewport.width * viewport.scale) as i32,
+            (viewport.height * viewport.scale) as i32,
+        ];
+        self.viewport.set([x, y, width, height]);
+        self.gl.viewport(x, y, width, height);
+    }
+
+    fn clear(&self) {
+        let Color { r, g, b, a } = self.background;
+        self.gl.clear_color(
+            f32::from(r) / 255.,
+            f32::from(g) / 255.,
+            f32::from(b) / 255.,
+            a as f32,
+        );
+        self.gl.clear(Gl::COLOR_BUFFER_BIT);
+    }
+
+    fn draw_image(
+        &self,
+        image: &HtmlImageElement,
+        src: [f64; 4],
+        matrix: [f64; 6],
+        alpha: f64,
+        tint: Option<Color>,
+    ) {
+        if let Some(texture) = self.texture(image) {
+            self.batch().push(texture, src, matrix, alpha, tint);
+        }
+    }
+
+    /// Only the fill styles [Color::from_css] reads are supported, others
+    /// are drawn in white
+    fn draw_rect(&self, fill_style: &str, x: f64, y: f64, w: f64, h: f64) {
+        let color = Color::from_css(fill_style).unwrap_or(Color::WHITE);
+        self.batch().push_rect(WHITE, x, y, w, h, color);
+    }
+
+    /// Tiles are batched like sprites, so the cached chunks are not used
+    fn draw_tile_layer(&self, cache: &TileLayerCache, map: &TileMap, tileset: &HtmlImageElement) {
+        let texture = match self.texture(tileset) {
+            Some(texture) => texture,
+            None => return,
+        };
+        let mut batch = self.batch.borrow_mut();
+        batch.set_layer(TILE_LAYER);
+        for (src, [x, y, w, h]) in visible_tiles(map, cache.layer(), self.width, self.height) {
+            batch.push(
+                texture,
+                src,
+                [w / src[2], 0., 0., h / src[3], x, y],
+                1.,
+                None,
+            );
+        }
+        batch.set_layer(SPRITE_LAYER);
+    }
+
+    /// New decals are stamped into the surface when the frame is presented,
+    /// and the surface is drawn on its own layer with the fading decals
+    fn draw_decals(&self, decals: &DecalLayer, sheet: &SpriteSheet) {
+        self.batch.borrow_mut().set_layer(DECAL_LAYER);
+        match &self.surface {
+            Some(surface) => {
+                let (clear, unstamped) = decals.take_unstamped_into(surface.texture);
+                if clear {
+                    self.clear_surface.set(true);
+                }
+                self.stamping.set(true);
+                for decal in decals.iter().skip(unstamped.start) {
+                    decal.draw(self, sheet, 1.);
+                }
+                self.stamping.set(false);
+                // Textures rendered into are upside down
+                self.batch.borrow_mut().push(
+                    surface.texture,
+                    [0., 0., self.width, self.height],
+                    [1., 0., 0., -1., 0., self.height],
+                    1.,
+                    None,
+                );
+                for (decal, fade) in decals.fading() {
+                    decal.draw(self, sheet, fade);
+                }
+            }
+            None => decals.draw(self, sheet),
+        }
+        self.batch.borrow_mut().set_layer(SPRITE_LAYER);
+    }
+
+    fn present(&self) {
+        self.gl.uniform2f(
+            Some(&self.resolution),
+            self.width as f32,
+            self.height as f32,
+        );
+        if let Some(surface) = &self.surface {
+            self.stamp(surface);
+        }
+        let (vertices, batches) = self.batch.borrow_mut().finish();
+        self.draw_batches(&vertices, &batches);
+    }
+}
+
+/// Blends images over what is drawn. Alpha is added up the same way as
+/// color, so textures rendered into end up premultiplied.
+fn blend_images(gl: &Gl) {
+    gl.blend_func_separate(
+        Gl::SRC_ALPHA,
+        Gl::ONE_MINUS_SRC_ALPHA,
+        Gl::ONE,
+        Gl::ONE_MINUS_SRC_ALPHA,
+    );
+}
+
+/// Creates a transparent texture of `width` by `height` with a framebuffer
+/// to render into it
+fn create_surface(gl: &Gl, width: i32, height: i32) -> Option<(WebGlFramebuffer, WebGlTexture)> {
+    let texture = create_texture(gl)?;
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        Gl::TEXTURE_2D,
+        0,
+        Gl::RGBA as i32,
+        width,
+        height,
+        0,
+        Gl::RGBA,
+        Gl::UNSIGNED_BYTE,
+        None,
+    )
+    .ok()?;
+    let framebuffer = gl.create_framebuffer()?;
+    gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&framebuffer));
+    gl.framebuffer_texture_2d(
+        Gl::FRAMEBUFFER,
+        Gl::COLOR_ATTACHMENT0,
+        Gl::TEXTURE_2D,
+        Some(&texture),
+        0,
+    );
+    let complete = gl.check_framebuffer_status(Gl::FRAMEBUFFER) == Gl::FRAMEBUFFER_COMPLETE;
+    gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+    Some((framebuffer, texture)).filter(|_| complete)
+}
+
+/// Creates and binds a texture sampled without smoothing, so pixel art stays
+/// sharp when scaled
+fn create_texture(gl: &Gl) -> Option<WebGlTexture> {
+    let texture = gl.create_texture()?;
+    gl.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MIN_FILTER, Gl::NEAREST as i32);
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MAG_FILTER, Gl::NEAREST as i32);
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE as i32);
+    Some(texture)
+}
+
+fn compile_shader(gl: &Gl, kind: u32, source: &str) -> Option<WebGlShader> {
+    let shader = gl.create_shader(kind)?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+    if gl
+        .get_shader_parameter(&shader, Gl::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Some(shader)
+    } else {
+        web_sys::console::log_1(&gl.get_shader_info_log(&shader).unwrap_or_default().into());
+        None
+    }
+}
+
+fn link_program(gl: &Gl) -> Option<WebGlProgram> {
+    let vertex = compile_shader(gl, Gl::VERTEX_SHADER, VERTEX_SHADER)?;
+    let fragment = compile_shader(gl, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER)?;
+    let program = gl.create_program()?;
+    gl.attach_shader(&program, &vertex);
+    gl.attach_shader(&program, &fragment);
+    gl.link_program(&program);
+    if gl
+        .get_program_parameter(&program, Gl::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Some(program)
+    } else {
+        None
+    }
+}
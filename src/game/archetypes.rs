@@ -0,0 +1,93 @@
+//! Enemy archetypes loaded from `assets/enemies.json`.
+//!
+//! Every blob type is described by data, so new kinds of enemies can be
+//! added without touching the game code. Spawn points spawn archetypes that
+//! share their `color`, picked randomly by `spawn_weight`.
+use crate::engine::error::EngineError;
+use crate::engine::json::from_js;
+use crate::engine::random::random;
+use crate::game::bullets::BulletSpec;
+use crate::game::power_ups::DropTable;
+use serde::Deserialize;
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Deserialize)]
+pub struct EnemyArchetypes {
+    pub archetypes: Vec<EnemyArchetype>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnemyArchetype {
+    pub name: String,
+    /// Palette index shared with spawn points and splatter
+    pub color: u8,
+    #[serde(default = "default_spawn_weight")]
+    pub spawn_weight: f64,
+    /// Clips played while walking left and right
+    pub walk_left: String,
+    pub walk_right: String,
+    pub health: i32,
+    pub speed: f64,
+    /// Chance per frame of firing
+    pub fire_chance: f64,
+    pub bullets: BulletSpec,
+    pub score: i32,
+    #[serde(default)]
+    pub drops: DropTable,
+    pub behaviour: Behaviour,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    /// Random walk
+    Wander,
+    /// Walk towards the nearest player
+    Chase,
+    /// Stand still and shoot
+    Sentry,
+}
+
+fn default_spawn_weight() -> f64 {
+    1.0
+}
+
+impl FromStr for EnemyArchetypes {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+impl EnemyArchetypes {
+    pub fn new_from_json(json: &JsValue) -> Result<EnemyArchetypes, EngineError> {
+        from_js(json)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.archetypes.iter().position(|a| a.name == name)
+    }
+
+    /// Picks a random archetype with the given color, weighted by `spawn_weight`
+    pub fn pick_for_color(&self, color: u8) -> Option<usize> {
+        let total: f64 = self
+            .archetypes
+            .iter()
+            .filter(|a| a.color == color)
+            .map(|a| a.spawn_weight)
+            .sum();
+        let mut roll = random() * total;
+        for (idx, a) in self.archetypes.iter().enumerate() {
+            if a.color != color {
+                continue;
+            }
+            if roll < a.spawn_weight {
+                return Some(idx);
+            }
+            roll -= a.spawn_weight;
+        }
+        None
+    }
+}
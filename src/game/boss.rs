@@ -0,0 +1,295 @@
+//! Data driven bosses loaded from `assets/bosses.json`.
+//!
+//! A boss runs through a list of phases. Each phase is a script of steps that
+//! loops until the health of the boss drops below the threshold of the next
+//! phase. Steps can be preceded by a telegraph, where the boss shows its
+//! telegraph sprite to warn the players before acting.
+use crate::engine::atlas::FrameId;
+use crate::engine::json::from_js;
+use crate::engine::math::{vec2_distance, Vec2};
+use crate::engine::random::random;
+use crate::game::bullets::{Bullet, BulletSpec, Shooter};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Deserialize)]
+pub struct BossDefinitions {
+    pub bosses: Vec<BossDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BossDefinition {
+    pub health: i32,
+    pub spawn: (f64, f64),
+    /// Area the boss is allowed to move in as `(min x, min y, max x, max y)`
+    pub arena: (f64, f64, f64, f64),
+    pub sprite: FrameId,
+    pub telegraph_sprite: FrameId,
+    pub phases: Vec<BossPhase>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BossPhase {
+    /// The phase starts when health drops to this fraction of full health
+    pub health: f64,
+    /// Ticks the boss flashes before starting the phase
+    #[serde(default)]
+    pub transition: u32,
+    /// Step to continue from after the last step of the phase
+    #[serde(default)]
+    pub loop_from: usize,
+    pub steps: Vec<BossStep>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BossStep {
+    #[serde(flatten)]
+    pub action: BossAction,
+    /// Ticks of warning before the action starts
+    #[serde(default)]
+    pub telegraph: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BossAction {
+    /// Move down into the arena
+    Enter {
+        y: f64,
+        speed: f64,
+    },
+    /// Walk sideways to a random x within `range` of the current position
+    Strafe {
+        speed: f64,
+        range: f64,
+    },
+    /// Rush towards the nearest player
+    Charge {
+        speed: f64,
+        ticks: u32,
+    },
+    /// Spawn minions around the boss
+    Summon {
+        #[serde(default)]
+        archetype: Option<String>,
+        count: u32,
+    },
+    /// Fire `volleys` volleys `interval` ticks apart
+    Fire {
+        bullets: BulletSpec,
+        #[serde(default = "default_volleys")]
+        volleys: u32,
+        #[serde(default)]
+        interval: u32,
+    },
+    Wait {
+        ticks: u32,
+    },
+}
+
+fn default_volleys() -> u32 {
+    1
+}
+
+impl BossDefinitions {
+    pub fn new_from_json(json: &JsValue) -> BossDefinitions {
+        from_js(json).unwrap()
+    }
+}
+
+impl FromStr for BossDefinitions {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Minions requested by a boss
+pub struct Summon {
+    pub archetype: Option<String>,
+    pub count: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Boss {
+    pub pos: Vec2,
+    pub health: i32,
+    /// Index into the loaded boss definitions
+    pub kind: usize,
+    pub phase: usize,
+    pub step: usize,
+    /// Ticks spent in the current step
+    pub timer: u32,
+    /// Ticks left of the phase transition
+    pub transition: u32,
+    pub tx: f64,
+    pub charge: Vec2,
+    pub spin: f64,
+    /// Ticks until piercing bullets can hit the boss again
+    #[serde(default)]
+    pub hit_cooldown: u32,
+}
+
+impl Boss {
+    pub fn new(kind: usize, def: &BossDefinition) -> Boss {
+        Boss {
+            pos: Vec2::new(def.spawn.0, def.spawn.1),
+            health: def.health,
+            kind,
+            phase: 0,
+            step: 0,
+            timer: 0,
+            transition: def.phases.first().map(|p| p.transition).unwrap_or(0),
+            tx: def.spawn.0,
+            charge: Vec2::new(0., 0.),
+            spin: 0.,
+            hit_cooldown: 0,
+        }
+    }
+
+    fn current_step<'a>(&self, def: &'a BossDefinition) -> Option<&'a BossStep> {
+        def.phases.get(self.phase)?.steps.get(self.step)
+    }
+
+    pub fn in_transition(&self) -> bool {
+        self.transition > 0
+    }
+
+    pub fn telegraphing(&self, def: &BossDefinition) -> bool {
+        match self.current_step(def) {
+            Some(step) => self.timer < step.telegraph,
+            None => false,
+        }
+    }
+
+    pub fn charging(&self, def: &BossDefinition) -> bool {
+        match self.current_step(def) {
+            Some(BossStep {
+                action: BossAction::Charge { .. },
+                telegraph,
+            }) => self.timer >= *telegraph,
+            _ => false,
+        }
+    }
+
+    /// Runs one tick of the boss script. Bullets are fired directly, while
+    /// summoned minions are returned for the game to spawn.
+    pub fn update(
+        &mut self,
+        def: &BossDefinition,
+        targets: &[Vec2],
+        bullets: &mut Vec<Bullet>,
+    ) -> Vec<Summon> {
+        let mut summons = Vec::new();
+        self.hit_cooldown = self.hit_cooldown.saturating_sub(1);
+
+        let fraction = self.health as f64 / def.health as f64;
+        if let Some(phase) = def.phases.iter().rposition(|p| fraction <= p.health) {
+            if phase > self.phase {
+                self.phase = phase;
+                self.step = 0;
+                self.timer = 0;
+                self.transition = def.phases[phase].transition;
+            }
+        }
+
+        if self.transition > 0 {
+            self.transition -= 1;
+            return summons;
+        }
+
+        let phase = match def.phases.get(self.phase) {
+            Some(phase) => phase,
+            None => return summons,
+        };
+        let step = match phase.steps.get(self.step) {
+            Some(step) => step,
+            None => return summons,
+        };
+
+        self.timer += 1;
+        if self.timer <= step.telegraph {
+            return summons;
+        }
+        let t = self.timer - step.telegraph;
+
+        let target = targets
+            .iter()
+            .min_by(|a, b| {
+                vec2_distance(a, &self.pos)
+                    .partial_cmp(&vec2_distance(b, &self.pos))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .cloned()
+            .unwrap_or_else(|| Vec2::new(self.pos.x, self.pos.y + 1.));
+
+        let (min_x, min_y, max_x, max_y) = def.arena;
+        let done = match &step.action {
+            BossAction::Enter { y, speed } => {
+                if self.pos.y < *y {
+                    self.pos.y += speed;
+                    false
+                } else {
+                    true
+                }
+            }
+            BossAction::Strafe { speed, range } => {
+                if t == 1 {
+                    self.tx = (self.pos.x + range * (1. - 2. * random()))
+                        .max(min_x)
+                        .min(max_x);
+                }
+                if self.pos.x < self.tx - speed {
+                    self.pos.x += speed;
+                    false
+                } else if self.pos.x > self.tx + speed {
+                    self.pos.x -= speed;
+                    false
+                } else {
+                    true
+                }
+            }
+            BossAction::Charge { speed, ticks } => {
+                if t == 1 {
+                    self.charge = Vec2::new(target.x - self.pos.x, target.y - self.pos.y);
+                    self.charge.normalize();
+                    self.charge.scale(*speed);
+                }
+                self.pos.add(&self.charge);
+                self.pos.x = self.pos.x.max(min_x).min(max_x);
+                self.pos.y = self.pos.y.max(min_y).min(max_y);
+                t >= *ticks
+            }
+            BossAction::Summon { archetype, count } => {
+                summons.push(Summon {
+                    archetype: archetype.clone(),
+                    count: *count,
+                });
+                true
+            }
+            BossAction::Fire {
+                bullets: spec,
+                volleys,
+                interval,
+            } => {
+                let interval = u32::max(1, *interval);
+                if (t - 1).is_multiple_of(interval) {
+                    spec.emit(&self.pos, &target, Shooter::Boss, &mut self.spin, bullets);
+                }
+                t > (volleys.max(&1) - 1) * interval
+            }
+            BossAction::Wait { ticks } => t >= *ticks,
+        };
+
+        if done {
+            self.timer = 0;
+            self.step += 1;
+            if self.step >= phase.steps.len() {
+                self.step = phase.loop_from;
+            }
+        }
+        summons
+    }
+}
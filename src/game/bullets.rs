@@ -0,0 +1,251 @@
+//! Bullets and the patterns they are fired in.
+//!
+//! Anything that shoots describes its bullets with a [BulletSpec]: a pattern
+//! deciding the directions, plus the speed, damage, sprite and behaviour of
+//! each bullet. Specs can be loaded from JSON, so enemies and bosses can use
+//! the same patterns as the players.
+use crate::engine::atlas::FrameId;
+use crate::engine::math::Vec2;
+use crate::engine::random::random;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub enum Shooter {
+    Player(u8),
+    Enemy(usize),
+    Boss,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bullet {
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub shooter: Shooter,
+    /// Ticks until the bullet disappears
+    pub lifetime: u32,
+    pub damage: i32,
+    pub sprite: FrameId,
+    /// Ticks until the bullet is fired. Until then it stays put and hidden.
+    pub delay: u32,
+    /// Change in speed per tick
+    pub acceleration: f64,
+    pub max_speed: f64,
+    /// Radians per tick the bullet can turn towards its target
+    pub homing: f64,
+    /// Keep going after hitting something
+    pub pierce: bool,
+    /// Rotate the sprite to face the direction of travel
+    pub oriented: bool,
+    /// Radius of the explosion when the bullet hits or runs out of lifetime
+    pub splash: f64,
+    /// Pixels per tick the bullet pushes back whatever it hits
+    pub knockback: f64,
+    /// Ticks a target hit by a piercing bullet can't be hit by piercing
+    /// bullets again
+    #[serde(default)]
+    pub hit_cooldown: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Pattern {
+    /// A single bullet at the target
+    Aimed,
+    /// `count` bullets fanned out over `angle` radians around the target
+    Spread { count: u8, angle: f64 },
+    /// `count` bullets evenly spaced in all directions
+    Ring { count: u8 },
+    /// `arms` evenly spaced bullets, turned `rotation` radians more each time
+    /// the pattern is fired
+    Spiral { arms: u8, rotation: f64 },
+    /// `count` bullets at the target, `interval` ticks apart
+    AimedBurst { count: u8, interval: u32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulletSpec {
+    #[serde(flatten)]
+    pub pattern: Pattern,
+    pub speed: f64,
+    pub sprite: FrameId,
+    #[serde(default = "default_damage")]
+    pub damage: i32,
+    #[serde(default = "default_lifetime")]
+    pub lifetime: u32,
+    #[serde(default)]
+    pub delay: u32,
+    #[serde(default)]
+    pub acceleration: f64,
+    #[serde(default = "default_max_speed")]
+    pub max_speed: f64,
+    #[serde(default)]
+    pub homing: f64,
+    #[serde(default)]
+    pub pierce: bool,
+    #[serde(default)]
+    pub oriented: bool,
+    #[serde(default)]
+    pub splash: f64,
+    #[serde(default = "default_knockback")]
+    pub knockback: f64,
+    /// Radians each bullet can be off at random
+    #[serde(default)]
+    pub jitter: f64,
+    /// Ticks a target hit by a piercing bullet can't be hit by piercing
+    /// bullets again, so beams deal the same damage however many of their
+    /// bullets overlap the target
+    #[serde(default)]
+    pub hit_cooldown: u32,
+}
+
+fn default_damage() -> i32 {
+    1
+}
+
+fn default_lifetime() -> u32 {
+    300
+}
+
+fn default_max_speed() -> f64 {
+    12.0
+}
+
+fn default_knockback() -> f64 {
+    3.0
+}
+
+impl BulletSpec {
+    pub fn new(pattern: Pattern, speed: f64, sprite: FrameId) -> Self {
+        BulletSpec {
+            pattern,
+            speed,
+            sprite,
+            damage: default_damage(),
+            lifetime: default_lifetime(),
+            delay: 0,
+            acceleration: 0.0,
+            max_speed: default_max_speed(),
+            homing: 0.0,
+            pierce: false,
+            oriented: false,
+            splash: 0.0,
+            knockback: default_knockback(),
+            jitter: 0.0,
+            hit_cooldown: 0,
+        }
+    }
+
+    /// Fires the pattern from `from` towards `target`.
+    ///
+    /// `spin` is the rotation state of the shooter, used by spirals.
+    pub fn emit(
+        &self,
+        from: &Vec2,
+        target: &Vec2,
+        shooter: Shooter,
+        spin: &mut f64,
+        bullets: &mut Vec<Bullet>,
+    ) {
+        let aim = f64::atan2(target.y - from.y, target.x - from.x);
+        let mut fire = |angle: f64, delay: u32| {
+            let angle = if self.jitter > 0.0 {
+                angle + self.jitter * (2. * random() - 1.)
+            } else {
+                angle
+            };
+            bullets.push(Bullet {
+                pos: from.clone(),
+                vel: Vec2::new(self.speed * angle.cos(), self.speed * angle.sin()),
+                shooter,
+                lifetime: self.lifetime,
+                damage: self.damage,
+                sprite: self.sprite,
+                delay: self.delay + delay,
+                acceleration: self.acceleration,
+                max_speed: self.max_speed,
+                homing: self.homing,
+                pierce: self.pierce,
+                oriented: self.oriented,
+                splash: self.splash,
+                knockback: self.knockback,
+                hit_cooldown: self.hit_cooldown,
+            })
+        };
+
+        match self.pattern {
+            Pattern::Aimed => fire(aim, 0),
+            Pattern::Spread { count, angle } => {
+                if count < 2 {
+                    fire(aim, 0);
+                } else {
+                    for i in 0..count {
+                        fire(aim - angle / 2. + angle * i as f64 / (count - 1) as f64, 0);
+                    }
+                }
+            }
+            Pattern::Ring { count } => {
+                for i in 0..count {
+                    fire(aim + 2. * PI * i as f64 / count as f64, 0);
+                }
+            }
+            Pattern::Spiral { arms, rotation } => {
+                for i in 0..arms {
+                    fire(*spin + 2. * PI * i as f64 / arms as f64, 0);
+                }
+                *spin += rotation;
+            }
+            Pattern::AimedBurst { count, interval } => {
+                for i in 0..count {
+                    fire(aim, i as u32 * interval);
+                }
+            }
+        }
+    }
+}
+
+impl Bullet {
+    pub fn waiting(&self) -> bool {
+        self.delay > 0
+    }
+
+    /// Whether the bullet can hit a target whose hit cooldown has `cooldown`
+    /// ticks left. Only piercing bullets care about the cooldown.
+    pub fn can_hit(&self, cooldown: u32) -> bool {
+        !self.pierce || cooldown == 0
+    }
+
+    /// Moves the bullet one tick, turning towards `target` if it is homing
+    pub fn advance(&mut self, target: Option<&Vec2>) {
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+        self.lifetime = self.lifetime.saturating_sub(1);
+
+        if let Some(target) = target {
+            if self.homing > 0.0 {
+                let heading = f64::atan2(self.vel.y, self.vel.x);
+                let wanted = f64::atan2(target.y - self.pos.y, target.x - self.pos.x);
+                let mut turn = wanted - heading;
+                while turn > PI {
+                    turn -= 2. * PI;
+                }
+                while turn < -PI {
+                    turn += 2. * PI;
+                }
+                self.vel.rotate(turn.max(-self.homing).min(self.homing));
+            }
+        }
+
+        if self.acceleration != 0.0 {
+            let speed = self.vel.length();
+            if speed > 0.0 {
+                let new_speed = (speed + self.acceleration).max(0.0).min(self.max_speed);
+                self.vel.scale(new_speed / speed);
+            }
+        }
+
+        self.pos.add(&self.vel);
+    }
+}
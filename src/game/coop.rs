@@ -0,0 +1,130 @@
+//! Co-op rules loaded from `assets/difficulty.json`.
+//!
+//! A player whose health drops to zero is downed rather than dead. The partner
+//! can revive them by standing next to them for a while, otherwise they bleed
+//! out. Dead players respawn after the wave ends, using up one of the
+//! continues shared by both players.
+use crate::engine::json::from_js;
+use crate::engine::math::{vec2_distance, Vec2};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Deserialize)]
+pub struct CoopConfig {
+    /// Name of the difficulty to play on
+    pub default: String,
+    /// Places where dead players can come back into the game
+    pub respawn_points: Vec<(f64, f64)>,
+    pub difficulties: Vec<Difficulty>,
+    /// Player bullets hurt the other player, for versus-style play
+    #[serde(default)]
+    pub friendly_fire: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Difficulty {
+    pub name: String,
+    /// Ticks a downed player lasts before dying
+    pub bleed_out: u32,
+    /// Ticks the partner has to stay close to revive a downed player
+    pub revive_ticks: u32,
+    pub revive_radius: f64,
+    /// Health of a revived player
+    pub revive_health: i32,
+    /// Respawns shared by both players
+    pub continues: u32,
+    /// Health of a respawned player
+    pub respawn_health: i32,
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlayerState {
+    Alive,
+    Downed {
+        /// Ticks left before the player dies
+        bleed_out: u32,
+        /// Ticks the partner has spent reviving
+        revive: u32,
+    },
+    Dead,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty {
+            name: String::from("normal"),
+            bleed_out: 600,
+            revive_ticks: 90,
+            revive_radius: 24.0,
+            revive_health: 5,
+            continues: 3,
+            respawn_health: 15,
+        }
+    }
+}
+
+impl FromStr for CoopConfig {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+impl CoopConfig {
+    pub fn new_from_json(json: &JsValue) -> CoopConfig {
+        from_js(json).unwrap()
+    }
+
+    pub fn difficulty(&self, name: &str) -> Option<&Difficulty> {
+        self.difficulties.iter().find(|d| d.name == name)
+    }
+
+    /// Returns the respawn point furthest away from any of the `threats`
+    pub fn safest_respawn_point(&self, threats: &[Vec2]) -> Option<Vec2> {
+        let safety = |p: &Vec2| {
+            threats
+                .iter()
+                .map(|t| vec2_distance(t, p))
+                .fold(f64::MAX, f64::min)
+        };
+        self.respawn_points
+            .iter()
+            .map(|(x, y)| Vec2::new(*x, *y))
+            .max_by(|a, b| {
+                safety(a)
+                    .partial_cmp(&safety(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    }
+}
+
+impl PlayerState {
+    /// Advances a downed player by one tick. `reviver` is the position of a
+    /// living partner, if there is one.
+    pub fn update(&mut self, pos: &Vec2, reviver: Option<&Vec2>, difficulty: &Difficulty) {
+        if let PlayerState::Downed { bleed_out, revive } = self {
+            let reviving = match reviver {
+                Some(r) => vec2_distance(r, pos) < difficulty.revive_radius,
+                None => false,
+            };
+            if reviving {
+                *revive += 1;
+            } else {
+                *revive = 0;
+                *bleed_out = bleed_out.saturating_sub(1);
+            }
+            if *bleed_out == 0 {
+                *self = PlayerState::Dead;
+            }
+        }
+    }
+
+    pub fn revived(&self, difficulty: &Difficulty) -> bool {
+        match self {
+            PlayerState::Downed { revive, .. } => *revive >= difficulty.revive_ticks,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Damage dealt by bullets and explosions.
+//!
+//! Collision checks don't change any health directly. They produce damage
+//! events which are applied afterwards, so direct hits, splash damage and
+//! friendly fire all go through the same path.
+//!
+//! Players are invulnerable for a moment after taking a hit. Enemies and
+//! bosses aren't, so every pellet of a shotgun blast counts and rapid fire
+//! is worth it. Piercing bullets, which would hit a target on every tick
+//! they overlap it, give their targets a hit cooldown instead, see
+//! [take_cooldown].
+use crate::engine::math::Vec2;
+use crate::game::bullets::Shooter;
+
+/// Ticks a player can't be hurt after taking a hit. Enemies have no such
+/// window, see above.
+pub const INVULNERABILITY_TICKS: u32 = 60;
+
+/// How quickly knock back wears off, per tick
+pub const KNOCKBACK_FRICTION: f64 = 0.8;
+
+#[derive(Copy, Clone)]
+pub enum Target {
+    Player(u8),
+    /// Index into the enemies
+    Enemy(usize),
+    Boss,
+}
+
+pub struct DamageEvent {
+    pub source: Shooter,
+    pub target: Target,
+    pub amount: i32,
+    /// Where the hit landed
+    pub pos: Vec2,
+    /// Impulse pushing the target away
+    pub knockback: Vec2,
+    /// Ticks the target can't take another hit with a cooldown, see
+    /// [take_cooldown]
+    pub cooldown: u32,
+}
+
+impl DamageEvent {
+    /// Creates an event pushing the target `strength` pixels per tick in
+    /// `direction`
+    pub fn new(
+        source: Shooter,
+        target: Target,
+        amount: i32,
+        pos: &Vec2,
+        direction: &Vec2,
+        strength: f64,
+    ) -> DamageEvent {
+        let mut knockback = direction.clone();
+        if knockback.length() > 0.0 {
+            knockback.normalize();
+        }
+        knockback.scale(strength);
+        DamageEvent {
+            source,
+            target,
+            amount,
+            pos: pos.clone(),
+            knockback,
+            cooldown: 0,
+        }
+    }
+
+    pub fn with_cooldown(mut self, ticks: u32) -> DamageEvent {
+        self.cooldown = ticks;
+        self
+    }
+}
+
+/// Checks a hit against the hit cooldown of its target, starting the
+/// cooldown if the hit gets through. Hits without a cooldown always get
+/// through and leave the cooldown alone.
+pub fn take_cooldown(cooldown: &mut u32, event: &DamageEvent) -> bool {
+    if event.cooldown == 0 {
+        return true;
+    }
+    if *cooldown > 0 {
+        return false;
+    }
+    *cooldown = event.cooldown;
+    true
+}
+
+/// Moves `pos` by the knock back unless `blocked` and lets the knock back
+/// wear off
+pub fn apply_knockback(pos: &mut Vec2, knockback: &mut Vec2, blocked: bool) {
+    if knockback.length() < 0.1 {
+        *knockback = Vec2::new(0., 0.);
+        return;
+    }
+    if !blocked {
+        pos.add(knockback);
+    }
+    knockback.scale(KNOCKBACK_FRICTION);
+}
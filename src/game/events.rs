@@ -0,0 +1,8 @@
+/// What the game reports to the page embedding it
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// The score of a player changed
+    Score { player: u8, score: i32 },
+    /// Both players are out of lives and continues
+    GameOver { scores: [i32; 2] },
+}
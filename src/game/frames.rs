@@ -0,0 +1,72 @@
+//! Frames and clips of the sprite sheet used directly by the game code.
+//!
+//! Everything else refers to them by name in its data files. The frame
+//! handles are looked up once here, instead of every time something is drawn.
+use crate::engine::animation::Clips;
+use crate::engine::atlas::FrameId;
+
+/// Number of enemy colors, shared by spawn points and splatter
+const COLORS: u8 = 3;
+
+pub struct Frames {
+    pub aim: FrameId,
+    pub gravestone: FrameId,
+    pub ammo: FrameId,
+
+    /// Splatter emitters and clips of the enemies, by color
+    enemy_splats: Vec<String>,
+
+    /// Blinking spawn points, by color
+    pub spawn_points: Vec<[FrameId; 2]>,
+    pub spawn_point_destroyed: FrameId,
+}
+
+impl Default for Frames {
+    fn default() -> Self {
+        Frames::new()
+    }
+}
+
+impl Frames {
+    pub fn new() -> Self {
+        Frames {
+            aim: FrameId::named("aim"),
+            gravestone: FrameId::named("gravestone"),
+            ammo: FrameId::named("icon_ammo"),
+            enemy_splats: (0..COLORS).map(|c| format!("enemy_splat_{}", c)).collect(),
+            spawn_points: (0..COLORS)
+                .map(|c| {
+                    [
+                        FrameId::named(&format!("spawn_point_{}_0", c)),
+                        FrameId::named(&format!("spawn_point_{}_1", c)),
+                    ]
+                })
+                .collect(),
+            spawn_point_destroyed: FrameId::named("spawn_point_destroyed"),
+        }
+    }
+
+    /// Splatter emitter of an enemy color. Colors without splatter of their
+    /// own use the first color's, so enemies of new colors can be added in
+    /// the data files alone.
+    pub fn enemy_splat(&self, color: u8) -> &str {
+        self.enemy_splats
+            .get(color as usize)
+            .unwrap_or(&self.enemy_splats[0])
+    }
+}
+
+/// Name of the clip of a player, walking or standing still
+pub fn player_clip(number: u8, walking: bool, left: bool) -> String {
+    format!(
+        "player_{}_{}_{}",
+        number,
+        if walking { "walk" } else { "idle" },
+        if left { "left" } else { "right" }
+    )
+}
+
+/// First frame of a clip, used for icons
+pub fn first_frame(clips: &Clips, clip: &str) -> Option<FrameId> {
+    clips.get(clip).map(|clip| clip.frame_at(0))
+}
@@ -0,0 +1,171 @@
+//! High score tables, kept in persistent storage.
+//!
+//! There is a separate board for every combination of level and difficulty,
+//! each holding the best `MAX_ENTRIES` scores.
+use crate::engine::gamepad::TwinStick;
+use crate::engine::storage::Storage;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_ENTRIES: usize = 10;
+
+const STORAGE_KEY: &str = "blobs-and-bullets.high-scores";
+
+/// Letters available for initials
+pub const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+pub const INITIALS: usize = 3;
+
+/// Ticks between letter changes while the stick is held
+const REPEAT_TICKS: u32 = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Board {
+    pub level: String,
+    pub difficulty: String,
+    pub entries: Vec<HighScore>,
+}
+
+pub struct HighScores {
+    boards: Vec<Board>,
+    storage: Box<dyn Storage>,
+}
+
+impl HighScores {
+    /// Loads the boards from `storage`, starting out empty if there are none
+    pub fn load(storage: Box<dyn Storage>) -> HighScores {
+        let boards = storage
+            .load(STORAGE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        HighScores { boards, storage }
+    }
+
+    pub fn top(&self, level: &str, difficulty: &str) -> &[HighScore] {
+        match self.board(level, difficulty) {
+            Some(board) => &board.entries,
+            None => &[],
+        }
+    }
+
+    /// Whether `score` is good enough to make it onto the board
+    pub fn qualifies(&self, level: &str, difficulty: &str, score: i32) -> bool {
+        let entries = self.top(level, difficulty);
+        score > 0 && (entries.len() < MAX_ENTRIES || entries.iter().any(|e| e.score < score))
+    }
+
+    /// Adds a score to the board and saves all boards. Returns the rank of the
+    /// new entry, or `None` if it didn't make it.
+    pub fn insert(&mut self, level: &str, difficulty: &str, entry: HighScore) -> Option<usize> {
+        if !self.qualifies(level, difficulty, entry.score) {
+            return None;
+        }
+        if self.board(level, difficulty).is_none() {
+            self.boards.push(Board {
+                level: String::from(level),
+                difficulty: String::from(difficulty),
+                entries: Vec::new(),
+            });
+        }
+        let board = self
+            .boards
+            .iter_mut()
+            .find(|b| b.level == level && b.difficulty == difficulty)?;
+        let rank = board
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(board.entries.len());
+        board.entries.insert(rank, entry);
+        board.entries.truncate(MAX_ENTRIES);
+        self.save();
+        Some(rank)
+    }
+
+    fn board(&self, level: &str, difficulty: &str) -> Option<&Board> {
+        self.boards
+            .iter()
+            .find(|b| b.level == level && b.difficulty == difficulty)
+    }
+
+    fn save(&mut self) {
+        if let Ok(json) = serde_json::to_string(&self.boards) {
+            self.storage.save(STORAGE_KEY, &json);
+        }
+    }
+}
+
+/// A player entering their initials for a new high score. The stick picks a
+/// letter and fire moves on to the next one.
+pub struct InitialsEntry {
+    pub player: u8,
+    pub score: i32,
+    pub letters: [usize; INITIALS],
+    pub cursor: usize,
+    /// Set once the entry has been added to the board
+    pub saved: bool,
+    repeat: u32,
+    shoot_held: bool,
+}
+
+impl InitialsEntry {
+    pub fn new(player: u8, score: i32) -> InitialsEntry {
+        InitialsEntry {
+            player,
+            score,
+            letters: [0; INITIALS],
+            cursor: 0,
+            saved: false,
+            repeat: 0,
+            // Fire has to be released first, so a held trigger doesn't skip
+            // past the letters
+            shoot_held: true,
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.cursor >= INITIALS
+    }
+
+    pub fn initials(&self) -> String {
+        self.letters.iter().map(|l| ALPHABET[*l] as char).collect()
+    }
+
+    pub fn update(&mut self, gamepad: &TwinStick) {
+        if self.done() {
+            return;
+        }
+
+        let axis = gamepad.move_y_axis;
+        if axis.abs() < 0.5 {
+            self.repeat = 0;
+        } else if self.repeat > 0 {
+            self.repeat -= 1;
+        } else {
+            let count = ALPHABET.len();
+            let letter = &mut self.letters[self.cursor];
+            *letter = if axis < 0. {
+                (*letter + 1) % count
+            } else {
+                (*letter + count - 1) % count
+            };
+            self.repeat = REPEAT_TICKS;
+        }
+
+        if gamepad.shoot && !self.shoot_held {
+            self.cursor += 1;
+        }
+        self.shoot_held = gamepad.shoot;
+    }
+
+    pub fn high_score(&self) -> HighScore {
+        HighScore {
+            initials: self.initials(),
+            score: self.score,
+        }
+    }
+}
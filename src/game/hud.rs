@@ -0,0 +1,424 @@
+//! HUD layout loaded from `assets/hud.json`.
+//!
+//! Every player gets a slot, anchored to a corner, an edge or the center of
+//! the screen so the HUD follows the resolution. The layout has slots for
+//! each number of players. The player widgets are placed relative to the
+//! slot of each player, and a mirrored slot flips them horizontally, which
+//! suits slots on the right side of the screen. Shared widgets are anchored
+//! to the screen directly.
+//!
+//! Widgets are drawn from their position towards the side given by their
+//! alignment, so a right aligned score grows to the left.
+use crate::engine::atlas::SpriteSheet;
+use crate::engine::bitmap_font::{Align, TextStyle};
+use crate::engine::json::from_js;
+use crate::engine::math::Vec2;
+use crate::engine::renderer::Renderer;
+use crate::engine::GameContext;
+use crate::game::scoring::COMBO_WINDOW;
+use crate::game::{Fonts, MyGame, Player};
+use serde::Deserialize;
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+/// Height in pixels of the heart and ammo icons
+const ICON_HEIGHT: f64 = 10.;
+
+/// Height in pixels of the boss health and timer bars
+const BAR_HEIGHT: f64 = 6.;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// The point of a screen of the given size the anchor stands for
+    pub fn point(self, width: f64, height: f64) -> Vec2 {
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0.,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => width / 2.,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => width,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0.,
+            Anchor::Left | Anchor::Center | Anchor::Right => height / 2.,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => height,
+        };
+        Vec2::new(x, y)
+    }
+}
+
+/// Where the widgets of a player go
+#[derive(Debug, Clone, Deserialize)]
+pub struct Slot {
+    #[serde(default)]
+    pub anchor: Anchor,
+    #[serde(default)]
+    pub x: f64,
+    #[serde(default)]
+    pub y: f64,
+    /// Flips the widgets horizontally
+    #[serde(default)]
+    pub mirrored: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetKind {
+    // widgets of a player
+    Score,
+    Weapon,
+    Ammo,
+    Hearts,
+    Buffs,
+    Multiplier,
+
+    // shared widgets
+    Continues,
+    BossBar,
+    WaveBanner,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Widget {
+    pub widget: WidgetKind,
+    /// Point of the screen a shared widget is placed from. Player widgets
+    /// are placed from their slot instead.
+    #[serde(default)]
+    pub anchor: Anchor,
+    #[serde(default)]
+    pub x: f64,
+    /// Top of the widget
+    #[serde(default)]
+    pub y: f64,
+    #[serde(default)]
+    pub align: Align,
+    /// Scale of the numbers
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// Scale of the text next to the numbers, if different
+    #[serde(default)]
+    pub label_scale: Option<f64>,
+    /// Pixels between the parts of the widget, such as its icons or its
+    /// label and number
+    #[serde(default)]
+    pub spacing: f64,
+    /// Width of the bar of the boss health and multiplier widgets
+    #[serde(default)]
+    pub width: Option<f64>,
+}
+
+fn default_scale() -> f64 {
+    1.
+}
+
+/// Where a widget is drawn, once anchored and mirrored
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Placement {
+    /// Left, center or right of the widget, depending on `align`
+    pub x: f64,
+    pub y: f64,
+    pub align: Align,
+}
+
+impl Placement {
+    /// Left of a widget `width` wide
+    pub fn left(&self, width: f64) -> f64 {
+        match self.align {
+            Align::Left => self.x,
+            Align::Center => self.x - width / 2.,
+            Align::Right => self.x - width,
+        }
+    }
+}
+
+impl Widget {
+    /// Placement of a shared widget on a screen of the given size
+    pub fn place(&self, width: f64, height: f64) -> Placement {
+        let anchor = self.anchor.point(width, height);
+        Placement {
+            x: anchor.x + self.x,
+            y: anchor.y + self.y,
+            align: self.align,
+        }
+    }
+
+    /// Placement of a player widget in `slot`
+    pub fn place_in(&self, slot: &Slot, width: f64, height: f64) -> Placement {
+        let anchor = slot.anchor.point(width, height);
+        let (x, align) = if slot.mirrored {
+            let align = match self.align {
+                Align::Left => Align::Right,
+                Align::Center => Align::Center,
+                Align::Right => Align::Left,
+            };
+            (-self.x, align)
+        } else {
+            (self.x, self.align)
+        };
+        Placement {
+            x: anchor.x + slot.x + x,
+            y: anchor.y + slot.y + self.y,
+            align,
+        }
+    }
+
+    fn label_scale(&self) -> f64 {
+        self.label_scale.unwrap_or(self.scale)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HudLayout {
+    /// Slots of the players, for each number of players
+    pub players: Vec<Vec<Slot>>,
+    /// Widgets drawn for each player, relative to their slot
+    #[serde(default)]
+    pub player: Vec<Widget>,
+    /// Widgets drawn once, relative to the screen
+    #[serde(default)]
+    pub shared: Vec<Widget>,
+}
+
+impl HudLayout {
+    pub fn new_from_json(json: &JsValue) -> HudLayout {
+        from_js(json).unwrap()
+    }
+
+    /// Slots for `count` players: the first of the layout with enough
+    /// slots, else the one with the most. Players without a slot have no
+    /// widgets.
+    pub fn slots(&self, count: usize) -> &[Slot] {
+        self.players
+            .iter()
+            .find(|slots| slots.len() >= count)
+            .or_else(|| self.players.iter().max_by_key(|slots| slots.len()))
+            .map(|slots| &slots[..])
+            .unwrap_or(&[])
+    }
+}
+
+impl FromStr for HudLayout {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+impl MyGame {
+    pub(super) fn draw_hud(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        fonts: &Fonts,
+        ctx: &GameContext,
+    ) {
+        let (width, height) = (ctx.window_width, ctx.window_height);
+        let players = [&self.player_1, &self.player_2];
+        for (player, slot) in players.iter().zip(self.hud.slots(players.len())) {
+            for widget in self.hud.player.iter() {
+                let placement = widget.place_in(slot, width, height);
+                self.draw_player_widget(renderer, sheet, fonts, widget, &placement, player);
+            }
+        }
+        for widget in self.hud.shared.iter() {
+            let placement = widget.place(width, height);
+            self.draw_shared_widget(renderer, sheet, fonts, widget, &placement, ctx);
+        }
+    }
+
+    fn draw_player_widget(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        fonts: &Fonts,
+        widget: &Widget,
+        at: &Placement,
+        player: &Player,
+    ) {
+        let numbers = TextStyle::scaled(widget.scale);
+        // middle of a line of numbers, where the icons are centered
+        let middle = at.y + fonts.digits.font.line_height * widget.scale / 2.;
+        match widget.widget {
+            WidgetKind::Score => {
+                let score = format!("{}", player.scoring.displayed);
+                renderer.draw_text(&fonts.digits, at.x, at.y, &score, &numbers.align(at.align));
+            }
+            WidgetKind::Weapon => {
+                let sprite = player.current_weapon().sprite();
+                renderer.draw_frame(sheet, sprite, at.left(16.) + 8., middle);
+            }
+            WidgetKind::Ammo => {
+                let ammo = format!("{}", player.ammo);
+                let icon = 12. + widget.spacing;
+                let left = at.left(icon + fonts.digits.font.measure(&ammo, &numbers).0);
+                renderer.draw_ammo(&fonts.digits.image, left, middle - ICON_HEIGHT / 2.);
+                renderer.draw_text(&fonts.digits, left + icon, at.y, &ammo, &numbers);
+            }
+            WidgetKind::Hearts => {
+                let hearts = (player.health.max(0) + 4) / 5;
+                let left = at.left(12. * hearts as f64);
+                renderer.draw_hearts(
+                    &fonts.digits.image,
+                    left,
+                    middle - ICON_HEIGHT / 2.,
+                    player.health,
+                );
+            }
+            WidgetKind::Buffs => {
+                let step = 16. + widget.spacing;
+                let count = player.buffs.len() as f64;
+                let mut x = at.left(f64::max(0., count * step - widget.spacing));
+                for buff in player.buffs.iter() {
+                    let sprite = self.power_up_registry.power_ups[buff.kind].sprite;
+                    renderer.draw_frame(sheet, sprite, x + 8., at.y + 8.);
+                    renderer.draw_rect("black", x, at.y + 18., 16., 4.);
+                    let remaining = buff.remaining as f64 / u32::max(1, buff.duration) as f64;
+                    renderer.draw_rect("white", x, at.y + 18., 16. * remaining, 4.);
+                    x += step;
+                }
+            }
+            WidgetKind::Multiplier => {
+                let multiplier = player.scoring.multiplier();
+                if multiplier < 2 {
+                    return;
+                }
+                let width = widget.width.unwrap_or(64.);
+                let left = at.left(width);
+                self.draw_labelled(
+                    renderer,
+                    fonts,
+                    widget,
+                    Vec2::new(left, at.y),
+                    "X",
+                    multiplier,
+                );
+                let y = at.y + fonts.digits.font.line_height * widget.scale + 4.;
+                let remaining = player.scoring.combo_timer as f64 / COMBO_WINDOW as f64;
+                renderer.draw_rect("black", left, y, width, 4.);
+                renderer.draw_rect("yellow", left, y, width * remaining, 4.);
+            }
+            _ => {}
+        }
+    }
+
+    fn draw_shared_widget(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        fonts: &Fonts,
+        widget: &Widget,
+        at: &Placement,
+        ctx: &GameContext,
+    ) {
+        let numbers = TextStyle::scaled(widget.scale);
+        match widget.widget {
+            WidgetKind::Continues => {
+                let continues = format!("{}", self.continues);
+                let icon = 16. + widget.spacing;
+                let left = at.left(icon + fonts.digits.font.measure(&continues, &numbers).0);
+                let middle = at.y + fonts.digits.font.line_height * widget.scale / 2.;
+                renderer.draw_frame(sheet, self.frames.gravestone, left + 8., middle);
+                renderer.draw_text(&fonts.digits, left + icon, at.y, &continues, &numbers);
+            }
+            WidgetKind::BossBar => {
+                if let Some(boss) = &self.boss {
+                    let def = &self.bosses.bosses[boss.kind];
+                    let width = widget.width.unwrap_or(480.);
+                    let (x, y) = (at.left(width), at.y);
+                    renderer.draw_rect("black", x - 2., y - 2., width + 4., BAR_HEIGHT + 4.);
+                    renderer.draw_rect(
+                        "red",
+                        x,
+                        y,
+                        width * f64::max(0., boss.health as f64) / def.health as f64,
+                        BAR_HEIGHT,
+                    );
+                    for phase in def.phases.iter().skip(1) {
+                        renderer.draw_rect(
+                            "white",
+                            x + width * phase.health - 1.,
+                            y - 2.,
+                            2.,
+                            BAR_HEIGHT + 4.,
+                        );
+                    }
+                }
+            }
+            WidgetKind::WaveBanner => {
+                if let Some(wave) = self.waves.announcement() {
+                    if (ctx.tick / 15).is_multiple_of(2) {
+                        let width = self.labelled_width(fonts, widget, "WAVE", wave);
+                        let left = at.left(width);
+                        self.draw_labelled(
+                            renderer,
+                            fonts,
+                            widget,
+                            Vec2::new(left, at.y),
+                            "WAVE",
+                            wave,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Width of a number after a label, as drawn by [MyGame::draw_labelled]
+    fn labelled_width<T: std::fmt::Display>(
+        &self,
+        fonts: &Fonts,
+        widget: &Widget,
+        label: &str,
+        number: T,
+    ) -> f64 {
+        let (label, _) = fonts
+            .text
+            .font
+            .measure(label, &TextStyle::scaled(widget.label_scale()));
+        let number = format!("{}", number);
+        let (number, _) = fonts
+            .digits
+            .font
+            .measure(&number, &TextStyle::scaled(widget.scale));
+        label + widget.spacing + number
+    }
+
+    /// Draws a label followed by a number at `pos`, centered on the line of
+    /// the number
+    fn draw_labelled<T: std::fmt::Display>(
+        &self,
+        renderer: &dyn Renderer,
+        fonts: &Fonts,
+        widget: &Widget,
+        pos: Vec2,
+        label: &str,
+        number: T,
+    ) {
+        let text = TextStyle::scaled(widget.label_scale());
+        let (width, height) = fonts.text.font.measure(label, &text);
+        let Vec2 { x, y } = pos;
+        let middle = y + fonts.digits.font.line_height * widget.scale / 2.;
+        renderer.draw_text(&fonts.text, x, middle - height / 2., label, &text);
+        renderer.draw_text(
+            &fonts.digits,
+            x + width + widget.spacing,
+            y,
+            &format!("{}", number),
+            &TextStyle::scaled(widget.scale),
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! The title, pause, options, controls and high score menus.
+//!
+//! Menus open on top of each other, and going back closes the top one. The
+//! title menu is at the bottom while the game is on the title screen, the
+//! pause menu while it's paused.
+use crate::engine::atlas::FrameId;
+use crate::engine::gamepad::Bindings;
+use crate::engine::renderer::Renderer;
+use crate::engine::transform::Color;
+use crate::engine::ui::{MenuInput, Skin, Theme};
+use crate::engine::GameContext;
+use crate::game::{MyGame, Screen};
+
+/// Width of the menu panels, wide enough for the title and for the label
+/// and value of every row at the scales of the [theme]
+pub const PANEL_WIDTH: f64 = 704.;
+
+/// Top of the menu panels
+pub const PANEL_TOP: f64 = 24.;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Menu {
+    Title,
+    Pause,
+    Options,
+    Controls,
+    HighScores,
+}
+
+impl Menu {
+    fn name(self) -> &'static str {
+        match self {
+            Menu::Title => "title",
+            Menu::Pause => "pause",
+            Menu::Options => "options",
+            Menu::Controls => "controls",
+            Menu::HighScores => "high scores",
+        }
+    }
+}
+
+/// Gamepad actions that can be put on other buttons
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Action {
+    Shoot,
+    NextWeapon,
+}
+
+/// Waiting for the button to put an action on
+pub struct Remapping {
+    action: Action,
+    /// All buttons have to be let go first, so the button that picked the
+    /// action isn't taken
+    released: bool,
+}
+
+/// Menus in the colors of the font, with the aiming reticle as cursor
+pub fn theme() -> Theme {
+    Theme {
+        cursor: FrameId::named("aim"),
+        knob: FrameId::named("bullet"),
+        panel: Color::BLACK.with_alpha(0.67),
+        border: Color::rgb(227, 115, 6),
+        highlight: Color::WHITE.with_alpha(0.1),
+        focus: Color::WHITE.with_alpha(0.5),
+        off: Color::rgb(64, 64, 64).with_alpha(0.8),
+        track: Color::BLACK,
+        fill: Color::rgb(255, 200, 50),
+        heading_scale: 2.,
+        text_scale: 2.,
+        digit_scale: 4.,
+        row_height: 40.,
+        cell_height: 28.,
+        padding: 24.,
+    }
+}
+
+impl MyGame {
+    /// Opens a menu on top of the others
+    fn open(&mut self, menu: Menu) {
+        self.menus.push(menu);
+    }
+
+    /// Closes the top menu. The title and pause menus can't be closed.
+    fn close(&mut self) {
+        if self.menus.len() > 1 {
+            self.menus.pop();
+        }
+    }
+
+    pub(super) fn update_menus(&mut self, ctx: &GameContext) {
+        let menu = match self.menus.last() {
+            Some(menu) => *menu,
+            None => return,
+        };
+        let mut input = MenuInput::read(ctx);
+        if self.remapping.is_some() {
+            // The buttons pressed are for remapping, so only confirm and
+            // back are passed on to keep track of them
+            input = MenuInput {
+                confirm: input.confirm,
+                back: input.back,
+                ..MenuInput::default()
+            };
+        }
+
+        self.ui.begin(menu.name(), input);
+        self.ui
+            .begin_panel(ctx.window_width / 2., PANEL_TOP, PANEL_WIDTH);
+        match menu {
+            Menu::Title => self.title_menu(),
+            Menu::Pause => self.pause_menu(),
+            Menu::Options => self.options_menu(),
+            Menu::Controls => self.controls_menu(),
+            Menu::HighScores => self.high_scores_menu(),
+        }
+        self.ui.end_panel();
+        self.ui.end();
+
+        if menu == Menu::Controls {
+            self.update_remapping(ctx);
+        }
+    }
+
+    pub(super) fn draw_menus(&self, renderer: &dyn Renderer) {
+        if let (Some(sheet), Some(fonts)) = (&self.sheet, &self.fonts) {
+            let skin = Skin {
+                sheet,
+                text: &fonts.text,
+                digits: &fonts.digits,
+            };
+            self.ui.draw(renderer, &skin);
+        }
+    }
+
+    fn title_menu(&mut self) {
+        self.ui.heading("BLOBS AND BULLETS");
+        self.ui.space(16.);
+        if self.ui.button("PLAY") {
+            self.reset();
+            self.play();
+        }
+        if self.ui.button("OPTIONS") {
+            self.open(Menu::Options);
+        }
+        let difficulty = self.difficulty.name.to_uppercase();
+        if self.ui.setting("HIGH SCORES", &difficulty) {
+            self.board = self
+                .coop
+                .difficulties
+                .iter()
+                .position(|d| d.name == self.difficulty.name)
+                .unwrap_or(0);
+            self.open(Menu::HighScores);
+        }
+        let difficulty = self.difficulty.name.clone();
+        self.score_table(&difficulty);
+    }
+
+    fn pause_menu(&mut self) {
+        self.ui.heading("PAUSED");
+        self.ui.space(16.);
+        if self.ui.button("RESUME") || self.ui.back_pressed() {
+            self.play();
+        }
+        if self.ui.button("OPTIONS") {
+            self.open(Menu::Options);
+        }
+        if self.ui.button("QUIT") {
+            self.screen = Screen::Title;
+            self.menus = vec![Menu::Title];
+        }
+    }
+
+    fn options_menu(&mut self) {
+        self.ui.heading("OPTIONS");
+        self.ui.space(16.);
+        // The difficulty can't change in the middle of a game
+        if let Screen::Title = self.screen {
+            let names: Vec<String> = self
+                .coop
+                .difficulties
+                .iter()
+                .map(|d| d.name.to_uppercase())
+                .collect();
+            let names: Vec<&str> = names.iter().map(String::as_str).collect();
+            let mut selected = self
+                .coop
+                .difficulties
+                .iter()
+                .position(|d| d.name == self.difficulty.name)
+                .unwrap_or(0);
+            if self.ui.list("DIFFICULTY", &names, &mut selected) {
+                if let Some(difficulty) = self.coop.difficulties.get(selected) {
+                    self.difficulty = difficulty.clone();
+                    self.continues = difficulty.continues;
+                    self.settings.options.difficulty = Some(difficulty.name.clone());
+                    self.settings.save();
+                }
+            }
+        }
+        let mut friendly_fire = self.coop.friendly_fire;
+        if self.ui.toggle("FRIENDLY FIRE", &mut friendly_fire) {
+            self.coop.friendly_fire = friendly_fire;
+            self.settings.options.friendly_fire = Some(friendly_fire);
+            self.settings.save();
+        }
+        if self.ui.button("CONTROLS") {
+            self.open(Menu::Controls);
+        }
+        if self.ui.button("BACK") || self.ui.back_pressed() {
+            self.close();
+        }
+    }
+
+    fn controls_menu(&mut self) {
+        self.ui.heading("CONTROLS");
+        self.ui.space(16.);
+        let bindings = self.settings.options.bindings;
+        let waiting = self.remapping.as_ref().map(|r| r.action);
+        let actions = [
+            ("SHOOT", Action::Shoot, bindings.shoot),
+            ("NEXT WEAPON", Action::NextWeapon, bindings.next_weapon),
+        ];
+        for (text, action, button) in actions.iter() {
+            let value = if waiting == Some(*action) {
+                String::from("PRESS")
+            } else {
+                format!("{}", button)
+            };
+            if self.ui.setting(text, &value) && waiting.is_none() {
+                self.remapping = Some(Remapping {
+                    action: *action,
+                    released: false,
+                });
+            }
+        }
+        let mut dead_zone = bindings.dead_zone;
+        if self.ui.slider("DEAD ZONE", &mut dead_zone, 0., 0.5, 0.05) {
+            self.settings.options.bindings.dead_zone = dead_zone;
+            self.settings.save();
+        }
+        if self.ui.button("RESET") && waiting.is_none() {
+            self.settings.options.bindings = Bindings::default();
+            self.settings.save();
+        }
+        if self.ui.button("BACK") && waiting.is_none() {
+            self.close();
+        }
+        if self.ui.back_pressed() {
+            if waiting.is_some() {
+                self.remapping = None;
+            } else {
+                self.close();
+            }
+        }
+    }
+
+    /// Puts the action being remapped on the next button pressed on either
+    /// gamepad
+    fn update_remapping(&mut self, ctx: &GameContext) {
+        let remapping = match &mut self.remapping {
+            Some(remapping) => remapping,
+            None => return,
+        };
+        let button = ctx.gamepad_1.button.or(ctx.gamepad_2.button);
+        match button {
+            None => remapping.released = true,
+            Some(button) if remapping.released => {
+                let bindings = &mut self.settings.options.bindings;
+                match remapping.action {
+                    Action::Shoot => bindings.shoot = button,
+                    Action::NextWeapon => bindings.next_weapon = button,
+                }
+                self.remapping = None;
+                self.settings.save();
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn high_scores_menu(&mut self) {
+        self.ui.heading("HIGH SCORES");
+        let names: Vec<String> = self
+            .coop
+            .difficulties
+            .iter()
+            .map(|d| d.name.to_uppercase())
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        self.ui.list("DIFFICULTY", &names, &mut self.board);
+
+        if let Some(difficulty) = self.coop.difficulties.get(self.board) {
+            let difficulty = difficulty.name.clone();
+            self.score_table(&difficulty);
+        }
+        if self.ui.button("BACK") || self.ui.back_pressed() {
+            self.close();
+        }
+    }
+
+    /// The best scores of the level on `difficulty`
+    fn score_table(&mut self, difficulty: &str) {
+        let entries = self.high_scores.top(&self.level, difficulty);
+        if entries.is_empty() {
+            self.ui.label("NO SCORES");
+        }
+        for (rank, entry) in entries.iter().enumerate() {
+            let rank = format!("{}", rank + 1);
+            let score = format!("{}", entry.score);
+            self.ui.columns(&[&rank, &entry.initials, &score]);
+        }
+    }
+}
@@ -1,59 +1,164 @@
-use crate::engine::gamepad::TwinStick;
+pub mod archetypes;
+pub mod boss;
+pub mod bullets;
+pub mod coop;
+pub mod damage;
+pub mod events;
+pub mod frames;
+pub mod high_scores;
+pub mod hud;
+pub mod menus;
+pub mod power_ups;
+pub mod scoring;
+pub mod settings;
+pub mod snapshot;
+pub mod waves;
+pub mod weapons;
+
+use crate::engine::animation::{Animator, Clips};
+use crate::engine::atlas::SpriteSheet;
+use crate::engine::bitmap_font::{Font, TextStyle};
+use crate::engine::decals::{Decal, DecalLayer};
+use crate::engine::gamepad::{Bindings, TwinStick};
 use crate::engine::math::{vec2_distance, Vec2};
+use crate::engine::particles::{Emitters, Particles};
 use crate::engine::preloader::{Preloader, Resources};
-use crate::engine::renderer::CanvasRenderer;
+use crate::engine::random::{self, random};
+use crate::engine::renderer::Renderer;
+use crate::engine::storage::{LocalStorage, Storage};
+use crate::engine::tile_cache::TileLayerCache;
 use crate::engine::tiled::TileMap;
+use crate::engine::transform::{Color, Transform};
+use crate::engine::ui::Ui;
+use crate::engine::viewport::Viewport;
 use crate::engine::GameContext;
-use js_sys::Math::random;
+use crate::game::archetypes::{Behaviour, EnemyArchetypes};
+use crate::game::boss::{Boss, BossDefinitions};
+use crate::game::bullets::{Bullet, Shooter};
+use crate::game::coop::{CoopConfig, Difficulty, PlayerState};
+use crate::game::damage::{
+    apply_knockback, take_cooldown, DamageEvent, Target, INVULNERABILITY_TICKS,
+};
+use crate::game::events::GameEvent;
+use crate::game::frames::{first_frame, player_clip, Frames};
+use crate::game::high_scores::{HighScores, InitialsEntry};
+use crate::game::hud::HudLayout;
+use crate::game::menus::{Menu, Remapping};
+use crate::game::power_ups::{Buff, Effect, PowerUp, PowerUpRegistry};
+use crate::game::scoring::{ScorePopup, Scoring, BOSS_HIT_SCORE, SPAWN_POINT_HIT_SCORE};
+use crate::game::settings::Settings;
+use crate::game::snapshot::{Snapshot, SNAPSHOT_VERSION};
+use crate::game::waves::{SpawnOrder, SpawnPoint, WaveConfig, WaveDirector};
+use crate::game::weapons::Weapon;
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
-use web_sys::HtmlImageElement;
-
-const PLAYER_1_LEFT_CYCLE: [u8; 2] = [161, 169];
-const PLAYER_1_RIGHT_CYCLE: [u8; 2] = [160, 168];
-const PLAYER_2_LEFT_CYCLE: [u8; 2] = [163, 171];
-const PLAYER_2_RIGHT_CYCLE: [u8; 2] = [162, 170];
-
-const ENEMY_0_LEFT_CYCLE: [u8; 2] = [121, 113];
-const ENEMY_0_RIGHT_CYCLE: [u8; 2] = [120, 112];
-const ENEMY_1_LEFT_CYCLE: [u8; 2] = [123, 115];
-const ENEMY_1_RIGHT_CYCLE: [u8; 2] = [122, 114];
-const ENEMY_2_LEFT_CYCLE: [u8; 2] = [125, 117];
-const ENEMY_2_RIGHT_CYCLE: [u8; 2] = [124, 116];
-
-#[derive(Copy, Clone)]
-enum Splat {
-    Sparks,
-    Blood,
-    Enemy(u8),
-    Water,
-    Explosion,
+use wasm_bindgen::JsValue;
+use web_sys::console;
+
+const PLAYER_HEALTH: i32 = 3 * 5;
+
+/// Name of the level the game starts on, used for its high score boards
+const LEVEL: &str = "arena";
+
+const QUICK_SAVE_KEY: &str = "F2";
+const QUICK_LOAD_KEY: &str = "F4";
+const QUICK_SAVE_STORAGE_KEY: &str = "blobs-and-bullets.quick-save";
+
+/// Pauses the game, as do escape and the start button
+const PAUSE_KEY: &str = "KeyP";
+
+/// Fonts of the HUD and menus
+struct Fonts {
+    /// Upper case letters
+    text: Font,
+    /// Its image also has the heart and ammo icons
+    digits: Font,
+}
+
+enum Screen {
+    Title,
+    Playing,
+    Paused,
+    /// Game over, showing the statistics and entering high scores
+    Results,
 }
 
 pub struct MyGame {
+    screen: Screen,
     player_1: Player,
     player_2: Player,
 
-    sprites: Option<HtmlImageElement>,
-    numbers: Option<HtmlImageElement>,
+    sheet: Option<SpriteSheet>,
+    clips: Clips,
+    frames: Frames,
+    fonts: Option<Fonts>,
     map: Option<TileMap>,
+    map_cache: Option<TileLayerCache>,
     bullets: Vec<Bullet>,
-    splatter: Vec<(Vec2, Splat, Vec2, i8)>,
-
-    spawn_points: Vec<(Vec2, u8, u8)>,
-    enemies: Vec<(Vec2, f64, u8)>,
+    particles: Particles,
+    emitters: Emitters,
+    /// Marks left on the floor by the fighting
+    decals: DecalLayer,
+
+    spawn_points: Vec<SpawnPoint>,
+    next_spawn_point: usize,
+    archetypes: EnemyArchetypes,
+    waves: WaveDirector,
+    enemies: Vec<Enemy>,
+    bosses: BossDefinitions,
     boss: Option<Boss>,
 
-    power_ups: Vec<(Vec2, i8)>,
+    power_up_registry: PowerUpRegistry,
+    power_ups: Vec<PowerUp>,
+    popups: Vec<ScorePopup>,
+    hud: HudLayout,
+
+    coop: CoopConfig,
+    difficulty: Difficulty,
+    /// Respawns left, shared by both players
+    continues: u32,
+
+    high_scores: HighScores,
+    initials: Vec<InitialsEntry>,
+    fire_held: bool,
+
+    ui: Ui,
+    /// Open menus, the top one last
+    menus: Vec<Menu>,
+    remapping: Option<Remapping>,
+    settings: Settings,
+    /// Difficulty of the high scores shown
+    board: usize,
+    pause_held: bool,
+
+    storage: Box<dyn Storage>,
+    quick_save_held: bool,
+    quick_load_held: bool,
+
+    /// Name of the current level
+    level: String,
+    /// Events for the page embedding the game, since they were last taken
+    events: Vec<GameEvent>,
+    /// Scores last reported with [GameEvent::Score]
+    reported_scores: [i32; 2],
 }
 
-pub struct Boss {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Enemy {
     pub pos: Vec2,
+    pub dir: f64,
+    /// Index into the loaded archetypes
+    pub kind: usize,
     pub health: i32,
-    pub heat: f64,
-    pub charging: bool,
-    pub tx: f64,
+    pub spin: f64,
+    pub knockback: Vec2,
+    pub animator: Animator,
+    /// Ticks until piercing bullets can hit the enemy again
+    #[serde(default)]
+    pub hit_cooldown: u32,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Player {
     pub number: u8,
     pub pos: Vec2,
@@ -63,26 +168,26 @@ pub struct Player {
 
     pub shooting: bool,
     pub heat: f64,
+    pub weapons: Vec<Weapon>,
+    pub weapon: usize,
+    pub switching_weapon: bool,
 
-    pub score: i32,
-    pub next_score: i32,
+    pub scoring: Scoring,
 
     pub health: i32,
+    pub state: PlayerState,
+    /// Ticks left before the player can be hurt again
+    pub invulnerable: u32,
+    pub knockback: Vec2,
     pub ammo: i32,
     pub next_ammo: i32,
-}
 
-#[derive(Copy, Clone)]
-enum Shooter {
-    Player(u8),
-    Enemy(u8),
-    Boss,
+    pub buffs: Vec<Buff>,
+    pub animator: Animator,
 }
 
-struct Bullet(Vec2, Vec2, Shooter);
-
 impl Player {
-    fn new(pos: Vec2, number: u8) -> Player {
+    pub fn new(pos: Vec2, number: u8) -> Player {
         let aim = Vec2::new(1.0, 0.0);
         let shooting = false;
         let face_left = false;
@@ -96,11 +201,18 @@ impl Player {
             moving: false,
             shooting,
             heat,
-            score: 0,
-            next_score: 0,
-            health: 3 * 5,
+            weapons: vec![Weapon::Pistol],
+            weapon: 0,
+            switching_weapon: false,
+            scoring: Scoring::default(),
+            health: PLAYER_HEALTH,
+            state: PlayerState::Alive,
+            invulnerable: 0,
+            knockback: Vec2::new(0., 0.),
             ammo: 250,
             next_ammo: 0,
+            buffs: Vec::new(),
+            animator: Animator::new(&player_clip(number, false, face_left)),
         }
     }
 
@@ -108,27 +220,35 @@ impl Player {
         &mut self,
         gamepad: &TwinStick,
         bullets: &mut Vec<Bullet>,
-        splatter: &mut Vec<(Vec2, Splat, Vec2, i8)>,
+        particles: &mut Particles,
+        emitters: &Emitters,
         map: &TileMap,
+        clips: &Clips,
     ) {
+        self.update_buffs();
+
         let dir = Vec2::new(gamepad.move_x_axis, gamepad.move_y_axis);
+        let speed = 2.0 * self.speed_factor();
 
         let tiles = &map.layers[0].data;
         let t = get_tile_at(
             tiles,
-            self.pos.x + dir.x * 2.0,
-            self.pos.y + dir.y * 2.0 + 8.0,
+            self.pos.x + dir.x * speed,
+            self.pos.y + dir.y * speed + 8.0,
         );
         let is_wall = is_wall_tile(t);
-        if (!is_wall) && self.health > 0 {
-            self.pos.x += dir.x * 2.0;
-            self.pos.y += dir.y * 2.0;
+        if (!is_wall) && self.alive() {
+            self.pos.x += dir.x * speed;
+            self.pos.y += dir.y * speed;
         }
 
-        let is_water_tile = is_water_tile(t);
-        if self.moving && is_water_tile && random() < 0.3 {
-            MyGame::add_splatter(splatter, &self.pos, 1, Splat::Water);
-        }
+        self.invulnerable = self.invulnerable.saturating_sub(1);
+        let t = get_tile_at(
+            tiles,
+            self.pos.x + self.knockback.x,
+            self.pos.y + self.knockback.y + 8.0,
+        );
+        apply_knockback(&mut self.pos, &mut self.knockback, is_wall_tile(t));
 
         if dir.x < -0.02 {
             self.face_left = true;
@@ -136,273 +256,902 @@ impl Player {
             self.face_left = false;
         }
         self.moving = dir.length() > 0.1;
+
+        let walking = self.moving && self.alive();
+        self.animator
+            .play(&player_clip(self.number, walking, self.face_left));
+        let event = self.animator.update(clips);
+        if event == Some("step") && is_water_tile(t) {
+            particles.emit(emitters, "water", &self.pos);
+        }
         self.aim.x = gamepad.aim_x_axis;
         self.aim.y = gamepad.aim_y_axis;
         self.shooting = gamepad.shoot;
 
-        let can_shoot = self.health > 0
-            && self.heat < 1.0
-            && (self.aim.x.abs() > 0.25 || self.aim.y.abs() > 0.25);
-
-        if self.shooting && can_shoot && self.ammo > 0 {
-            self.heat += 10.0;
-            let mut bullet_velocity = self.aim.clone();
-            bullet_velocity.normalize();
-            bullet_velocity.scale(7.0);
-            bullets.push(Bullet(
-                self.pos.clone(),
-                bullet_velocity,
-                Shooter::Player(self.number),
-            ));
-            self.ammo -= 1;
+        if gamepad.next_weapon && !self.switching_weapon {
+            self.weapon = (self.weapon + 1) % self.weapons.len();
+        }
+        self.switching_weapon = gamepad.next_weapon;
+
+        let weapon = self.current_weapon();
+        let can_shoot =
+            self.alive() && self.heat < 1.0 && (self.aim.x.abs() > 0.25 || self.aim.y.abs() > 0.25);
+
+        if self.shooting && can_shoot && self.ammo >= weapon.ammo_cost() {
+            self.heat += weapon.heat() * self.heat_factor();
+            let offsets: Vec<f64> = match self.spread() {
+                Some((count, angle)) if count > 1 => (0..count)
+                    .map(|i| -angle / 2. + angle * i as f64 / (count - 1) as f64)
+                    .collect(),
+                _ => vec![0.0],
+            };
+            let spec = weapon.bullets();
+            let fired = bullets.len();
+            for offset in offsets {
+                let mut aim = self.aim.clone();
+                aim.rotate(offset);
+                let target = Vec2::new(self.pos.x + aim.x, self.pos.y + aim.y);
+                let mut spin = 0.0;
+                spec.emit(
+                    &self.pos,
+                    &target,
+                    Shooter::Player(self.number),
+                    &mut spin,
+                    bullets,
+                );
+            }
+            self.scoring.stats.shots_fired += (bullets.len() - fired) as u32;
+            self.ammo -= weapon.ammo_cost();
         } else if self.heat > 0.0 {
             self.heat -= 1.0;
         }
     }
+
+    pub fn alive(&self) -> bool {
+        self.state == PlayerState::Alive
+    }
+
+    /// Downs the player when out of health and handles bleeding out and being
+    /// revived. `reviver` is the position of the partner, if they are alive.
+    pub fn update_state(&mut self, reviver: Option<&Vec2>, difficulty: &Difficulty) {
+        if self.alive() && self.health < 1 {
+            self.state = PlayerState::Downed {
+                bleed_out: difficulty.bleed_out,
+                revive: 0,
+            };
+            self.buffs.clear();
+            self.moving = false;
+        }
+        self.state.update(&self.pos, reviver, difficulty);
+        if self.state.revived(difficulty) {
+            self.state = PlayerState::Alive;
+            self.health = difficulty.revive_health;
+            self.invulnerable = INVULNERABILITY_TICKS;
+        }
+    }
+
+    fn respawn(&mut self, pos: Vec2, health: i32) {
+        self.pos = pos;
+        self.health = health;
+        self.heat = 0.0;
+        self.state = PlayerState::Alive;
+        self.invulnerable = INVULNERABILITY_TICKS;
+        self.knockback = Vec2::new(0., 0.);
+    }
+
+    fn current_weapon(&self) -> Weapon {
+        self.weapons[self.weapon]
+    }
+
+    fn pick_up_weapon(&mut self, weapon: Weapon) {
+        match self.weapons.iter().position(|w| *w == weapon) {
+            Some(idx) => self.weapon = idx,
+            None => {
+                self.weapons.push(weapon);
+                self.weapon = self.weapons.len() - 1;
+            }
+        }
+    }
+
+    /// Applies the effect of a collected power up. Picking up a buff that is
+    /// already active restarts its timer.
+    pub fn collect(&mut self, kind: usize, registry: &PowerUpRegistry) {
+        let def = &registry.power_ups[kind];
+        self.scoring.stats.pickups += 1;
+        match def.effect {
+            Effect::Ammo { amount } => {
+                self.ammo = i32::max(self.ammo, i32::min(self.ammo + amount, registry.max_ammo))
+            }
+            Effect::Health { amount } => {
+                self.health = i32::max(
+                    self.health,
+                    i32::min(self.health + amount, registry.max_health),
+                )
+            }
+            Effect::Weapon { weapon } => self.pick_up_weapon(weapon),
+            _ => match self.buffs.iter_mut().find(|b| b.kind == kind) {
+                Some(buff) => buff.remaining = buff.duration,
+                None => self.buffs.push(Buff {
+                    kind,
+                    effect: def.effect.clone(),
+                    remaining: def.duration,
+                    duration: def.duration,
+                }),
+            },
+        }
+    }
+
+    pub fn update_buffs(&mut self) {
+        for buff in self.buffs.iter_mut() {
+            buff.remaining = buff.remaining.saturating_sub(1);
+        }
+        self.buffs.retain(|b| b.remaining > 0);
+    }
+
+    fn speed_factor(&self) -> f64 {
+        self.buffs
+            .iter()
+            .map(|b| match b.effect {
+                Effect::SpeedBoost { factor } => factor,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    fn heat_factor(&self) -> f64 {
+        self.buffs
+            .iter()
+            .map(|b| match b.effect {
+                Effect::RapidFire { factor } => factor,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    fn shielded(&self) -> bool {
+        self.buffs
+            .iter()
+            .any(|b| matches!(b.effect, Effect::Shield))
+    }
+
+    fn spread(&self) -> Option<(u8, f64)> {
+        self.buffs.iter().find_map(|b| match b.effect {
+            Effect::SpreadShot { count, angle } => Some((count, angle)),
+            _ => None,
+        })
+    }
+
+    fn magnet(&self) -> Option<f64> {
+        self.buffs.iter().find_map(|b| match b.effect {
+            Effect::Magnet { radius } => Some(radius),
+            _ => None,
+        })
+    }
+
+    /// Takes a hit unless invulnerable or shielded. Returns whether the hit
+    /// got through.
+    pub fn hit(&mut self, amount: i32, knockback: &Vec2) -> bool {
+        if !self.alive() || self.invulnerable > 0 || self.shielded() {
+            return false;
+        }
+        self.health -= amount;
+        self.invulnerable = INVULNERABILITY_TICKS;
+        self.knockback.add(knockback);
+        true
+    }
+}
+
+impl Default for MyGame {
+    fn default() -> Self {
+        MyGame::new()
+    }
 }
 
 impl MyGame {
     pub fn new() -> Self {
-        let spawn_points = vec![
-            (Vec2::new(16. * 30., 16. * 19.), 0, 5),
-            (Vec2::new(16. * 13., 16. * 23.), 1, 5),
-            (Vec2::new(16. * 50., 16. * 24.), 2, 5),
-            (Vec2::new(16. * 18., 16. * 8.), 2, 5),
-        ];
+        let spawn_points = initial_spawn_points();
 
         MyGame {
+            screen: Screen::Title,
             player_1: Player::new(Vec2::new(100., 100.), 1),
             player_2: Player::new(Vec2::new(170., 90.), 2),
-            sprites: None,
-            numbers: None,
+            sheet: None,
+            clips: Clips::default(),
+            frames: Frames::new(),
+            fonts: None,
             map: None,
+            map_cache: None,
             bullets: Vec::new(),
-            splatter: Vec::new(),
+            particles: Particles::default(),
+            emitters: Emitters::default(),
+            decals: DecalLayer::default(),
             spawn_points,
+            next_spawn_point: 0,
+            archetypes: EnemyArchetypes {
+                archetypes: Vec::new(),
+            },
+            waves: WaveDirector::new(WaveConfig::default()),
             enemies: Vec::new(),
+            bosses: BossDefinitions { bosses: Vec::new() },
             boss: None,
+            power_up_registry: PowerUpRegistry::default(),
             power_ups: Vec::new(),
+            popups: Vec::new(),
+            hud: HudLayout::default(),
+            coop: CoopConfig {
+                default: String::from("normal"),
+                respawn_points: vec![(100., 100.), (170., 90.)],
+                difficulties: Vec::new(),
+                friendly_fire: false,
+            },
+            difficulty: Difficulty::default(),
+            continues: Difficulty::default().continues,
+            high_scores: HighScores::load(Box::new(LocalStorage::new())),
+            initials: Vec::new(),
+            fire_held: false,
+            ui: Ui::new(menus::theme()),
+            menus: vec![Menu::Title],
+            remapping: None,
+            settings: Settings::load(Box::new(LocalStorage::new())),
+            board: 0,
+            pause_held: false,
+            storage: Box::new(LocalStorage::new()),
+            quick_save_held: false,
+            quick_load_held: false,
+            level: String::from(LEVEL),
+            events: Vec::new(),
+            reported_scores: [0, 0],
+        }
+    }
+
+    /// Puts everything back in place for a new game
+    fn reset(&mut self) {
+        self.player_1 = Player::new(Vec2::new(100., 100.), 1);
+        self.player_2 = Player::new(Vec2::new(170., 90.), 2);
+        self.bullets.clear();
+        self.particles.clear();
+        self.decals.clear();
+        self.spawn_points = initial_spawn_points();
+        self.next_spawn_point = 0;
+        self.waves.reset();
+        self.enemies.clear();
+        self.boss = None;
+        self.power_ups.clear();
+        self.popups.clear();
+        self.continues = self.difficulty.continues;
+        self.initials.clear();
+    }
+
+    /// Starts a new game right away, skipping the title screen
+    pub fn restart(&mut self) {
+        self.reset();
+        self.play();
+    }
+
+    /// Closes the menus and goes on playing
+    fn play(&mut self) {
+        self.screen = Screen::Playing;
+        self.menus.clear();
+        self.remapping = None;
+        self.ui.close();
+    }
+
+    /// Gamepad buttons chosen by the players
+    pub fn bindings(&self) -> Bindings {
+        self.settings.options.bindings
+    }
+
+    /// Replaces the map with one in Tiled JSON and starts a new game on it.
+    /// High scores are kept per level `name`.
+    pub fn load_level(&mut self, name: &str, json: &JsValue) {
+        let map = TileMap::new_from_json(json);
+        self.map_cache.replace(TileLayerCache::new(&map, 0));
+        self.map.replace(map);
+        self.level = String::from(name);
+        self.restart();
+    }
+
+    /// Events that happened since the last call
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Captures the state of the running game
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            rng: random::state(),
+            player_1: self.player_1.clone(),
+            player_2: self.player_2.clone(),
+            bullets: self.bullets.clone(),
+            particles: self.particles.clone(),
+            decals: self.decals.iter().cloned().collect(),
+            spawn_points: self.spawn_points.clone(),
+            next_spawn_point: self.next_spawn_point,
+            enemies: self.enemies.clone(),
+            waves: self.waves.clone(),
+            boss: self.boss.clone(),
+            power_ups: self.power_ups.clone(),
+            popups: self.popups.clone(),
+            difficulty: self.difficulty.clone(),
+            continues: self.continues,
+        }
+    }
+
+    /// Continues the game from a snapshot
+    fn restore(&mut self, snapshot: Snapshot) {
+        random::set_state(snapshot.rng);
+        self.player_1 = snapshot.player_1;
+        self.player_2 = snapshot.player_2;
+        self.bullets = snapshot.bullets;
+        self.particles = snapshot.particles;
+        self.decals.restore(snapshot.decals);
+        self.spawn_points = snapshot.spawn_points;
+        self.next_spawn_point = snapshot.next_spawn_point;
+        self.enemies = snapshot.enemies;
+        self.waves.restore(snapshot.waves);
+        self.boss = snapshot.boss;
+        self.power_ups = snapshot.power_ups;
+        self.popups = snapshot.popups;
+        self.difficulty = snapshot.difficulty;
+        self.continues = snapshot.continues;
+        self.initials.clear();
+        self.play();
+    }
+
+    fn quick_save(&mut self) {
+        if let Ok(json) = self.snapshot().to_json() {
+            self.storage.save(QUICK_SAVE_STORAGE_KEY, &json);
+        }
+    }
+
+    fn quick_load(&mut self) {
+        let snapshot = self
+            .storage
+            .load(QUICK_SAVE_STORAGE_KEY)
+            .and_then(|json| Snapshot::from_json(&json).ok());
+        if let Some(snapshot) = snapshot {
+            self.restore(snapshot);
         }
     }
 
     pub fn preload(&self, loader: &mut Preloader) {
-        loader.load_image(String::from("assets/lorez.png"));
-        loader.load_image(String::from("assets/numbers.png"));
+        loader.load_atlas(String::from("assets/lorez.json"));
+        loader.load_font(String::from("assets/font.json"));
+        loader.load_font(String::from("assets/digits.json"));
         loader.load_json(String::from("assets/tilemap.json"));
+        loader.load_json(String::from("assets/enemies.json"));
+        loader.load_json(String::from("assets/waves.json"));
+        loader.load_json(String::from("assets/bosses.json"));
+        loader.load_json(String::from("assets/powerups.json"));
+        loader.load_json(String::from("assets/difficulty.json"));
+        loader.load_json(String::from("assets/hud.json"));
+        loader.load_json(String::from("assets/particles.json"));
     }
 
     pub fn init(&mut self, mut resources: Resources) {
-        let sprite_sheet: Option<HtmlImageElement> = resources.images.remove("assets/lorez.png");
-        if let Some(img) = sprite_sheet {
-            self.sprites.replace(img);
-        };
-        let bitmap_font: Option<HtmlImageElement> = resources.images.remove("assets/numbers.png");
-        if let Some(img) = bitmap_font {
-            self.numbers.replace(img);
+        let sprite_sheet = resources.sprite_sheets.remove("assets/lorez.json");
+        if let Some(mut sheet) = sprite_sheet {
+            self.clips = std::mem::take(&mut sheet.atlas.clips);
+            self.sheet.replace(sheet);
         };
+        let text = resources.fonts.remove("assets/font.json");
+        let digits = resources.fonts.remove("assets/digits.json");
+        if let (Some(text), Some(digits)) = (text, digits) {
+            self.fonts.replace(Fonts { text, digits });
+        }
         let tilemap = resources.jsons.remove("assets/tilemap.json");
         if let Some(map) = tilemap {
             let realmap = TileMap::new_from_json(&map);
+            self.map_cache.replace(TileLayerCache::new(&realmap, 0));
             self.map.replace(realmap);
         }
+        let enemies = resources.jsons.remove("assets/enemies.json");
+        if let Some(json) = enemies {
+            match EnemyArchetypes::new_from_json(&json) {
+                Ok(archetypes) => self.archetypes = archetypes,
+                Err(e) => console::error_2(&"Could not read assets/enemies.json".into(), &e.into()),
+            }
+        }
+        let waves = resources.jsons.remove("assets/waves.json");
+        if let Some(json) = waves {
+            self.waves = WaveDirector::new(WaveConfig::new_from_json(&json));
+        }
+        let bosses = resources.jsons.remove("assets/bosses.json");
+        if let Some(json) = bosses {
+            self.bosses = BossDefinitions::new_from_json(&json);
+        }
+        let power_ups = resources.jsons.remove("assets/powerups.json");
+        if let Some(json) = power_ups {
+            self.power_up_registry = PowerUpRegistry::new_from_json(&json);
+        }
+        let particles = resources.jsons.remove("assets/particles.json");
+        if let Some(json) = particles {
+            self.emitters = Emitters::new_from_json(&json);
+            self.decals = DecalLayer::new(self.emitters.decals);
+        }
+        let hud = resources.jsons.remove("assets/hud.json");
+        if let Some(json) = hud {
+            self.hud = HudLayout::new_from_json(&json);
+        }
+        let coop = resources.jsons.remove("assets/difficulty.json");
+        if let Some(json) = coop {
+            self.coop = CoopConfig::new_from_json(&json);
+            let options = &self.settings.options;
+            let name = options.difficulty.as_ref().unwrap_or(&self.coop.default);
+            let difficulty = self
+                .coop
+                .difficulty(name)
+                .or_else(|| self.coop.difficulty(&self.coop.default));
+            if let Some(difficulty) = difficulty {
+                self.difficulty = difficulty.clone();
+            }
+            if let Some(friendly_fire) = options.friendly_fire {
+                self.coop.friendly_fire = friendly_fire;
+            }
+            self.continues = self.difficulty.continues;
+        }
     }
 
+    /// Called when the canvas changed size. The game is drawn at a fixed
+    /// virtual resolution, so nothing depends on the size of the canvas.
+    pub fn on_resize(&mut self, _viewport: &Viewport) {}
+
     pub fn update(&mut self, ctx: &GameContext) {
+        // menus can also be confirmed by clicking or tapping the game
+        let tapped = matches!(self.screen, Screen::Results)
+            && ctx.pointer.is_pressed()
+            && ctx.pointer.position(&ctx.viewport).is_some();
+        let fire = ctx.gamepad_1.shoot || ctx.gamepad_2.shoot || tapped;
+        let fire_pressed = fire && !self.fire_held;
+        self.fire_held = fire;
+
+        let quick_save = ctx.keyboard.is_pressed(QUICK_SAVE_KEY);
+        if quick_save && !self.quick_save_held {
+            if let Screen::Playing = self.screen {
+                self.quick_save();
+            }
+        }
+        self.quick_save_held = quick_save;
+
+        let quick_load = ctx.keyboard.is_pressed(QUICK_LOAD_KEY);
+        if quick_load && !self.quick_load_held {
+            self.quick_load();
+        }
+        self.quick_load_held = quick_load;
+
+        let pause =
+            ctx.keyboard.is_pressed(PAUSE_KEY) || ctx.gamepad_1.start || ctx.gamepad_2.start;
+        let escape = ctx.keyboard.is_pressed("Escape");
+        let pause_pressed = pause && !self.pause_held;
+        let escape_pressed = escape && !self.pause_held;
+        self.pause_held = pause || escape;
+
+        match self.screen {
+            Screen::Title => {
+                self.update_menus(ctx);
+                return;
+            }
+            Screen::Paused => {
+                if pause_pressed {
+                    self.play();
+                } else {
+                    self.update_menus(ctx);
+                }
+                return;
+            }
+            Screen::Playing => {
+                if pause_pressed || escape_pressed {
+                    self.screen = Screen::Paused;
+                    self.menus = vec![Menu::Pause];
+                    return;
+                }
+                if self.game_over() {
+                    self.show_results();
+                }
+            }
+            Screen::Results => self.update_results(ctx, fire_pressed),
+        }
+
         self.player_1.update(
             &ctx.gamepad_1,
             &mut self.bullets,
-            &mut self.splatter,
-            &self.map.as_ref().unwrap(),
+            &mut self.particles,
+            &self.emitters,
+            self.map.as_ref().unwrap(),
+            &self.clips,
         );
         self.player_2.update(
             &ctx.gamepad_2,
             &mut self.bullets,
-            &mut self.splatter,
-            &self.map.as_ref().unwrap(),
+            &mut self.particles,
+            &self.emitters,
+            self.map.as_ref().unwrap(),
+            &self.clips,
         );
-        self.update_bullets(&ctx);
-        self.update_splatter();
+        self.update_bullets(ctx);
+        self.update_coop();
+        self.particles.update(&self.emitters, &mut self.decals);
+        self.decals.update();
         self.spawn_enemies();
-        self.update_enemies(&ctx);
+        self.update_enemies(ctx);
         self.update_boss();
         self.update_power_ups();
         self.update_scores();
     }
 
-    fn update_power_ups(&mut self) {
-        let player1_pos = self.player_1.pos.clone();
-        let player2_pos = self.player_2.pos.clone();
-        let mut player_1_ammo = 0;
-        let mut player_2_ammo = 0;
-        let mut player_1_health = 0;
-        let mut player_2_health = 0;
-        self.power_ups.retain(|p| {
-            let hit1 = vec2_distance(&p.0, &player1_pos) < 16.0;
-            if hit1 {
-                match p.1 {
-                    0 => player_1_ammo += 25,
-                    1 => player_1_health += 5,
-                    _ => (),
-                }
+    fn show_results(&mut self) {
+        self.screen = Screen::Results;
+        self.events.push(GameEvent::GameOver {
+            scores: [self.player_1.scoring.score, self.player_2.scoring.score],
+        });
+        let difficulty = &self.difficulty.name;
+        for player in [&self.player_1, &self.player_2].iter() {
+            let score = player.scoring.score;
+            if self.high_scores.qualifies(&self.level, difficulty, score) {
+                self.initials.push(InitialsEntry::new(player.number, score));
+            }
+        }
+    }
+
+    fn update_results(&mut self, ctx: &GameContext, fire_pressed: bool) {
+        for entry in self.initials.iter_mut() {
+            let gamepad = if entry.player == 1 {
+                &ctx.gamepad_1
+            } else {
+                &ctx.gamepad_2
+            };
+            entry.update(gamepad);
+            if entry.done() && !entry.saved {
+                entry.saved = true;
+                self.high_scores
+                    .insert(&self.level, &self.difficulty.name, entry.high_score());
             }
+        }
 
-            let hit2 = vec2_distance(&p.0, &player2_pos) < 16.0;
-            if hit2 {
-                match p.1 {
-                    0 => player_2_ammo += 25,
-                    1 => player_2_health += 5,
-                    _ => (),
+        if fire_pressed && self.initials.iter().all(|e| e.saved) {
+            self.screen = Screen::Title;
+            self.menus = vec![Menu::Title];
+        }
+    }
+
+    fn update_coop(&mut self) {
+        let player1_pos = Some(self.player_1.pos.clone()).filter(|_| self.player_1.alive());
+        let player2_pos = Some(self.player_2.pos.clone()).filter(|_| self.player_2.alive());
+        self.player_1
+            .update_state(player2_pos.as_ref(), &self.difficulty);
+        self.player_2
+            .update_state(player1_pos.as_ref(), &self.difficulty);
+
+        // Nobody is left to finish the wave, so bring the players back now
+        if self.player_1.state == PlayerState::Dead && self.player_2.state == PlayerState::Dead {
+            self.respawn_players();
+        }
+    }
+
+    /// Respawns dead players at the safest respawn point while there are
+    /// continues left
+    fn respawn_players(&mut self) {
+        let mut threats: Vec<Vec2> = self.enemies.iter().map(|e| e.pos.clone()).collect();
+        threats.extend(
+            self.bullets
+                .iter()
+                .filter(|b| !matches!(b.shooter, Shooter::Player(_)))
+                .map(|b| b.pos.clone()),
+        );
+        if let Some(boss) = &self.boss {
+            threats.push(boss.pos.clone());
+        }
+        respawn_dead(
+            [&mut self.player_1, &mut self.player_2],
+            &mut self.continues,
+            &self.coop,
+            &self.difficulty,
+            &threats,
+        );
+    }
+
+    fn game_over(&self) -> bool {
+        self.player_1.state == PlayerState::Dead
+            && self.player_2.state == PlayerState::Dead
+            && self.continues == 0
+    }
+
+    fn update_power_ups(&mut self) {
+        self.power_ups.retain_mut(PowerUp::update);
+
+        for player in [&self.player_1, &self.player_2].iter() {
+            if !player.alive() {
+                continue;
+            }
+            if let Some(radius) = player.magnet() {
+                for p in self.power_ups.iter_mut() {
+                    let distance = vec2_distance(&p.pos, &player.pos);
+                    if distance > 1.0 && distance < radius {
+                        let mut pull = Vec2::new(player.pos.x - p.pos.x, player.pos.y - p.pos.y);
+                        pull.normalize();
+                        pull.scale(3.0);
+                        p.pos.add(&pull);
+                    }
                 }
             }
+        }
 
+        let player1_pos = Some(self.player_1.pos.clone()).filter(|_| self.player_1.alive());
+        let player2_pos = Some(self.player_2.pos.clone()).filter(|_| self.player_2.alive());
+        let mut player_1_collected = Vec::new();
+        let mut player_2_collected = Vec::new();
+        self.power_ups.retain(|p| {
+            let touches = |player_pos: &Option<Vec2>| match player_pos {
+                Some(pos) => vec2_distance(&p.pos, pos) < 16.0,
+                None => false,
+            };
+            let hit1 = touches(&player1_pos);
+            let hit2 = touches(&player2_pos);
+            if hit1 {
+                player_1_collected.push(p.kind);
+            } else if hit2 {
+                player_2_collected.push(p.kind);
+            }
             !(hit1 || hit2)
         });
-        self.player_1.ammo += player_1_ammo;
-        self.player_2.ammo += player_2_ammo;
-        self.player_1.health += player_1_health;
-        self.player_2.health += player_2_health;
+        for kind in player_1_collected {
+            self.player_1.collect(kind, &self.power_up_registry);
+        }
+        for kind in player_2_collected {
+            self.player_2.collect(kind, &self.power_up_registry);
+        }
     }
 
     pub fn update_bullets(&mut self, ctx: &GameContext) {
+        let players: Vec<Vec2> = [&self.player_1, &self.player_2]
+            .iter()
+            .filter(|p| p.alive())
+            .map(|p| p.pos.clone())
+            .collect();
+        let mut enemies: Vec<Vec2> = self.enemies.iter().map(|e| e.pos.clone()).collect();
+        if let Some(boss) = &self.boss {
+            enemies.push(boss.pos.clone());
+        }
         for b in self.bullets.iter_mut() {
-            b.0.x += b.1.x;
-            b.0.y += b.1.y;
+            let target = if b.homing > 0.0 {
+                let candidates = match b.shooter {
+                    Shooter::Player(_) => &enemies,
+                    _ => &players,
+                };
+                candidates.iter().min_by(|p1, p2| {
+                    vec2_distance(p1, &b.pos)
+                        .partial_cmp(&vec2_distance(p2, &b.pos))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+            } else {
+                None
+            };
+            b.advance(target);
         }
 
+        let friendly_fire = self.coop.friendly_fire;
         let mut hit_bullets = Vec::new();
-        let mut hit_enemies = Vec::new();
+        let mut damage = Vec::new();
         let mut hit_spawn_points = Vec::new();
-        let mut hit_boss = 0;
+        let mut explosions = Vec::new();
+        let mut connected = Vec::new();
 
         for (b_idx, b) in self.bullets.iter().enumerate() {
-            let inside_bounds =
-                b.0.x > 0.0 && b.0.x < ctx.window_width && b.0.y > 0.0 && b.0.y < ctx.window_height;
+            if b.waiting() {
+                continue;
+            }
+            let inside_bounds = b.pos.x > 0.0
+                && b.pos.x < ctx.window_width
+                && b.pos.y > 0.0
+                && b.pos.y < ctx.window_height;
             if inside_bounds {
-                let t = get_tile_at(&self.map.as_ref().unwrap().layers[0].data, b.0.x, b.0.y);
+                let t = get_tile_at(&self.map.as_ref().unwrap().layers[0].data, b.pos.x, b.pos.y);
                 let hit_wall = is_wall_tile(t);
                 if hit_wall {
-                    let kind = match b.2 {
-                        Shooter::Enemy(t) => Splat::Enemy(t),
-                        _ => Splat::Sparks,
+                    let emitter = match b.shooter {
+                        Shooter::Enemy(t) => {
+                            let color = self.archetypes.archetypes[t].color;
+                            self.frames.enemy_splat(color)
+                        }
+                        _ => "sparks",
                     };
                     hit_bullets.push(b_idx);
-                    Self::add_splatter(&mut self.splatter, &b.0, 4, kind);
+                    self.particles.burst(&self.emitters, emitter, &b.pos, 4);
+                    if b.splash > 0.0 {
+                        explosions.push(b_idx);
+                    }
                 } else {
-                    match b.2 {
+                    match b.shooter {
                         Shooter::Player(player_num) => {
-                            let hit = self
-                                .enemies
+                            let hit_player = [&self.player_1, &self.player_2]
                                 .iter()
-                                .enumerate()
-                                .find(|(_e_idx, e)| vec2_distance(&e.0, &b.0) < 16.0);
+                                .filter(|p| friendly_fire && p.number != player_num)
+                                .find(|p| p.alive() && vec2_distance(&p.pos, &b.pos) < 16.0)
+                                .map(|p| p.number);
+                            let hit = self.enemies.iter().position(|e| {
+                                e.health > 0
+                                    && b.can_hit(e.hit_cooldown)
+                                    && vec2_distance(&e.pos, &b.pos) < 16.0
+                            });
                             let hit_spawn_point = self
                                 .spawn_points
                                 .iter()
                                 .enumerate()
-                                .find(|(_e_idx, e)| vec2_distance(&e.0, &b.0) < 16.0);
-                            if let Some((e_idx, e)) = hit {
+                                .find(|(_e_idx, e)| vec2_distance(&e.0, &b.pos) < 16.0);
+                            if let Some(number) = hit_player {
                                 hit_bullets.push(b_idx);
-                                hit_enemies.push(e_idx);
-                                if player_num == 1 {
-                                    self.player_1.next_score += 125;
-                                } else {
-                                    self.player_2.next_score += 125;
+                                if b.splash > 0.0 {
+                                    explosions.push(b_idx);
                                 }
-                                if random() < 0.3 {
-                                    self.power_ups
-                                        .push((b.0.clone(), if random() < 0.5 { 0 } else { 1 }));
+                                damage.push(DamageEvent::new(
+                                    b.shooter,
+                                    Target::Player(number),
+                                    b.damage,
+                                    &b.pos,
+                                    &b.vel,
+                                    b.knockback,
+                                ));
+                            } else if let Some(e_idx) = hit {
+                                connected.push(player_num);
+                                if !b.pierce {
+                                    hit_bullets.push(b_idx);
+                                }
+                                if b.splash > 0.0 {
+                                    explosions.push(b_idx);
                                 }
-                                let kind = Splat::Enemy(e.2);
-                                MyGame::add_splatter(&mut self.splatter, &b.0, 8, kind);
+                                damage.push(
+                                    DamageEvent::new(
+                                        b.shooter,
+                                        Target::Enemy(e_idx),
+                                        b.damage,
+                                        &b.pos,
+                                        &b.vel,
+                                        b.knockback,
+                                    )
+                                    .with_cooldown(b.hit_cooldown),
+                                );
                             } else if let Some((e_idx, e)) = hit_spawn_point {
                                 if e.1 != 3 && e.2 > 0 {
                                     hit_bullets.push(b_idx);
                                     hit_spawn_points.push(e_idx);
-                                    if player_num == 1 {
-                                        self.player_1.next_score += 125;
+                                    connected.push(player_num);
+                                    let scoring = if player_num == 1 {
+                                        &mut self.player_1.scoring
                                     } else {
-                                        self.player_2.next_score += 125;
-                                    }
+                                        &mut self.player_2.scoring
+                                    };
+                                    let points = scoring.award(SPAWN_POINT_HIT_SCORE);
+                                    self.popups.push(ScorePopup::new(&e.0, points));
                                     if e.2 < 2 {
-                                        self.power_ups.push((
-                                            b.0.clone(),
-                                            if random() < 0.5 { 0 } else { 1 },
-                                        ));
-                                        let kind = Splat::Enemy(e.1);
-                                        MyGame::add_splatter(&mut self.splatter, &b.0, 8, kind);
-                                        Self::add_splatter(
-                                            &mut self.splatter,
-                                            &b.0,
-                                            16,
-                                            Splat::Explosion,
-                                        )
+                                        let registry = &self.power_up_registry;
+                                        for (i, table) in
+                                            registry.spawn_point_drops.iter().enumerate()
+                                        {
+                                            let pos = Vec2::new(b.pos.x + 16. * i as f64, b.pos.y);
+                                            if let Some(p) =
+                                                table.roll().and_then(|n| registry.spawn(n, &pos))
+                                            {
+                                                self.power_ups.push(p);
+                                            }
+                                        }
+                                        let emitter = self.frames.enemy_splat(e.1);
+                                        self.particles.emit(&self.emitters, emitter, &b.pos);
+                                        self.particles.emit(&self.emitters, "explosion", &b.pos);
                                     }
-                                    let kind = Splat::Enemy(e.1);
-                                    MyGame::add_splatter(&mut self.splatter, &b.0, 8, kind);
+                                    let emitter = self.frames.enemy_splat(e.1);
+                                    self.particles.emit(&self.emitters, emitter, &b.pos);
                                 }
                             } else if let Some(boss) = &self.boss {
                                 let bp = boss.pos.clone();
-                                if vec2_distance(&b.0, &bp) < 32.0 {
-                                    hit_bullets.push(b_idx);
-                                    hit_boss += 1;
-                                    if player_num == 1 {
-                                        self.player_1.next_score += 255;
-                                    } else {
-                                        self.player_2.next_score += 250;
+                                if b.can_hit(boss.hit_cooldown) && vec2_distance(&b.pos, &bp) < 32.0
+                                {
+                                    if !b.pierce {
+                                        hit_bullets.push(b_idx);
+                                    }
+                                    if b.splash > 0.0 {
+                                        explosions.push(b_idx);
                                     }
-                                    MyGame::add_splatter(
-                                        &mut self.splatter,
-                                        &b.0,
-                                        8,
-                                        Splat::Enemy(0),
+                                    damage.push(
+                                        DamageEvent::new(
+                                            b.shooter,
+                                            Target::Boss,
+                                            b.damage,
+                                            &b.pos,
+                                            &b.vel,
+                                            0.0,
+                                        )
+                                        .with_cooldown(b.hit_cooldown),
                                     );
-                                    MyGame::add_splatter(&mut self.splatter, &b.0, 8, Splat::Blood);
+                                    connected.push(player_num);
+                                    let scoring = if player_num == 1 {
+                                        &mut self.player_1.scoring
+                                    } else {
+                                        &mut self.player_2.scoring
+                                    };
+                                    scoring.award(BOSS_HIT_SCORE);
+                                    let emitter = self.frames.enemy_splat(0);
+                                    self.particles.emit(&self.emitters, emitter, &b.pos);
+                                    self.particles.emit(&self.emitters, "blood", &b.pos);
                                 }
                             }
                         }
-                        Shooter::Enemy(t) => {
-                            let hit1 = vec2_distance(&self.player_1.pos, &b.0) < 16.0;
-                            let hit2 = vec2_distance(&self.player_2.pos, &b.0) < 16.0;
-                            if hit1 {
-                                self.player_1.health -= 1;
-                            }
-                            if hit2 {
-                                self.player_2.health -= 1;
-                            }
-                            if hit1 || hit2 {
-                                hit_bullets.push(b_idx);
-                                MyGame::add_splatter(&mut self.splatter, &b.0, 8, Splat::Blood);
-                                MyGame::add_splatter(&mut self.splatter, &b.0, 8, Splat::Enemy(t));
-                            }
-                        }
-                        Shooter::Boss => {
-                            let hit1 = vec2_distance(&self.player_1.pos, &b.0) < 16.0;
-                            let hit2 = vec2_distance(&self.player_2.pos, &b.0) < 16.0;
-                            if hit1 {
-                                self.player_1.health -= 3;
-                            }
-                            if hit2 {
-                                self.player_2.health -= 3;
-                            }
-                            if hit1 || hit2 {
-                                hit_bullets.push(b_idx);
-                                MyGame::add_splatter(&mut self.splatter, &b.0, 8, Splat::Enemy(0));
+                        Shooter::Enemy(_) | Shooter::Boss => {
+                            for p in [&self.player_1, &self.player_2].iter() {
+                                if p.alive() && vec2_distance(&p.pos, &b.pos) < 16.0 {
+                                    hit_bullets.push(b_idx);
+                                    damage.push(DamageEvent::new(
+                                        b.shooter,
+                                        Target::Player(p.number),
+                                        b.damage,
+                                        &b.pos,
+                                        &b.vel,
+                                        b.knockback,
+                                    ));
+                                    let color = match b.shooter {
+                                        Shooter::Enemy(t) => self.archetypes.archetypes[t].color,
+                                        _ => 0,
+                                    };
+                                    let emitter = self.frames.enemy_splat(color);
+                                    self.particles.emit(&self.emitters, emitter, &b.pos);
+                                }
                             }
                         }
                     }
                 }
             }
         }
+        for (b_idx, b) in self.bullets.iter().enumerate() {
+            if b.lifetime == 0 && b.splash > 0.0 && !explosions.contains(&b_idx) {
+                explosions.push(b_idx);
+            }
+        }
+        for b_idx in explosions.iter() {
+            let b = &self.bullets[*b_idx];
+            if let Shooter::Player(player_num) = b.shooter {
+                let splash = |pos: &Vec2, target: Target| {
+                    let away = Vec2::new(pos.x - b.pos.x, pos.y - b.pos.y);
+                    DamageEvent::new(b.shooter, target, b.damage, pos, &away, b.knockback)
+                };
+                for (e_idx, e) in self.enemies.iter().enumerate() {
+                    if vec2_distance(&e.pos, &b.pos) < b.splash {
+                        damage.push(splash(&e.pos, Target::Enemy(e_idx)));
+                    }
+                }
+                if let Some(boss) = &self.boss {
+                    if vec2_distance(&boss.pos, &b.pos) < b.splash + 16.0 {
+                        damage.push(splash(&boss.pos, Target::Boss));
+                    }
+                }
+                for p in [&self.player_1, &self.player_2].iter() {
+                    let hurts = friendly_fire && p.number != player_num && p.alive();
+                    if hurts && vec2_distance(&p.pos, &b.pos) < b.splash {
+                        damage.push(splash(&p.pos, Target::Player(p.number)));
+                    }
+                }
+            }
+            self.particles.emit(&self.emitters, "explosion", &b.pos);
+        }
+        self.apply_damage(&damage);
+        for player_num in connected {
+            self.player_mut(player_num).scoring.stats.shots_hit += 1;
+        }
+
         hit_bullets.sort();
+        hit_bullets.dedup();
         hit_bullets.reverse();
         for idx in hit_bullets.iter() {
             self.bullets.remove(*idx);
         }
+        self.bullets.retain(|b| {
+            b.lifetime > 0
+                && b.pos.x > 0.0
+                && b.pos.x < ctx.window_width
+                && b.pos.y > 0.0
+                && b.pos.y < ctx.window_height
+        });
 
-        hit_enemies.reverse();
-        for idx in hit_enemies.iter() {
-            self.enemies.remove(*idx);
-        }
+        self.enemies.retain(|e| e.health > 0);
 
         let mut destroyed_spawn_points = false;
         for idx in hit_spawn_points.iter() {
@@ -420,352 +1169,594 @@ impl MyGame {
 
         let active_spawn_point = self.spawn_points.iter().find(|sp| sp.2 > 0);
         if destroyed_spawn_points && active_spawn_point.is_none() {
-            self.boss.replace(Boss {
-                pos: Vec2::new(450., -55.),
-                health: 80,
-                heat: 100.,
-                charging: false,
-                tx: 300. + random() * 300.,
-            });
+            if let Some(def) = self.bosses.bosses.first() {
+                self.boss.replace(Boss::new(0, def));
+            }
         }
 
-        if let Some(boss) = &mut self.boss {
-            boss.health -= hit_boss;
+        if let Some(boss) = &self.boss {
             if boss.health < 1 {
                 self.boss.take();
             }
         }
     }
 
-    fn add_splatter(
-        splatter: &mut Vec<(Vec2, Splat, Vec2, i8)>,
-        pos: &Vec2,
-        amount: u8,
-        kind: Splat,
-    ) {
-        for i in 0..amount {
-            let v = match kind {
-                Splat::Explosion => 1.0 + 2.0 * random(),
-                _ => 0.5 + 4.0 * random(),
-            };
-            splatter.push((
-                pos.clone(),
-                kind,
-                Vec2::new(
-                    v * f64::cos((i as f64 / amount as f64 + 8.0 * random()) * PI),
-                    v * f64::sin((i as f64 / amount as f64 + 8.0 * random()) * PI),
-                ),
-                match kind {
-                    Splat::Explosion => 24 + (8. * random()) as i8,
-                    _ => (10. + 5. * random()) as i8,
-                },
-            ));
+    fn apply_damage(&mut self, events: &[DamageEvent]) {
+        for event in events.iter() {
+            match event.target {
+                Target::Player(number) => {
+                    let player = self.player_mut(number);
+                    if player.hit(event.amount, &event.knockback) {
+                        player.scoring.stats.damage_taken += event.amount;
+                        self.particles.emit(&self.emitters, "blood", &event.pos);
+                    }
+                }
+                Target::Enemy(e_idx) => self.hit_enemy(e_idx, event),
+                Target::Boss => {
+                    if let Some(boss) = &mut self.boss {
+                        if take_cooldown(&mut boss.hit_cooldown, event) {
+                            boss.health -= event.amount;
+                        }
+                    }
+                }
+            }
         }
     }
 
-    fn update_splatter(&mut self) {
-        for s in self.splatter.iter_mut() {
-            s.0.add(&s.2);
-            s.3 -= 1;
+    fn hit_enemy(&mut self, e_idx: usize, event: &DamageEvent) {
+        let pos = &event.pos;
+        let e = &mut self.enemies[e_idx];
+        if e.health < 1 || !take_cooldown(&mut e.hit_cooldown, event) {
+            return;
+        }
+        e.health -= event.amount;
+        e.knockback.add(&event.knockback);
+        let kind = e.kind;
+        let archetype = &self.archetypes.archetypes[kind];
+        let color = archetype.color;
+        if e.health < 1 {
+            self.waves.record_kill();
+            let registry = &self.power_up_registry;
+            if let Some(p) = archetype.drops.roll().and_then(|n| registry.spawn(n, pos)) {
+                self.power_ups.push(p);
+            }
+            let points = archetype.score;
+            if let Shooter::Player(number) = event.source {
+                let points = self.player_mut(number).scoring.kill(kind, points);
+                self.popups.push(ScorePopup::new(pos, points));
+            }
+            let emitter = self.frames.enemy_splat(color);
+            self.particles.emit(&self.emitters, emitter, pos);
+            self.leave_corpse(e_idx);
+        } else {
+            let emitter = self.frames.enemy_splat(color);
+            self.particles.burst(&self.emitters, emitter, pos, 2);
+        }
+    }
+
+    /// Leaves a dead enemy on the floor, on its side and darkened
+    fn leave_corpse(&mut self, e_idx: usize) {
+        let e = &self.enemies[e_idx];
+        if let Some(frame) = e.animator.frame(&self.clips) {
+            let mut corpse = Decal::new(frame, &e.pos);
+            corpse.rotation = if random() < 0.5 { -PI / 2. } else { PI / 2. };
+            corpse.tint = Some(Color::BLACK.with_alpha(0.5));
+            corpse.alpha = 0.8;
+            self.decals.add(corpse);
+        }
+    }
+
+    fn player_mut(&mut self, number: u8) -> &mut Player {
+        if number == 1 {
+            &mut self.player_1
+        } else {
+            &mut self.player_2
         }
-        self.splatter.retain(|s| s.3 > 0);
     }
 
     fn spawn_enemies(&mut self) {
-        let no_boss = self.boss.is_none();
-        if random() < 0.02 && no_boss {
-            let idx = (4. * random()).floor() as usize;
-            let spawn_points = &self.spawn_points[idx];
-            if spawn_points.2 > 0 {
-                self.enemies.push((
-                    Vec2::new(spawn_points.0.x, spawn_points.0.y),
-                    random() * PI,
-                    spawn_points.1,
-                ));
-            }
+        if self.boss.is_some() {
+            return;
+        }
+        let health = (self.player_1.health.max(0) + self.player_2.health.max(0)) as f64
+            / (2 * PLAYER_HEALTH) as f64;
+        let wave = self.waves.wave;
+        let orders = self.waves.update(self.enemies.len(), health.min(1.0));
+        for order in orders.iter() {
+            self.spawn_enemy(order);
+        }
+        if self.waves.wave > wave {
+            self.respawn_players();
+        }
+    }
+
+    fn spawn_enemy(&mut self, order: &SpawnOrder) {
+        let archetype = order
+            .archetype
+            .as_ref()
+            .and_then(|name| self.archetypes.find(name));
+        let color = archetype.map(|a| self.archetypes.archetypes[a].color);
+        let players: Vec<Vec2> = [&self.player_1, &self.player_2]
+            .iter()
+            .filter(|p| p.alive())
+            .map(|p| p.pos.clone())
+            .collect();
+        let candidates = order.spawn_points.candidates(
+            &self.spawn_points,
+            color,
+            &players,
+            &mut self.next_spawn_point,
+        );
+        if candidates.is_empty() {
+            return;
+        }
+
+        let idx = candidates[(candidates.len() as f64 * random()).floor() as usize];
+        let spawn_point = &self.spawn_points[idx];
+        let kind = archetype.or_else(|| self.archetypes.pick_for_color(spawn_point.1));
+        if let Some(kind) = kind {
+            self.enemies.push(Enemy {
+                pos: Vec2::new(spawn_point.0.x, spawn_point.0.y),
+                dir: random() * PI,
+                kind,
+                health: self.archetypes.archetypes[kind].health,
+                spin: 0.0,
+                knockback: Vec2::new(0., 0.),
+                animator: Animator::new(&self.archetypes.archetypes[kind].walk_right),
+                hit_cooldown: 0,
+            });
         }
     }
 
     fn update_scores(&mut self) {
-        if self.player_1.score < self.player_1.next_score {
-            let delta = self.player_1.next_score - self.player_1.score;
-            self.player_1.score += if delta > 50 { 25 } else { 5 };
+        self.player_1.scoring.update();
+        self.player_2.scoring.update();
+        for (i, player) in [&self.player_1, &self.player_2].iter().enumerate() {
+            if player.scoring.score != self.reported_scores[i] {
+                self.reported_scores[i] = player.scoring.score;
+                self.events.push(GameEvent::Score {
+                    player: player.number,
+                    score: player.scoring.score,
+                });
+            }
         }
-        if self.player_2.score < self.player_2.next_score {
-            let delta = self.player_2.next_score - self.player_2.score;
-            self.player_2.score += if delta > 50 { 25 } else { 5 };
+        for popup in self.popups.iter_mut() {
+            popup.update();
         }
+        self.popups.retain(|p| p.ttl > 0);
     }
+
     fn update_enemies(&mut self, ctx: &GameContext) {
+        let players = [&self.player_1, &self.player_2];
         for b in self.enemies.iter_mut() {
-            let dir = Vec2::new(f64::cos(b.1), f64::sin(b.1));
-            let t = get_tile_at(
-                &self.map.as_ref().unwrap().layers[0].data,
-                b.0.x + dir.x,
-                b.0.y + dir.y + 8.0,
-            );
+            let archetype = &self.archetypes.archetypes[b.kind];
+            b.hit_cooldown = b.hit_cooldown.saturating_sub(1);
 
-            let is_wall = is_wall_tile(t);
-            if !is_wall {
-                b.0.x += dir.x;
-                b.0.y += dir.y;
-            }
-
-            if random() < 0.01 {
-                let target = if random() < 0.5 {
+            if let Behaviour::Chase = archetype.behaviour {
+                let d1 = vec2_distance(&b.pos, &self.player_1.pos);
+                let d2 = vec2_distance(&b.pos, &self.player_2.pos);
+                let target = if !self.player_2.alive() || (self.player_1.alive() && d1 < d2) {
                     &self.player_1.pos
                 } else {
                     &self.player_2.pos
                 };
-                let mut bullet_velocity = Vec2::new(target.x - b.0.x, target.y - b.0.y);
-                bullet_velocity.normalize();
-                bullet_velocity.scale(7.0);
-                self.bullets
-                    .push(Bullet(b.0.clone(), bullet_velocity, Shooter::Enemy(b.2)));
+                b.dir = f64::atan2(target.y - b.pos.y, target.x - b.pos.x);
+            }
+
+            if let Behaviour::Wander | Behaviour::Chase = archetype.behaviour {
+                let mut dir = Vec2::new(f64::cos(b.dir), f64::sin(b.dir));
+                dir.scale(archetype.speed);
+                let t = get_tile_at(
+                    &self.map.as_ref().unwrap().layers[0].data,
+                    b.pos.x + dir.x,
+                    b.pos.y + dir.y + 8.0,
+                );
+
+                let is_wall = is_wall_tile(t);
+                if !is_wall {
+                    b.pos.add(&dir);
+                }
+            }
+
+            let t = get_tile_at(
+                &self.map.as_ref().unwrap().layers[0].data,
+                b.pos.x + b.knockback.x,
+                b.pos.y + b.knockback.y + 8.0,
+            );
+            apply_knockback(&mut b.pos, &mut b.knockback, is_wall_tile(t));
+
+            if f64::cos(b.dir) < 0. {
+                b.animator.play(&archetype.walk_left);
+            } else {
+                b.animator.play(&archetype.walk_right);
+            }
+            b.animator.update(&self.clips);
+
+            let firing = random() < archetype.fire_chance;
+            if let Some(target) = pick_target(&players).filter(|_| firing) {
+                archetype.bullets.emit(
+                    &b.pos,
+                    &target.pos,
+                    Shooter::Enemy(b.kind),
+                    &mut b.spin,
+                    &mut self.bullets,
+                );
             }
-            b.1 += random() * 0.5 - 0.25;
+            b.dir += random() * 0.5 - 0.25;
         }
         self.enemies.retain(|b| {
-            b.0.x > 0.0 && b.0.x < ctx.window_width && b.0.y > 0.0 && b.0.y < ctx.window_height
+            b.pos.x > 0.0
+                && b.pos.x < ctx.window_width
+                && b.pos.y > 0.0
+                && b.pos.y < ctx.window_height
         });
     }
 
     fn update_boss(&mut self) {
         if let Some(boss) = &mut self.boss {
-            if boss.pos.y < 255.0 {
-                boss.pos.y += 1.;
-            } else {
-                boss.heat += 1.;
-                if !boss.charging {
-                    if boss.pos.x < boss.tx - 2. {
-                        boss.pos.x += 1.5;
-                    } else if boss.pos.x > boss.tx + 2. {
-                        boss.pos.x -= 1.5;
-                    } else {
-                        boss.charging = true;
-                        boss.heat = 0.;
-                    }
-                } else if boss.heat > 25. {
-                    boss.tx += 100.0 - 200. * random();
-                    if boss.tx < 300. {
-                        boss.tx = 300.
-                    } else if boss.tx > 7. * 100. {
-                        boss.tx = 700.
-                    }
-
-                    boss.charging = false;
-                    boss.heat = 0.;
-                    let offset = random();
-                    for i in 0..10 {
-                        let a = offset + (PI / 5.0) * i as f64;
-                        let v = 5.;
-                        let p = boss.pos.clone();
-                        self.bullets.push(Bullet(
-                            p,
-                            Vec2::new(v * f64::cos(a), v * f64::sin(a)),
-                            Shooter::Boss,
-                        ));
+            let def = &self.bosses.bosses[boss.kind];
+            let targets: Vec<Vec2> = [&self.player_1, &self.player_2]
+                .iter()
+                .filter(|p| p.alive())
+                .map(|p| p.pos.clone())
+                .collect();
+            let summons = boss.update(def, &targets, &mut self.bullets);
+            let archetypes = &self.archetypes;
+            for summon in summons.iter() {
+                let kind = summon
+                    .archetype
+                    .as_ref()
+                    .and_then(|name| archetypes.find(name));
+                for _ in 0..summon.count {
+                    let count = archetypes.archetypes.len();
+                    if count == 0 {
+                        break;
                     }
+                    let kind = kind.unwrap_or((count as f64 * random()).floor() as usize);
+                    let a = random() * 2. * PI;
+                    self.enemies.push(Enemy {
+                        pos: Vec2::new(boss.pos.x + 32. * a.cos(), boss.pos.y + 32. * a.sin()),
+                        dir: a,
+                        kind,
+                        health: archetypes.archetypes[kind].health,
+                        spin: 0.0,
+                        knockback: Vec2::new(0., 0.),
+                        animator: Animator::new(&archetypes.archetypes[kind].walk_right),
+                        hit_cooldown: 0,
+                    });
                 }
             }
         }
     }
 
-    pub fn render(&self, renderer: &CanvasRenderer, ctx: &GameContext) {
+    pub fn render(&self, renderer: &dyn Renderer, ctx: &GameContext) {
         renderer.clear();
 
-        let option = self.sprites.as_ref();
-        if let Some(image) = option {
-            if let Some(map) = self.map.as_ref() {
-                renderer.draw_map(&map, image);
+        let option = self.sheet.as_ref();
+        if let Some(sheet) = option {
+            if let (Some(map), Some(cache)) = (&self.map, &self.map_cache) {
+                renderer.draw_tile_layer(cache, map, &sheet.image);
             }
 
-            for sp in self.spawn_points.iter() {
-                let idx = 144
-                    + sp.1 * 2
-                    + if (ctx.tick as u32 + sp.1 as u32 * 20) % 60 > 30 {
-                        0
-                    } else {
-                        8
-                    };
-                renderer.draw_block(
-                    &image,
-                    if sp.2 < 1 { 150 } else { idx },
-                    sp.0.x - 16.,
-                    sp.0.y - 8.,
-                    2,
-                    1,
-                );
+            if let Screen::Title = self.screen {
+                self.draw_menus(renderer);
+                return;
             }
 
-            if self.player_1.health > 0 {
-                let frame = if self.player_1.moving {
-                    (ctx.tick / 4) % 2
-                } else {
+            renderer.draw_decals(&self.decals, sheet);
+
+            for sp in self.spawn_points.iter() {
+                let frame = if (ctx.tick as u32 + sp.1 as u32 * 20) % 60 > 30 {
                     0
-                } as usize;
-                let sprite = if self.player_1.face_left {
-                    PLAYER_1_LEFT_CYCLE[frame]
                 } else {
-                    PLAYER_1_RIGHT_CYCLE[frame]
+                    1
                 };
-                renderer.draw_sprite(
-                    image,
-                    sprite,
-                    self.player_1.pos.x - 8.,
-                    self.player_1.pos.y - 8.,
-                );
-                if self.player_1.aim.length() > 0.1 {
-                    renderer.draw_sprite(
-                        &image,
-                        164,
-                        self.player_1.pos.x - 8. + 16. * self.player_1.aim.x,
-                        self.player_1.pos.y - 8. + 16. * self.player_1.aim.y,
-                    );
+                let sprite = if sp.2 < 1 {
+                    self.frames.spawn_point_destroyed
+                } else {
+                    self.frames.spawn_points[sp.1 as usize][frame]
+                };
+                renderer.draw_frame(sheet, sprite, sp.0.x, sp.0.y);
+            }
+
+            self.draw_player(renderer, sheet, &self.player_1, ctx);
+            self.draw_player(renderer, sheet, &self.player_2, ctx);
+
+            for b in self.enemies.iter() {
+                if let Some(sprite) = b.animator.frame(&self.clips) {
+                    renderer.draw_frame(sheet, sprite, b.pos.x, b.pos.y);
                 }
-            } else {
-                renderer.draw_sprite(&image, 167, self.player_1.pos.x, self.player_1.pos.y);
             }
 
-            if self.player_2.health > 0 {
-                let frame = if self.player_2.moving {
-                    (ctx.tick / 4) % 2
-                } else {
-                    0
-                } as usize;
-                let sprite = if self.player_2.face_left {
-                    PLAYER_2_LEFT_CYCLE[frame]
+            if let Some(boss) = &self.boss {
+                let def = &self.bosses.bosses[boss.kind];
+                let telegraph = if boss.in_transition() {
+                    (ctx.tick / 4).is_multiple_of(2)
                 } else {
-                    PLAYER_2_RIGHT_CYCLE[frame]
+                    boss.telegraphing(def) || boss.charging(def)
                 };
-                renderer.draw_sprite(
-                    image,
-                    sprite,
-                    self.player_2.pos.x - 8.,
-                    self.player_2.pos.y - 8.,
-                );
-                if self.player_2.aim.length() > 0.1 {
-                    renderer.draw_sprite(
-                        &image,
-                        164,
-                        self.player_2.pos.x - 8. + 16. * self.player_2.aim.x,
-                        self.player_2.pos.y - 8. + 16. * self.player_2.aim.y,
-                    );
-                }
-            } else {
-                renderer.draw_sprite(&image, 167, self.player_2.pos.x, self.player_2.pos.y);
-            }
-
-            for (bi, b) in self.enemies.iter().enumerate() {
-                let dx = f64::cos(b.1);
-                let frame = ((ctx.tick as u32 + bi as u32) / 5 % 2) as usize;
-
-                let left = dx < 0.;
-                let table = match (b.2, left) {
-                    (0, true) => ENEMY_0_LEFT_CYCLE,
-                    (0, false) => ENEMY_0_RIGHT_CYCLE,
-                    (1, true) => ENEMY_1_LEFT_CYCLE,
-                    (1, false) => ENEMY_1_RIGHT_CYCLE,
-                    (2, true) => ENEMY_2_LEFT_CYCLE,
-                    (2, false) => ENEMY_2_RIGHT_CYCLE,
-                    _ => ENEMY_0_LEFT_CYCLE,
+                let sprite = if telegraph {
+                    def.telegraph_sprite
+                } else {
+                    def.sprite
                 };
-                let sprite = table[frame];
-                renderer.draw_sprite(&image, sprite, b.0.x - 8., b.0.y - 8.);
+                renderer.draw_frame(sheet, sprite, boss.pos.x, boss.pos.y);
             }
 
-            if let Some(boss) = &self.boss {
-                if boss.charging {
-                    renderer.draw_block(&image, 176, boss.pos.x - 12., boss.pos.y - 12., 3, 3)
+            for b in self.bullets.iter() {
+                // delayed bullets show up when they fire
+                if b.waiting() {
+                    continue;
+                }
+                if b.oriented {
+                    let transform = Transform::rotated(b.vel.y.atan2(b.vel.x));
+                    renderer.draw_frame_transformed(sheet, b.sprite, b.pos.x, b.pos.y, &transform);
                 } else {
-                    renderer.draw_block(&image, 179, boss.pos.x - 12., boss.pos.y - 12., 3, 3)
+                    renderer.draw_frame(sheet, b.sprite, b.pos.x, b.pos.y);
                 }
+            }
 
-                renderer.draw_rect("black", boss.pos.x - 12., boss.pos.y + 40., 48., 2.);
-                renderer.draw_rect(
-                    "red",
-                    boss.pos.x - 12.,
-                    boss.pos.y + 40.,
-                    (48. * boss.health as f64) / 80.0,
-                    2.,
+            for p in self.power_ups.iter() {
+                if self.power_up_registry.blinking(p) && (ctx.tick / 4).is_multiple_of(2) {
+                    continue;
+                }
+                let sprite = self.power_up_registry.power_ups[p.kind].sprite;
+                renderer.draw_frame(sheet, sprite, p.pos.x, p.pos.y);
+            }
+
+            self.particles
+                .draw(renderer, sheet, &self.clips, &self.emitters);
+
+            let fonts = self.fonts.as_ref().unwrap();
+            let popups = TextStyle::scaled(2.).centered();
+            for popup in self.popups.iter() {
+                let text = format!("{}", popup.points);
+                renderer.draw_text(
+                    &fonts.digits,
+                    popup.pos.x,
+                    popup.pos.y - 16.,
+                    &text,
+                    &popups,
                 );
             }
 
-            for b in self.bullets.iter() {
-                let sprite = match b.2 {
-                    Shooter::Player(_) => 165,
-                    Shooter::Enemy(t) => match t {
-                        1 => 130,
-                        2 => 132,
-                        _ => 128,
-                    },
-                    Shooter::Boss => 176,
-                };
-                renderer.draw_sprite(&image, sprite, b.0.x - 8., b.0.y - 8.);
+            self.draw_hud(renderer, sheet, fonts, ctx);
+
+            match self.screen {
+                Screen::Results => self.draw_results(renderer, sheet, fonts, ctx),
+                Screen::Paused => self.draw_menus(renderer),
+                _ => {}
             }
+        } else {
+        }
+    }
 
-            for b in self.power_ups.iter() {
-                match b.1 {
-                    0 => renderer.draw_sprite(&image, 106, b.0.x - 8., b.0.y - 8.),
-                    1 => renderer.draw_sprite(&image, 98, b.0.x - 8., b.0.y - 8.),
-                    _ => (),
+    fn draw_player(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        player: &Player,
+        ctx: &GameContext,
+    ) {
+        let sprite = player.animator.frame(&self.clips);
+        match player.state {
+            PlayerState::Alive => {
+                let flashing = player.invulnerable > 0 && (ctx.tick / 3).is_multiple_of(2);
+                if let (Some(sprite), false) = (sprite, flashing) {
+                    renderer.draw_frame(sheet, sprite, player.pos.x, player.pos.y);
+                }
+                if player.aim.length() > 0.1 {
+                    renderer.draw_frame(
+                        sheet,
+                        self.frames.aim,
+                        player.pos.x + 16. * player.aim.x,
+                        player.pos.y + 16. * player.aim.y,
+                    );
                 }
             }
+            PlayerState::Downed { bleed_out, revive } => {
+                if let Some(sprite) = sprite {
+                    // Lying on the floor, fading in and out
+                    let pulse = (ctx.tick as f64 / 8.).sin();
+                    let transform = Transform {
+                        rotation: if player.face_left { -PI / 2. } else { PI / 2. },
+                        tint: Some(Color::RED.with_alpha(0.3 + 0.2 * pulse)),
+                        ..Transform::default()
+                    };
+                    renderer.draw_frame_transformed(
+                        sheet,
+                        sprite,
+                        player.pos.x,
+                        player.pos.y,
+                        &transform,
+                    );
+                }
+                let x = player.pos.x - 8.;
+                let y = player.pos.y - 14.;
+                let bleed_out = bleed_out as f64 / u32::max(1, self.difficulty.bleed_out) as f64;
+                let revive = revive as f64 / u32::max(1, self.difficulty.revive_ticks) as f64;
+                renderer.draw_rect("black", x, y, 16., 4.);
+                renderer.draw_rect("red", x, y, 16. * bleed_out, 2.);
+                renderer.draw_rect("lime", x, y + 2., 16. * revive.min(1.), 2.);
+            }
+            PlayerState::Dead => {
+                renderer.draw_frame(
+                    sheet,
+                    self.frames.gravestone,
+                    player.pos.x + 8.,
+                    player.pos.y + 8.,
+                );
+            }
+        }
+    }
 
-            for b in self.splatter.iter() {
-                let frame = if b.3 < 10 { 8 } else { 0 };
-                let idx = match b.1 {
-                    Splat::Sparks => 134 + frame,
-                    Splat::Water => 135 + frame,
-                    Splat::Blood => 166 + frame,
-                    Splat::Enemy(e) => 129 + e * 2 + frame,
-                    Splat::Explosion => 208 + u8::min(7, 8 - (b.3 / 4) as u8),
-                };
-                renderer.draw_sprite(&image, idx, b.0.x - 8., b.0.y - 8.);
-            }
-
-            let numbers = &self.numbers.as_ref().unwrap();
-
-            let mut px = 10.0;
-            let score = format!("{}", self.player_1.score);
-            renderer.draw_numbers(&numbers, px, 10., &score);
-            px += 6.0 * 8.0;
-
-            let ammo = format!("{}", self.player_1.ammo);
-            renderer.draw_ammo(&numbers, px, 10.0);
-            px += 12.0;
-            renderer.draw_numbers(&numbers, px, 10., &ammo);
-            px += 5.0 * 10.0;
-            renderer.draw_hearts(&numbers, px, 10., self.player_1.health);
-
-            let mut px = ctx.window_width - 5.0 * 12.0;
-            renderer.draw_hearts(&numbers, px, 10., self.player_2.health);
-
-            let ammo = format!("{}", self.player_2.ammo);
-            px -= 5.0 * 10.0;
-            renderer.draw_numbers(&numbers, px, 10., &ammo);
-            px -= 12.0;
-            renderer.draw_ammo(&numbers, px, 10.0);
-
-            let score = format!("{}", self.player_2.score);
-            px -= 6.0 * 8.0;
-            renderer.draw_numbers(&numbers, px, 10., &score);
-
-            if self.player_1.health < 1 && self.player_2.health < 1 {
-                renderer.draw_rect("#0006", 96., 270. - 48., 1920. / 2. - 192., 96.);
-                let text_x = (((1920 / 2) - 13 * 32) / 2) as f64;
-                renderer.draw_big_text(
-                    &self.numbers.as_ref().unwrap(),
-                    text_x,
-                    270. - 16.,
-                    &"@ GAME OVER @",
-                )
+    fn draw_results(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        fonts: &Fonts,
+        ctx: &GameContext,
+    ) {
+        renderer.draw_rect(
+            "#000a",
+            96.,
+            48.,
+            ctx.window_width - 192.,
+            ctx.window_height - 96.,
+        );
+        let center = ctx.window_width / 2.;
+        let heading = TextStyle::scaled(2.).centered();
+        renderer.draw_text(&fonts.text, center, 72., "@ GAME OVER @", &heading);
+
+        let column = ctx.window_width / 4.;
+        for entry in self.initials.iter() {
+            let x = if entry.player == 1 {
+                column
+            } else {
+                3. * column
+            };
+            self.draw_initials(renderer, fonts, entry, x - 48., 430., ctx);
+        }
+        if self.initials.iter().all(|e| e.saved) && (ctx.tick / 15).is_multiple_of(2) {
+            let y = ctx.window_height - 96.;
+            renderer.draw_text(&fonts.text, center, y, "PRESS FIRE", &heading);
+        }
+
+        self.draw_stats(
+            renderer,
+            sheet,
+            fonts,
+            &self.player_1,
+            &player_clip(1, false, false),
+            column,
+        );
+        self.draw_stats(
+            renderer,
+            sheet,
+            fonts,
+            &self.player_2,
+            &player_clip(2, false, true),
+            3. * column,
+        );
+    }
+
+    fn draw_initials(
+        &self,
+        renderer: &dyn Renderer,
+        fonts: &Fonts,
+        entry: &InitialsEntry,
+        x: f64,
+        y: f64,
+        ctx: &GameContext,
+    ) {
+        let style = TextStyle::scaled(2.);
+        let initials = entry.initials();
+        renderer.draw_text(&fonts.text, x, y, &initials, &style);
+        if !entry.done() && (ctx.tick / 8).is_multiple_of(2) {
+            let (typed, _) = fonts.text.font.measure(&initials[..entry.cursor], &style);
+            renderer.draw_rect("white", x + typed, y + 34., 30., 4.);
+        }
+    }
+
+    /// Draws the final score and statistics of a player centered around `x`
+    fn draw_stats(
+        &self,
+        renderer: &dyn Renderer,
+        sheet: &SpriteSheet,
+        fonts: &Fonts,
+        player: &Player,
+        clip: &str,
+        x: f64,
+    ) {
+        let style = TextStyle::scaled(2.);
+        let stats = &player.scoring.stats;
+        let x = x - 64.;
+        let mut y = 136.;
+        if let Some(sprite) = first_frame(&self.clips, clip) {
+            renderer.draw_frame(sheet, sprite, x + 8., y + 8.);
+        }
+        let score = format!("{}", player.scoring.score);
+        renderer.draw_text(
+            &fonts.digits,
+            x + 32.,
+            y - 2.,
+            &score,
+            &TextStyle::scaled(4.),
+        );
+        y += 40.;
+
+        for (kind, kills) in stats.kills.iter().enumerate() {
+            if *kills == 0 {
+                continue;
             }
-        } else {
+            let archetype = &self.archetypes.archetypes[kind];
+            if let Some(sprite) = first_frame(&self.clips, &archetype.walk_right) {
+                renderer.draw_frame(sheet, sprite, x + 8., y + 8.);
+            }
+            renderer.draw_text(
+                &fonts.digits,
+                x + 32.,
+                y + 3.,
+                &format!("{}", kills),
+                &style,
+            );
+            y += 24.;
+        }
+
+        y += 8.;
+        renderer.draw_frame(sheet, self.frames.aim, x + 8., y + 8.);
+        let accuracy = format!("{}", stats.accuracy());
+        renderer.draw_text(&fonts.digits, x + 32., y + 3., &accuracy, &style);
+        y += 24.;
+        renderer.draw_hearts(&fonts.digits.image, x + 2., y + 3., 5);
+        let damage = format!("{}", stats.damage_taken);
+        renderer.draw_text(&fonts.digits, x + 32., y + 3., &damage, &style);
+        y += 24.;
+        renderer.draw_frame(sheet, self.frames.ammo, x + 8., y + 8.);
+        let pickups = format!("{}", stats.pickups);
+        renderer.draw_text(&fonts.digits, x + 32., y + 3., &pickups, &style);
+    }
+}
+
+/// Respawns dead players at the respawn point furthest from the `threats`,
+/// each one using up one of the `continues` shared by both players
+pub fn respawn_dead(
+    players: [&mut Player; 2],
+    continues: &mut u32,
+    coop: &CoopConfig,
+    difficulty: &Difficulty,
+    threats: &[Vec2],
+) {
+    for player in players {
+        if player.state != PlayerState::Dead || *continues == 0 {
+            continue;
+        }
+        if let Some(pos) = coop.safest_respawn_point(threats) {
+            *continues -= 1;
+            player.respawn(pos, difficulty.respawn_health);
         }
     }
 }
 
+/// Picks one of the living players at random for an enemy to shoot at
+pub fn pick_target<'a>(players: &[&'a Player]) -> Option<&'a Player> {
+    let living: Vec<&Player> = players.iter().copied().filter(|p| p.alive()).collect();
+    if living.is_empty() {
+        return None;
+    }
+    let i = (random() * living.len() as f64) as usize;
+    Some(living[usize::min(i, living.len() - 1)])
+}
+
+fn initial_spawn_points() -> Vec<SpawnPoint> {
+    vec![
+        (Vec2::new(16. * 30., 16. * 19.), 0, 5),
+        (Vec2::new(16. * 13., 16. * 23.), 1, 5),
+        (Vec2::new(16. * 50., 16. * 24.), 2, 5),
+        (Vec2::new(16. * 18., 16. * 8.), 2, 5),
+    ]
+}
+
 fn get_tile_at(data: &[u8], x: f64, y: f64) -> u8 {
     let tx = (x / 16.0) as usize;
     let ty = (y / 16.0) as usize;
@@ -781,5 +1772,5 @@ fn is_wall_tile(t: u8) -> bool {
 }
 
 fn is_water_tile(t: u8) -> bool {
-    t >= 72 && t <= 93
+    (72..=93).contains(&t)
 }
@@ -0,0 +1,165 @@
+//! Power ups loaded from `assets/powerups.json`.
+//!
+//! Every power up has an effect that is either applied instantly when it is
+//! picked up, or lasts as a buff on the player for `duration` ticks. Power
+//! ups lying on the floor despawn after a while and blink before they do.
+use crate::engine::atlas::FrameId;
+use crate::engine::json::from_js;
+use crate::engine::math::Vec2;
+use crate::engine::random::random;
+use crate::game::weapons::Weapon;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Deserialize)]
+pub struct PowerUpRegistry {
+    pub power_ups: Vec<PowerUpDefinition>,
+    /// Ticks a power up stays on the floor
+    pub despawn: u32,
+    /// Ticks before despawning where the power up starts blinking
+    pub blink: u32,
+    pub max_health: i32,
+    pub max_ammo: i32,
+    /// Tables rolled when a spawn point is destroyed
+    #[serde(default)]
+    pub spawn_point_drops: Vec<DropTable>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PowerUpDefinition {
+    pub name: String,
+    pub sprite: FrameId,
+    pub effect: Effect,
+    /// Ticks a buff lasts, unused by instant effects
+    #[serde(default)]
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    Ammo {
+        amount: i32,
+    },
+    Health {
+        amount: i32,
+    },
+    Weapon {
+        weapon: Weapon,
+    },
+    /// Multiplies the movement speed
+    SpeedBoost {
+        factor: f64,
+    },
+    /// Multiplies the heat added per shot
+    RapidFire {
+        factor: f64,
+    },
+    /// Blocks all damage
+    Shield,
+    /// Fires every shot `count` times, fanned out over `angle` radians
+    SpreadShot {
+        count: u8,
+        angle: f64,
+    },
+    /// Pulls power ups within `radius` towards the player
+    Magnet {
+        radius: f64,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DropChance {
+    pub power_up: String,
+    pub chance: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct DropTable(pub Vec<DropChance>);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PowerUp {
+    pub pos: Vec2,
+    /// Index into the registry
+    pub kind: usize,
+    /// Ticks left before it despawns
+    pub ttl: u32,
+}
+
+/// A timed effect active on a player
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Buff {
+    pub kind: usize,
+    pub effect: Effect,
+    pub remaining: u32,
+    pub duration: u32,
+}
+
+impl Default for PowerUpRegistry {
+    fn default() -> Self {
+        PowerUpRegistry {
+            power_ups: Vec::new(),
+            despawn: 600,
+            blink: 120,
+            max_health: 30,
+            max_ammo: 500,
+            spawn_point_drops: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for PowerUpRegistry {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+impl PowerUpRegistry {
+    pub fn new_from_json(json: &JsValue) -> PowerUpRegistry {
+        from_js(json).unwrap()
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.power_ups.iter().position(|p| p.name == name)
+    }
+
+    /// Creates a power up on the floor, if `name` is known
+    pub fn spawn(&self, name: &str, pos: &Vec2) -> Option<PowerUp> {
+        self.find(name).map(|kind| PowerUp {
+            pos: pos.clone(),
+            kind,
+            ttl: self.despawn,
+        })
+    }
+
+    pub fn blinking(&self, power_up: &PowerUp) -> bool {
+        power_up.ttl < self.blink
+    }
+}
+
+impl PowerUp {
+    /// Ages the power up by a tick, returns false once it has despawned
+    pub fn update(&mut self) -> bool {
+        self.ttl = self.ttl.saturating_sub(1);
+        self.ttl > 0
+    }
+}
+
+impl DropTable {
+    /// Rolls the table once and returns the name of the power up to drop
+    pub fn roll(&self) -> Option<&str> {
+        let roll = random();
+        let mut acc = 0.0;
+        for d in self.0.iter() {
+            acc += d.chance;
+            if roll < acc {
+                return Some(&d.power_up);
+            }
+        }
+        None
+    }
+}
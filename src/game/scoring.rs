@@ -0,0 +1,114 @@
+//! Scores, combos and per-player statistics.
+//!
+//! Every kill extends the kill streak of the player. Each `KILLS_PER_LEVEL`
+//! kills in a streak raise the score multiplier by one. When no kill is made
+//! for `COMBO_WINDOW` ticks the multiplier drops back one level at a time.
+use crate::engine::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+pub const SPAWN_POINT_HIT_SCORE: i32 = 125;
+pub const BOSS_HIT_SCORE: i32 = 250;
+
+/// Ticks without a kill before the multiplier drops a level
+pub const COMBO_WINDOW: u32 = 120;
+pub const KILLS_PER_LEVEL: u32 = 5;
+pub const MAX_MULTIPLIER: u32 = 8;
+
+/// Ticks a score popup stays on screen
+const POPUP_TICKS: u32 = 45;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    /// Kills per enemy archetype
+    pub kills: Vec<u32>,
+    pub shots_fired: u32,
+    pub shots_hit: u32,
+    pub damage_taken: i32,
+    pub pickups: u32,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Scoring {
+    pub score: i32,
+    /// Score shown in the HUD, counting up towards `score`
+    pub displayed: i32,
+    pub streak: u32,
+    /// Ticks left before the multiplier drops
+    pub combo_timer: u32,
+    pub stats: Stats,
+}
+
+/// Points floating up from where they were scored
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScorePopup {
+    pub pos: Vec2,
+    pub points: i32,
+    pub ttl: u32,
+}
+
+impl Stats {
+    /// Percentage of shots that hit something
+    pub fn accuracy(&self) -> u32 {
+        if self.shots_fired == 0 {
+            return 0;
+        }
+        u32::min(100, 100 * self.shots_hit / self.shots_fired)
+    }
+}
+
+impl Scoring {
+    pub fn multiplier(&self) -> i32 {
+        u32::min(MAX_MULTIPLIER, 1 + self.streak / KILLS_PER_LEVEL) as i32
+    }
+
+    /// Awards points with the current multiplier and returns the points given
+    pub fn award(&mut self, points: i32) -> i32 {
+        let points = points * self.multiplier();
+        self.score += points;
+        points
+    }
+
+    /// Counts a kill of an enemy of archetype `kind` and awards its points
+    pub fn kill(&mut self, kind: usize, points: i32) -> i32 {
+        if self.stats.kills.len() <= kind {
+            self.stats.kills.resize(kind + 1, 0);
+        }
+        self.stats.kills[kind] += 1;
+        self.streak += 1;
+        self.combo_timer = COMBO_WINDOW;
+        self.award(points)
+    }
+
+    pub fn update(&mut self) {
+        if self.displayed < self.score {
+            let delta = self.score - self.displayed;
+            self.displayed += if delta > 50 { 25 } else { 5 };
+            self.displayed = self.displayed.min(self.score);
+        }
+
+        if self.combo_timer > 0 {
+            self.combo_timer -= 1;
+        } else if self.streak > 0 {
+            let level = self.multiplier() as u32 - 1;
+            self.streak = level.saturating_sub(1) * KILLS_PER_LEVEL;
+            if self.streak > 0 {
+                self.combo_timer = COMBO_WINDOW;
+            }
+        }
+    }
+}
+
+impl ScorePopup {
+    pub fn new(pos: &Vec2, points: i32) -> ScorePopup {
+        ScorePopup {
+            pos: pos.clone(),
+            points,
+            ttl: POPUP_TICKS,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.pos.y -= 0.5;
+        self.ttl = self.ttl.saturating_sub(1);
+    }
+}
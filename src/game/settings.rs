@@ -0,0 +1,38 @@
+//! Options chosen in the menus, kept for the next visit.
+use crate::engine::gamepad::Bindings;
+use crate::engine::storage::Storage;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "blobs-and-bullets.settings";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Options {
+    /// Name of the difficulty, instead of the default of the game data
+    pub difficulty: Option<String>,
+    /// Overrides whether the game data has friendly fire
+    pub friendly_fire: Option<bool>,
+    pub bindings: Bindings,
+}
+
+pub struct Settings {
+    pub options: Options,
+    storage: Box<dyn Storage>,
+}
+
+impl Settings {
+    /// Loads the options from `storage`, or the defaults if there are none
+    pub fn load(storage: Box<dyn Storage>) -> Settings {
+        let options = storage
+            .load(STORAGE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Settings { options, storage }
+    }
+
+    pub fn save(&mut self) {
+        if let Ok(json) = serde_json::to_string(&self.options) {
+            self.storage.save(STORAGE_KEY, &json);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Snapshots of the whole simulation.
+//!
+//! A snapshot holds everything needed to continue a game exactly where it
+//! left off, including the state of the random number generator. Snapshots
+//! can be stored as JSON, which is handy to attach to bug reports, or as
+//! compact MessagePack. Both carry a format version and refuse to load
+//! snapshots from other versions.
+use crate::engine::decals::Decal;
+use crate::engine::math::Vec2;
+use crate::engine::particles::Particles;
+use crate::game::boss::Boss;
+use crate::game::bullets::Bullet;
+use crate::game::coop::Difficulty;
+use crate::game::power_ups::PowerUp;
+use crate::game::scoring::ScorePopup;
+use crate::game::waves::WaveDirector;
+use crate::game::{Enemy, Player};
+use serde::{Deserialize, Serialize};
+
+/// Bump when the contents of the snapshot change
+pub const SNAPSHOT_VERSION: u32 = 6;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// State of the random number generator
+    pub rng: u64,
+    pub player_1: Player,
+    pub player_2: Player,
+    pub bullets: Vec<Bullet>,
+    pub particles: Particles,
+    pub decals: Vec<Decal>,
+    pub spawn_points: Vec<(Vec2, u8, u8)>,
+    pub next_spawn_point: usize,
+    pub enemies: Vec<Enemy>,
+    pub waves: WaveDirector,
+    pub boss: Option<Boss>,
+    pub power_ups: Vec<PowerUp>,
+    pub popups: Vec<ScorePopup>,
+    pub difficulty: Difficulty,
+    pub continues: u32,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot was made by another version of the game
+    Version(u32),
+    Format(String),
+}
+
+/// Just the version, read before the rest of the snapshot
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+impl Snapshot {
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Snapshot, SnapshotError> {
+        let header: Header = serde_json::from_str(json)?;
+        check_version(header.version)?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        let header: Header = rmp_serde::from_slice(bytes)?;
+        check_version(header.version)?;
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+fn check_version(version: u32) -> Result<(), SnapshotError> {
+    if version == SNAPSHOT_VERSION {
+        Ok(())
+    } else {
+        Err(SnapshotError::Version(version))
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Format(e.to_string())
+    }
+}
+
+impl From<rmp_serde::encode::Error> for SnapshotError {
+    fn from(e: rmp_serde::encode::Error) -> Self {
+        SnapshotError::Format(e.to_string())
+    }
+}
+
+impl From<rmp_serde::decode::Error> for SnapshotError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        SnapshotError::Format(e.to_string())
+    }
+}
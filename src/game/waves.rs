@@ -0,0 +1,365 @@
+//! Wave director deciding when, what and where enemies spawn.
+//!
+//! Waves are scripted in `assets/waves.json`. When the scripted waves run out
+//! the last one is repeated with escalating counts and shorter intervals.
+//! In adaptive mode the spawn rate and alive cap are scaled by how well the
+//! players are doing.
+use crate::engine::json::from_js;
+use crate::engine::math::{vec2_distance, Vec2};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WaveConfig {
+    /// Ticks between two waves
+    pub break_ticks: u32,
+    /// Maximum number of enemies alive at once, unless overridden by the wave
+    pub alive_cap: usize,
+    pub waves: Vec<WaveDefinition>,
+    #[serde(default)]
+    pub escalation: Escalation,
+    #[serde(default)]
+    pub adaptive: Adaptive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveDefinition {
+    pub groups: Vec<SpawnGroup>,
+    #[serde(default)]
+    pub alive_cap: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnGroup {
+    /// Archetype name, or `None` to let the spawn point pick one of its color
+    #[serde(default)]
+    pub archetype: Option<String>,
+    pub count: u32,
+    /// Ticks between two spawns
+    pub interval: u32,
+    /// Ticks from the start of the wave until the first spawn
+    #[serde(default)]
+    pub delay: u32,
+    #[serde(default)]
+    pub spawn_points: SpawnSelection,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnSelection {
+    /// A spawn point with the same color as the archetype
+    #[default]
+    Matching,
+    Random,
+    RoundRobin,
+    /// The spawn point closest to a player
+    Nearest,
+    /// The spawn point furthest away from the players
+    Farthest,
+    /// A random one of the listed spawn point indices
+    Fixed(Vec<usize>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Escalation {
+    pub count_scale: f64,
+    pub interval_scale: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Adaptive {
+    pub enabled: bool,
+    pub min_intensity: f64,
+    pub max_intensity: f64,
+    /// Kill rate at which the players are considered to be doing well
+    pub target_kills_per_minute: f64,
+}
+
+impl Default for Escalation {
+    fn default() -> Self {
+        Escalation {
+            count_scale: 1.25,
+            interval_scale: 0.9,
+        }
+    }
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Adaptive {
+            enabled: false,
+            min_intensity: 0.5,
+            max_intensity: 2.0,
+            target_kills_per_minute: 30.0,
+        }
+    }
+}
+
+impl WaveConfig {
+    pub fn new_from_json(json: &JsValue) -> WaveConfig {
+        from_js(json).unwrap()
+    }
+}
+
+impl FromStr for WaveConfig {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+    }
+}
+
+/// A spawn point: its position, its color and its health. Spawn points
+/// without health are destroyed.
+pub type SpawnPoint = (Vec2, u8, u8);
+
+impl SpawnSelection {
+    /// Indices of the spawn points an enemy can come from, one of which is
+    /// picked at random.
+    ///
+    /// `color` is the color of the archetype of the enemy, if it has one,
+    /// and `players` the positions of the living players. `next` counts the
+    /// round robin picks.
+    pub fn candidates(
+        &self,
+        points: &[SpawnPoint],
+        color: Option<u8>,
+        players: &[Vec2],
+        next: &mut usize,
+    ) -> Vec<usize> {
+        let active: Vec<usize> = (0..points.len()).filter(|idx| points[*idx].2 > 0).collect();
+        if active.is_empty() {
+            return active;
+        }
+        match self {
+            SpawnSelection::Matching => {
+                let matching: Vec<usize> = active
+                    .iter()
+                    .copied()
+                    .filter(|idx| Some(points[*idx].1) == color)
+                    .collect();
+                if matching.is_empty() {
+                    active
+                } else {
+                    matching
+                }
+            }
+            SpawnSelection::Random => active,
+            SpawnSelection::RoundRobin => {
+                let pick = active[*next % active.len()];
+                *next += 1;
+                vec![pick]
+            }
+            SpawnSelection::Nearest | SpawnSelection::Farthest => {
+                let distance = |idx: &usize| {
+                    players
+                        .iter()
+                        .map(|p| vec2_distance(p, &points[*idx].0))
+                        .fold(f64::MAX, f64::min)
+                };
+                let by_distance = |a: &usize, b: &usize| {
+                    distance(a)
+                        .partial_cmp(&distance(b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                };
+                let pick = if let SpawnSelection::Nearest = self {
+                    active.iter().copied().min_by(by_distance)
+                } else {
+                    active.iter().copied().max_by(by_distance)
+                };
+                pick.into_iter().collect()
+            }
+            SpawnSelection::Fixed(indices) => active
+                .into_iter()
+                .filter(|idx| indices.contains(idx))
+                .collect(),
+        }
+    }
+}
+
+/// Request for the game to spawn one enemy
+pub struct SpawnOrder {
+    pub archetype: Option<String>,
+    pub spawn_points: SpawnSelection,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+enum WaveState {
+    Break {
+        remaining: u32,
+    },
+    Active {
+        definition: WaveDefinition,
+        spawned: Vec<u32>,
+        timers: Vec<f64>,
+    },
+}
+
+/// Runtime state of the waves. The config isn't part of snapshots, it is
+/// kept when restoring one.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WaveDirector {
+    #[serde(skip)]
+    config: WaveConfig,
+    state: WaveState,
+    /// Number of the current or upcoming wave, starting at 1
+    pub wave: usize,
+    pub intensity: f64,
+    tick: u64,
+    kill_ticks: Vec<u64>,
+}
+
+const TICKS_PER_MINUTE: u64 = 60 * 60;
+
+impl WaveDirector {
+    pub fn new(config: WaveConfig) -> Self {
+        let remaining = config.break_ticks;
+        WaveDirector {
+            config,
+            state: WaveState::Break { remaining },
+            wave: 1,
+            intensity: 1.0,
+            tick: 0,
+            kill_ticks: Vec::new(),
+        }
+    }
+
+    /// Starts over from the first wave
+    pub fn reset(&mut self) {
+        self.state = WaveState::Break {
+            remaining: self.config.break_ticks,
+        };
+        self.wave = 1;
+        self.intensity = 1.0;
+        self.tick = 0;
+        self.kill_ticks.clear();
+    }
+
+    /// Continues from the state of another director, keeping the config
+    pub fn restore(&mut self, other: WaveDirector) {
+        let config = std::mem::take(&mut self.config);
+        *self = other;
+        self.config = config;
+    }
+
+    /// Returns the number of the upcoming wave while waiting between waves
+    pub fn announcement(&self) -> Option<usize> {
+        match self.state {
+            WaveState::Break { .. } if self.definition(self.wave).is_some() => Some(self.wave),
+            _ => None,
+        }
+    }
+
+    pub fn record_kill(&mut self) {
+        self.kill_ticks.push(self.tick);
+    }
+
+    /// Advances the director by one tick and returns the enemies to spawn.
+    ///
+    /// `health` is the average health of the players relative to full health.
+    pub fn update(&mut self, alive: usize, health: f64) -> Vec<SpawnOrder> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.kill_ticks.retain(|t| *t + TICKS_PER_MINUTE > tick);
+        self.update_intensity(health);
+
+        let mut orders = Vec::new();
+        let mut next_state = None;
+        match &mut self.state {
+            WaveState::Break { remaining } => {
+                if *remaining > 0 {
+                    *remaining -= 1;
+                } else if let Some(definition) = self.definition(self.wave) {
+                    let groups = definition.groups.len();
+                    let timers = definition
+                        .groups
+                        .iter()
+                        .map(|g| -(g.delay as f64))
+                        .collect();
+                    next_state = Some(WaveState::Active {
+                        definition,
+                        spawned: vec![0; groups],
+                        timers,
+                    });
+                }
+            }
+            WaveState::Active {
+                definition,
+                spawned,
+                timers,
+            } => {
+                let cap = definition.alive_cap.unwrap_or(self.config.alive_cap);
+                let cap = (cap as f64 * self.intensity).round() as usize;
+                let mut alive = alive;
+                for (idx, group) in definition.groups.iter().enumerate() {
+                    if spawned[idx] >= group.count {
+                        continue;
+                    }
+                    timers[idx] += self.intensity;
+                    if timers[idx] >= group.interval as f64 && alive < cap {
+                        timers[idx] = 0.0;
+                        spawned[idx] += 1;
+                        alive += 1;
+                        orders.push(SpawnOrder {
+                            archetype: group.archetype.clone(),
+                            spawn_points: group.spawn_points.clone(),
+                        });
+                    }
+                }
+
+                let all_spawned = definition
+                    .groups
+                    .iter()
+                    .zip(spawned.iter())
+                    .all(|(g, s)| *s >= g.count);
+                if all_spawned && alive == 0 {
+                    self.wave += 1;
+                    next_state = Some(WaveState::Break {
+                        remaining: self.config.break_ticks,
+                    });
+                }
+            }
+        }
+        if let Some(state) = next_state {
+            self.state = state;
+        }
+        orders
+    }
+
+    /// Returns the definition of wave `number`, escalating the last scripted
+    /// wave once the script runs out
+    fn definition(&self, number: usize) -> Option<WaveDefinition> {
+        let scripted = self.config.waves.len();
+        if number <= scripted {
+            return self.config.waves.get(number - 1).cloned();
+        }
+        let mut definition = self.config.waves.last()?.clone();
+        let level = (number - scripted) as i32;
+        let count_scale = self.config.escalation.count_scale.powi(level);
+        let interval_scale = self.config.escalation.interval_scale.powi(level);
+        for g in definition.groups.iter_mut() {
+            g.count = (g.count as f64 * count_scale).round() as u32;
+            g.interval = u32::max(1, (g.interval as f64 * interval_scale).round() as u32);
+        }
+        definition.alive_cap = definition
+            .alive_cap
+            .map(|cap| (cap as f64 * count_scale).round() as usize);
+        Some(definition)
+    }
+
+    fn update_intensity(&mut self, health: f64) {
+        let adaptive = &self.config.adaptive;
+        if !adaptive.enabled {
+            self.intensity = 1.0;
+            return;
+        }
+        // players losing health get fewer enemies, players killing faster
+        // than the target more. Killing slower doesn't lower the intensity,
+        // as there is less to kill when fewer enemies spawn.
+        let kill_rate = self.kill_ticks.len() as f64 / adaptive.target_kills_per_minute;
+        let target = ((0.5 + 0.5 * health) * kill_rate.max(1.0))
+            .clamp(adaptive.min_intensity, adaptive.max_intensity);
+        self.intensity += (target - self.intensity) * 0.01;
+    }
+}
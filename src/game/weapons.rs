@@ -0,0 +1,89 @@
+//! Player weapons.
+//!
+//! Each weapon has its own heat per shot, ammo cost, bullets and sprite shown
+//! in the HUD. Weapons are picked up through power ups.
+use crate::engine::atlas::FrameId;
+use crate::game::bullets::{BulletSpec, Pattern};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weapon {
+    Pistol,
+    Shotgun,
+    MachineGun,
+    Laser,
+    Grenade,
+}
+
+impl Weapon {
+    /// Heat added per shot. The weapon can't fire again until it has cooled
+    /// down, one unit per tick.
+    pub fn heat(self) -> f64 {
+        match self {
+            Weapon::Pistol => 10.0,
+            Weapon::Shotgun => 30.0,
+            Weapon::MachineGun => 4.0,
+            Weapon::Laser => 1.0,
+            Weapon::Grenade => 40.0,
+        }
+    }
+
+    pub fn ammo_cost(self) -> i32 {
+        match self {
+            Weapon::Pistol => 1,
+            Weapon::Shotgun => 3,
+            Weapon::MachineGun => 1,
+            Weapon::Laser => 1,
+            Weapon::Grenade => 5,
+        }
+    }
+
+    pub fn sprite(self) -> FrameId {
+        FrameId::named(match self {
+            Weapon::Pistol => "icon_pistol",
+            Weapon::Shotgun => "icon_shotgun",
+            Weapon::MachineGun => "icon_machine_gun",
+            Weapon::Laser => "icon_laser_gun",
+            Weapon::Grenade => "icon_grenade_launcher",
+        })
+    }
+
+    pub fn bullets(self) -> BulletSpec {
+        let bullet = FrameId::named("bullet");
+        match self {
+            Weapon::Pistol => BulletSpec::new(Pattern::Aimed, 7.0, bullet),
+            Weapon::Shotgun => BulletSpec {
+                lifetime: 20,
+                ..BulletSpec::new(
+                    Pattern::Spread {
+                        count: 5,
+                        angle: 0.6,
+                    },
+                    7.0,
+                    bullet,
+                )
+            },
+            Weapon::MachineGun => BulletSpec {
+                jitter: 0.15,
+                ..BulletSpec::new(Pattern::Aimed, 8.0, bullet)
+            },
+            Weapon::Laser => BulletSpec {
+                lifetime: 40,
+                pierce: true,
+                oriented: true,
+                knockback: 0.5,
+                hit_cooldown: 10,
+                ..BulletSpec::new(Pattern::Aimed, 16.0, FrameId::named("laser"))
+            },
+            Weapon::Grenade => BulletSpec {
+                damage: 3,
+                lifetime: 45,
+                acceleration: -0.1,
+                splash: 48.0,
+                knockback: 6.0,
+                ..BulletSpec::new(Pattern::Aimed, 5.0, FrameId::named("grenade"))
+            },
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! The game as seen from JavaScript.
+//!
+//! ```js
+//! const game = new GameHandle(new EngineConfig().selector("#game"));
+//! game.on_score((player, score) => console.log(player, score));
+//! game.on_game_over(scores => console.log(scores));
+//! game.start();
+//! ```
+use crate::engine::config::EngineConfig;
+use crate::engine::control::Command;
+use crate::engine::json::load_json;
+use crate::engine::Engine;
+use crate::game::events::GameEvent;
+use crate::game::MyGame;
+use js_sys::{Array, Function, Promise};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+#[derive(Default)]
+struct Callbacks {
+    on_score: Option<Function>,
+    on_game_over: Option<Function>,
+}
+
+#[wasm_bindgen]
+pub struct GameHandle {
+    config: EngineConfig,
+    engine: Option<Engine>,
+    callbacks: Rc<RefCell<Callbacks>>,
+}
+
+#[wasm_bindgen]
+impl GameHandle {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: EngineConfig) -> GameHandle {
+        GameHandle {
+            config,
+            engine: None,
+            callbacks: Rc::new(RefCell::new(Callbacks::default())),
+        }
+    }
+
+    /// Loads the assets and starts the game on the title screen. Fails if
+    /// the game was started before, or if the canvas can't be found.
+    pub fn start(&mut self) -> Result<(), JsValue> {
+        if self.engine.is_some() {
+            return Err(JsValue::from_str("the game was already started"));
+        }
+        let engine = Engine::launch(MyGame::new(), self.config.clone())?;
+        let callbacks = self.callbacks.clone();
+        engine
+            .control()
+            .set_listener(Box::new(move |event| dispatch(&callbacks, event)));
+        self.engine = Some(engine);
+        Ok(())
+    }
+
+    /// Stops updating the game, which stays on screen
+    pub fn pause(&self) {
+        if let Some(engine) = &self.engine {
+            engine.control().set_paused(true);
+        }
+    }
+
+    pub fn resume(&self) {
+        if let Some(engine) = &self.engine {
+            engine.control().set_paused(false);
+        }
+    }
+
+    /// Starts a new game right away
+    pub fn restart(&self) {
+        if let Some(engine) = &self.engine {
+            engine.control().send(Command::Restart);
+        }
+    }
+
+    /// Volume from 0 to 1, passed on to the game
+    pub fn set_volume(&self, volume: f64) {
+        if let Some(engine) = &self.engine {
+            engine.control().set_volume(volume);
+        }
+    }
+
+    /// Loads a map in Tiled JSON from `url` and starts a new game on it. The
+    /// promise resolves once the map is loaded.
+    pub fn load_level(&self, url: String) -> Promise {
+        let control = self.engine.as_ref().map(|engine| engine.control().clone());
+        future_to_promise(async move {
+            let control = control.ok_or_else(|| JsValue::from_str("the game is not started"))?;
+            let map = load_json(&url).await?;
+            control.send(Command::LoadLevel {
+                name: level_name(&url),
+                map,
+            });
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Calls `callback(player, score)` whenever the score of a player changes
+    pub fn on_score(&self, callback: Function) {
+        self.callbacks.borrow_mut().on_score = Some(callback);
+    }
+
+    /// Calls `callback(scores)` with the final scores of both players when
+    /// the game is over
+    pub fn on_game_over(&self, callback: Function) {
+        self.callbacks.borrow_mut().on_game_over = Some(callback);
+    }
+
+    /// Ends the game loop, releases the game and removes the canvas if it
+    /// was added by the game. The handle can't be started again.
+    pub fn destroy(&mut self) {
+        if let Some(engine) = &self.engine {
+            engine.stop();
+        }
+        self.callbacks.replace(Callbacks::default());
+    }
+}
+
+/// Calls the callback for an event. The callback is cloned first, since it
+/// may replace the callbacks or destroy the game.
+fn dispatch(callbacks: &RefCell<Callbacks>, event: &GameEvent) {
+    let (on_score, on_game_over) = {
+        let callbacks = callbacks.borrow();
+        (callbacks.on_score.clone(), callbacks.on_game_over.clone())
+    };
+    let result = match event {
+        GameEvent::Score { player, score } => on_score.map(|callback| {
+            callback.call2(
+                &JsValue::NULL,
+                &JsValue::from(*player),
+                &JsValue::from(*score),
+            )
+        }),
+        GameEvent::GameOver { scores } => on_game_over.map(|callback| {
+            let scores: Array = scores.iter().map(|s| JsValue::from(*s)).collect();
+            callback.call1(&JsValue::NULL, &scores)
+        }),
+    };
+    if let Some(Err(err)) = result {
+        web_sys::console::error_1(&err);
+    }
+}
+
+/// Name of a level, used for its high score boards: the file name of its
+/// map without the extension
+fn level_name(url: &str) -> String {
+    let file = url.rsplit('/').next().unwrap_or(url);
+    let name = match file.rfind('.') {
+        Some(dot) if dot > 0 => &file[..dot],
+        _ => file,
+    };
+    String::from(name)
+}
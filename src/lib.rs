@@ -1,21 +1,29 @@
-mod engine;
-mod game;
+pub mod engine;
+pub mod game;
+pub mod handle;
 
 use wasm_bindgen::prelude::*;
 
-use crate::engine::Engine;
-use crate::game::MyGame;
+use crate::engine::config::EngineConfig;
+use crate::handle::GameHandle;
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[wasm_bindgen(start)]
-pub fn main_js() -> Result<(), JsValue> {
+/// Launches the game on a new canvas at the end of the page
+#[wasm_bindgen]
+pub fn main_js() -> Result<GameHandle, JsValue> {
+    launch(EngineConfig::default())
+}
+
+/// Launches the game as configured, e.g. on a canvas already on the page
+#[wasm_bindgen]
+pub fn launch(config: EngineConfig) -> Result<GameHandle, JsValue> {
     // #[cfg(debug_assertions)]
     // console_error_panic_hook::set_once();
 
-    let game = MyGame::new();
-    Engine::launch(game);
-    Ok(())
+    let mut handle = GameHandle::new(config);
+    handle.start()?;
+    Ok(handle)
 }
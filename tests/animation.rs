@@ -0,0 +1,91 @@
+use rust_webpack_template::engine::animation::{Animator, Clip, Clips, Keyframe, Mode};
+use rust_webpack_template::engine::atlas::{Atlas, FrameId};
+
+fn clip(mode: Mode) -> Clip {
+    let frames = ["a", "b", "c"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Keyframe {
+            frame: FrameId::named(name),
+            ticks: i as u32 + 1,
+            event: if i == 1 {
+                Some(String::from("hit"))
+            } else {
+                None
+            },
+        })
+        .collect();
+    Clip::new(frames, mode)
+}
+
+fn names(clip: &Clip, ticks: u32) -> String {
+    (0..ticks).map(|t| clip.frame_at(t).name()).collect()
+}
+
+#[test]
+fn clips_loop_play_once_or_ping_pong() {
+    assert_eq!(clip(Mode::Loop).length(), 6);
+    assert_eq!(names(&clip(Mode::Loop), 8), "abbcccab");
+    assert_eq!(names(&clip(Mode::Once), 8), "abbccccc");
+    assert_eq!(names(&clip(Mode::PingPong), 12), "abbcccbbabbc");
+}
+
+#[test]
+fn animators_report_events_and_finish() {
+    let mut clips = Clips::default();
+    clips.insert("swing", clip(Mode::Once));
+    clips.insert("idle", clip(Mode::Loop));
+
+    let mut animator = Animator::new("swing");
+    let events: Vec<Option<&str>> = (0..8).map(|_| animator.update(&clips)).collect();
+    assert_eq!(events.iter().filter(|e| e.is_some()).count(), 1);
+    assert_eq!(events[1], Some("hit"));
+    assert!(animator.finished(&clips));
+    assert_eq!(animator.frame(&clips), Some(FrameId::named("c")));
+
+    animator.play("idle");
+    assert!(!animator.finished(&clips));
+    assert_eq!(animator.frame(&clips), Some(FrameId::named("a")));
+    let hits = (0..12)
+        .filter(|_| animator.update(&clips).is_some())
+        .count();
+    assert_eq!(hits, 2);
+
+    animator.play("missing");
+    assert_eq!(animator.frame(&clips), None);
+    assert_eq!(animator.update(&clips), None);
+}
+
+#[test]
+fn aseprite_tags_become_clips() {
+    let atlas: Atlas = r#"{
+        "frames": [
+            { "filename": "walk 0", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 100 },
+            { "filename": "walk 1", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 50 },
+            { "filename": "walk 2", "frame": { "x": 16, "y": 0, "w": 8, "h": 8 }, "duration": 100 }
+        ],
+        "meta": {
+            "image": "walk.png",
+            "frameTags": [
+                { "name": "walk", "from": 0, "to": 2, "direction": "forward", "data": "0:step, 2:step" },
+                { "name": "bounce", "from": 0, "to": 2, "direction": "pingpong" },
+                { "name": "back", "from": 1, "to": 2, "direction": "reverse", "repeat": "1" }
+            ]
+        }
+    }"#
+    .parse()
+    .unwrap();
+    assert_eq!(atlas.clips.len(), 3);
+
+    let walk = atlas.clips.get("walk").unwrap();
+    assert_eq!(walk.mode, Mode::Loop);
+    let ticks: Vec<u32> = walk.frames.iter().map(|f| f.ticks).collect();
+    assert_eq!(ticks, vec![6, 3, 6]);
+    let events: Vec<Option<&str>> = walk.frames.iter().map(|f| f.event.as_deref()).collect();
+    assert_eq!(events, vec![Some("step"), None, Some("step")]);
+
+    assert_eq!(atlas.clips.get("bounce").unwrap().mode, Mode::PingPong);
+    let back = atlas.clips.get("back").unwrap();
+    assert_eq!(back.mode, Mode::Once);
+    assert_eq!(back.frame_at(0), FrameId::named("walk 2"));
+}
@@ -0,0 +1,125 @@
+use rust_webpack_template::engine::particles::Emitters;
+use rust_webpack_template::engine::random;
+use rust_webpack_template::game::archetypes::{Behaviour, EnemyArchetypes};
+use rust_webpack_template::game::frames::Frames;
+
+fn archetypes() -> EnemyArchetypes {
+    r#"{
+        "archetypes": [
+            {
+                "name": "green", "color": 0, "walk_left": "green_left", "walk_right": "green_right",
+                "health": 1, "speed": 1.0, "fire_chance": 0.01, "score": 100,
+                "bullets": { "type": "aimed", "speed": 7.0, "sprite": "green_bullet" },
+                "behaviour": "wander"
+            },
+            {
+                "name": "big_green", "color": 0, "spawn_weight": 3.0,
+                "walk_left": "green_left", "walk_right": "green_right",
+                "health": 5, "speed": 0.5, "fire_chance": 0.0, "score": 500,
+                "bullets": { "type": "aimed", "speed": 7.0, "sprite": "green_bullet" },
+                "drops": [{ "power_up": "health", "chance": 1.0 }],
+                "behaviour": "chase"
+            },
+            {
+                "name": "red", "color": 2, "walk_left": "red_left", "walk_right": "red_right",
+                "health": 2, "speed": 1.5, "fire_chance": 0.02, "score": 200,
+                "bullets": { "type": "spread", "count": 3, "angle": 0.5, "speed": 5.0, "sprite": "red_bullet" },
+                "behaviour": "sentry"
+            }
+        ]
+    }"#
+    .parse()
+    .unwrap()
+}
+
+#[test]
+fn the_game_archetypes_load() {
+    let archetypes: EnemyArchetypes = include_str!("../static/assets/enemies.json")
+        .parse()
+        .unwrap();
+    assert!(!archetypes.archetypes.is_empty());
+    for archetype in archetypes.archetypes.iter() {
+        assert!(archetype.health > 0, "{}", archetype.name);
+        assert!(archetype.spawn_weight > 0., "{}", archetype.name);
+    }
+}
+
+#[test]
+fn defaults_are_filled_in() {
+    let archetypes = archetypes();
+    let green = &archetypes.archetypes[0];
+    assert_eq!(green.spawn_weight, 1.);
+    assert!(green.drops.0.is_empty());
+    assert!(matches!(green.behaviour, Behaviour::Wander));
+
+    let big_green = &archetypes.archetypes[1];
+    assert_eq!(big_green.spawn_weight, 3.);
+    assert_eq!(big_green.drops.0.len(), 1);
+    assert!(matches!(big_green.behaviour, Behaviour::Chase));
+    assert!(matches!(
+        archetypes.archetypes[2].behaviour,
+        Behaviour::Sentry
+    ));
+}
+
+#[test]
+fn malformed_archetypes_are_an_error() {
+    let missing_health = r#"{ "archetypes": [{ "name": "green", "color": 0 }] }"#;
+    assert!(missing_health.parse::<EnemyArchetypes>().is_err());
+    let unknown_behaviour =
+        include_str!("../static/assets/enemies.json").replacen("\"wander\"", "\"dance\"", 1);
+    assert!(unknown_behaviour.parse::<EnemyArchetypes>().is_err());
+}
+
+#[test]
+fn archetypes_are_found_by_name() {
+    let archetypes = archetypes();
+    assert_eq!(archetypes.find("green"), Some(0));
+    assert_eq!(archetypes.find("red"), Some(2));
+    assert_eq!(archetypes.find("purple"), None);
+}
+
+#[test]
+fn picks_are_of_the_color_asked_for() {
+    let archetypes = archetypes();
+    random::seed(7);
+    for _ in 0..100 {
+        assert_eq!(archetypes.pick_for_color(2), Some(2));
+        let green = archetypes.pick_for_color(0).unwrap();
+        assert_eq!(archetypes.archetypes[green].color, 0);
+    }
+    assert_eq!(archetypes.pick_for_color(1), None);
+    assert_eq!(archetypes.pick_for_color(200), None);
+}
+
+#[test]
+fn picks_follow_the_spawn_weights() {
+    let archetypes = archetypes();
+    random::seed(42);
+    let picks = 4000;
+    let big = (0..picks)
+        .filter(|_| archetypes.pick_for_color(0) == Some(1))
+        .count();
+    // big_green has three times the weight of green
+    let share = big as f64 / picks as f64;
+    assert!((share - 0.75).abs() < 0.03, "{}", share);
+}
+
+#[test]
+fn every_color_splatters() {
+    let emitters: Emitters = include_str!("../static/assets/particles.json")
+        .parse()
+        .unwrap();
+    let frames = Frames::new();
+    let purple: EnemyArchetypes = include_str!("../static/assets/enemies.json")
+        .replacen("\"color\": 0", "\"color\": 7", 1)
+        .parse()
+        .unwrap();
+    assert!(purple.archetypes.iter().any(|a| a.color == 7));
+    for archetype in purple.archetypes.iter() {
+        let splat = frames.enemy_splat(archetype.color);
+        assert!(emitters.get(splat).is_some(), "{}", splat);
+    }
+    assert_eq!(frames.enemy_splat(2), "enemy_splat_2");
+    assert_eq!(frames.enemy_splat(255), "enemy_splat_0");
+}
@@ -0,0 +1,95 @@
+use rust_webpack_template::engine::atlas::{Atlas, FrameId};
+use serde_json::Value;
+use std::fs::read_to_string;
+
+#[test]
+fn hash_and_array_formats_are_read() {
+    let hash: Atlas = r#"{
+        "frames": {
+            "hero": { "frame": { "x": 8, "y": 16, "w": 8, "h": 8 } },
+            "door": { "frame": { "x": 0, "y": 0, "w": 16, "h": 8 }, "pivot": { "x": 0, "y": 1 } }
+        },
+        "meta": { "image": "sheet.png" }
+    }"#
+    .parse()
+    .unwrap();
+    let array: Atlas = r#"{
+        "frames": [
+            { "filename": "hero", "frame": { "x": 8, "y": 16, "w": 8, "h": 8 }, "duration": 100 },
+            { "filename": "door", "frame": { "x": 0, "y": 0, "w": 16, "h": 8 }, "pivot": { "x": 0, "y": 1 } }
+        ],
+        "meta": { "image": "sheet.png" }
+    }"#
+    .parse()
+    .unwrap();
+
+    for atlas in [hash, array].iter() {
+        assert_eq!(atlas.len(), 2);
+        let hero = atlas.frame(FrameId::named("hero")).unwrap();
+        assert_eq!((hero.x, hero.y, hero.w, hero.h), (8., 16., 8., 8.));
+        assert_eq!((hero.pivot.x, hero.pivot.y), (4., 4.));
+        let door = atlas.frame(FrameId::named("door")).unwrap();
+        assert_eq!((door.pivot.x, door.pivot.y), (0., 8.));
+        assert!(atlas.frame(FrameId::named("missing")).is_none());
+        assert_eq!(atlas.image_path("assets/sheet.json"), "assets/sheet.png");
+    }
+}
+
+#[test]
+fn frame_ids_are_serialized_by_name() {
+    let id = FrameId::named("hero");
+    assert_eq!(id, FrameId::named("hero"));
+    assert_ne!(id, FrameId::named("villain"));
+
+    let json = serde_json::to_string(&id).unwrap();
+    assert_eq!(json, r#""hero""#);
+    assert_eq!(serde_json::from_str::<FrameId>(&json).unwrap(), id);
+    assert!(serde_json::from_str::<FrameId>(r#""""#).is_err());
+}
+
+/// Collects the frame names used by `sprite` and `telegraph_sprite`, and
+/// the clip names used by `walk_left` and `walk_right` in a data file
+fn names(value: &Value, frames: &mut Vec<String>, clips: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter() {
+                match (key.as_str(), value) {
+                    ("sprite", Value::String(name)) | ("telegraph_sprite", Value::String(name)) => {
+                        frames.push(name.clone())
+                    }
+                    ("walk_left", Value::String(name)) | ("walk_right", Value::String(name)) => {
+                        clips.push(name.clone())
+                    }
+                    _ => names(value, frames, clips),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|v| names(v, frames, clips)),
+        _ => {}
+    }
+}
+
+#[test]
+fn game_data_only_uses_frames_and_clips_from_the_atlas() {
+    let atlas: Atlas = read_to_string("static/assets/lorez.json")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let mut frames = Vec::new();
+    let mut clips = Vec::new();
+    for file in ["enemies", "bosses", "powerups"].iter() {
+        let json = read_to_string(format!("static/assets/{}.json", file)).unwrap();
+        names(
+            &serde_json::from_str(&json).unwrap(),
+            &mut frames,
+            &mut clips,
+        );
+    }
+    assert!(!frames.is_empty() && !clips.is_empty());
+    for name in frames.iter() {
+        assert!(atlas.frame(FrameId::named(name)).is_some(), "{}", name);
+    }
+    for name in clips.iter() {
+        assert!(atlas.clips.get(name).is_some(), "{}", name);
+    }
+}
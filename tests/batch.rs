@@ -0,0 +1,89 @@
+use rust_webpack_template::engine::batch::{
+    quad, sort_key, Batch, SpriteBatch, FLOATS_PER_VERTEX, VERTICES_PER_QUAD,
+};
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::transform::{Color, Transform};
+
+#[test]
+fn quads_cover_the_transformed_rectangle() {
+    let vertices = quad([8., 16., 4., 2.], [2., 0., 0., 2., 100., 50.], 1., None);
+    let corners: Vec<_> = vertices.iter().map(|v| (v.x, v.y, v.u, v.v)).collect();
+    assert_eq!(
+        corners,
+        vec![
+            (100., 50., 8., 16.),
+            (108., 50., 12., 16.),
+            (100., 54., 8., 18.),
+            (100., 54., 8., 18.),
+            (108., 50., 12., 16.),
+            (108., 54., 12., 18.),
+        ]
+    );
+}
+
+#[test]
+fn quads_use_the_sprite_transform() {
+    let transform = Transform {
+        flip_x: true,
+        alpha: 0.5,
+        tint: Some(Color::RED.with_alpha(0.25)),
+        ..Transform::default()
+    };
+    let matrix = transform.matrix(100., 50., &Vec2::new(4., 4.), 2.);
+    let vertices = quad([0., 0., 8., 8.], matrix, transform.alpha, transform.tint);
+    assert_eq!((vertices[0].x, vertices[0].y), (108., 42.));
+    assert_eq!((vertices[5].x, vertices[5].y), (92., 58.));
+    assert_eq!(vertices[0].tint, [1., 0., 0., 0.25]);
+    assert!(vertices.iter().all(|v| v.alpha == 0.5));
+}
+
+#[test]
+fn layers_sort_before_order() {
+    assert!(sort_key(0, 1000) < sort_key(1, 0));
+    assert!(sort_key(1, 1) < sort_key(1, 2));
+}
+
+#[test]
+fn consecutive_quads_of_a_texture_share_a_batch() {
+    let mut batch = SpriteBatch::new();
+    let identity = [1., 0., 0., 1., 0., 0.];
+    batch.set_layer(1);
+    batch.push(1, [0., 0., 8., 8.], identity, 1., None);
+    batch.push(1, [8., 0., 8., 8.], identity, 1., None);
+    batch.push(2, [0., 0., 3., 5.], identity, 1., None);
+    batch.push_rect(0, 0., 0., 10., 10., Color::WHITE);
+    batch.push(1, [0., 0., 8., 8.], identity, 1., None);
+    // drawn first, although added last
+    batch.set_layer(0);
+    batch.push(1, [0., 8., 8., 8.], identity, 1., None);
+    assert_eq!(batch.len(), 6);
+
+    let (vertices, batches) = batch.finish();
+    assert!(batch.is_empty());
+    assert_eq!(vertices.len(), 6 * VERTICES_PER_QUAD * FLOATS_PER_VERTEX);
+    let quads = |texture, first, count| Batch {
+        texture,
+        first: first * VERTICES_PER_QUAD,
+        count: count * VERTICES_PER_QUAD,
+    };
+    assert_eq!(
+        batches,
+        vec![
+            quads(1, 0, 3),
+            quads(2, 3, 1),
+            quads(0, 4, 1),
+            quads(1, 5, 1),
+        ]
+    );
+    // texture coordinates of the first vertex of the quad of layer 0
+    assert_eq!(&vertices[2..4], &[0., 8.]);
+}
+
+#[test]
+fn rectangles_are_tinted_white_pixels() {
+    let mut batch = SpriteBatch::new();
+    batch.push_rect(0, 10., 20., 30., 40., Color::rgb(0, 0, 0).with_alpha(0.5));
+    let (vertices, _) = batch.finish();
+    let last = &vertices[5 * FLOATS_PER_VERTEX..];
+    assert_eq!(last, &[40., 60., 1., 1., 0., 0., 0., 1., 0.5]);
+}
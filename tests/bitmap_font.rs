@@ -0,0 +1,95 @@
+use rust_webpack_template::engine::bitmap_font::{Align, BitmapFont, TextStyle};
+use rust_webpack_template::game::high_scores::ALPHABET;
+use std::fs::read_to_string;
+
+fn font() -> BitmapFont {
+    r#"{
+        "pages": ["font.png"],
+        "common": { "lineHeight": 10 },
+        "chars": [
+            { "id": 32, "x": 0, "y": 0, "width": 0, "height": 0, "xoffset": 0, "yoffset": 0, "xadvance": 4 },
+            { "id": 65, "x": 0, "y": 0, "width": 6, "height": 8, "xoffset": 0, "yoffset": 1, "xadvance": 7 },
+            { "id": 86, "x": 8, "y": 0, "width": 6, "height": 8, "xoffset": 0, "yoffset": 1, "xadvance": 7 },
+            { "id": 63, "x": 16, "y": 0, "width": 5, "height": 8, "xoffset": 1, "yoffset": 1, "xadvance": 7 }
+        ],
+        "kernings": [{ "first": 65, "second": 86, "amount": -2 }]
+    }"#
+    .parse()
+    .unwrap()
+}
+
+#[test]
+fn glyphs_are_read_with_fallbacks() {
+    let font = font();
+    assert_eq!(font.line_height, 10.);
+    assert_eq!(font.image_path("assets/font.json"), "assets/font.png");
+    let (c, glyph) = font.glyph('A').unwrap();
+    assert_eq!(c, 'A');
+    assert_eq!((glyph.x, glyph.width, glyph.x_advance), (0., 6., 7.));
+    assert_eq!(font.glyph('a').unwrap().0, 'A');
+    assert_eq!(font.glyph('é').unwrap().0, '?');
+    assert_eq!(font.kerning('A', 'V'), -2.);
+    assert_eq!(font.kerning('V', 'A'), 0.);
+}
+
+#[test]
+fn text_is_measured_with_kerning_and_scale() {
+    let font = font();
+    let style = TextStyle::default();
+    assert_eq!(font.measure("AV", &style), (12., 10.));
+    assert_eq!(font.measure("VA", &style), (14., 10.));
+    // Trailing spaces don't count
+    assert_eq!(font.measure("A ", &style), (7., 10.));
+    assert_eq!(font.measure("A\nAA", &TextStyle::scaled(2.)), (28., 40.));
+}
+
+#[test]
+fn lines_are_aligned_on_the_position() {
+    let font = font();
+    let left = font.layout("A", 100., 50., &TextStyle::scaled(2.));
+    assert_eq!(left, vec![([0., 0., 6., 8.], [100., 52., 12., 16.])]);
+    let right = TextStyle::scaled(2.).align(Align::Right);
+    assert_eq!(font.layout("A", 100., 50., &right)[0].1[0], 86.);
+    let centered = TextStyle::default().centered();
+    let glyphs = font.layout("A\nAAA", 100., 0., &centered);
+    assert_eq!(glyphs.len(), 4);
+    assert_eq!(glyphs[0].1[0], 96.5);
+    assert_eq!(glyphs[1].1, [89.5, 11., 6., 8.]);
+    // Spaces only move the pen
+    assert_eq!(font.layout(" ", 0., 0., &TextStyle::default()), vec![]);
+}
+
+#[test]
+fn long_lines_are_wrapped_between_words() {
+    let font = font();
+    let style = TextStyle::default().wrapped(33.);
+    assert_eq!(
+        font.lines("AA AA AAAAAA A", &style),
+        vec!["AA AA", "AAAAAA", "A"]
+    );
+    assert_eq!(font.lines("AAA AA\nA", &style), vec!["AAA", "AA", "A"]);
+    assert_eq!(font.measure("AA AA AA", &style), (32., 20.));
+    assert_eq!(
+        font.lines("AA AA AA", &TextStyle::default()),
+        vec!["AA AA AA"]
+    );
+}
+
+#[test]
+fn game_fonts_have_the_characters_the_game_shows() {
+    let text: BitmapFont = read_to_string("static/assets/font.json")
+        .unwrap()
+        .parse()
+        .unwrap();
+    for c in ALPHABET.iter().chain(b"0123456789.,:!-+/%'@? ".iter()) {
+        assert_eq!(text.glyph(*c as char).unwrap().0, *c as char);
+    }
+    let digits: BitmapFont = read_to_string("static/assets/digits.json")
+        .unwrap()
+        .parse()
+        .unwrap();
+    for c in "0123456789".chars() {
+        assert_eq!(digits.glyph(c).unwrap().0, c);
+    }
+    assert_eq!(text.image, digits.image);
+}
@@ -0,0 +1,178 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::boss::{Boss, BossDefinitions};
+
+fn definitions() -> BossDefinitions {
+    r#"{
+        "bosses": [{
+            "health": 100,
+            "spawn": [100, 100],
+            "arena": [0, 0, 200, 200],
+            "sprite": "boss",
+            "telegraph_sprite": "boss_telegraph",
+            "phases": [
+                {
+                    "health": 1.0,
+                    "transition": 2,
+                    "loop_from": 1,
+                    "steps": [
+                        { "type": "wait", "ticks": 2 },
+                        { "type": "summon", "count": 1, "telegraph": 1 },
+                        { "type": "wait", "ticks": 1 }
+                    ]
+                },
+                {
+                    "health": 0.5,
+                    "transition": 3,
+                    "steps": [
+                        {
+                            "type": "fire",
+                            "bullets": { "type": "ring", "count": 4, "speed": 1, "sprite": "boss_bullet" },
+                            "volleys": 3,
+                            "interval": 2
+                        },
+                        { "type": "wait", "ticks": 4 }
+                    ]
+                },
+                {
+                    "health": 0.2,
+                    "steps": [{ "type": "summon", "archetype": "red", "count": 2 }]
+                }
+            ]
+        }]
+    }"#
+    .parse()
+    .unwrap()
+}
+
+/// Runs the boss for `ticks` ticks and returns the step it was on after
+/// each tick
+fn steps(boss: &mut Boss, definitions: &BossDefinitions, ticks: u32) -> Vec<usize> {
+    let mut bullets = Vec::new();
+    (0..ticks)
+        .map(|_| {
+            boss.update(&definitions.bosses[0], &[], &mut bullets);
+            boss.step
+        })
+        .collect()
+}
+
+#[test]
+fn the_first_phase_starts_with_its_transition() {
+    let definitions = definitions();
+    let mut boss = Boss::new(0, &definitions.bosses[0]);
+    assert!(boss.in_transition());
+    assert_eq!(steps(&mut boss, &definitions, 2), vec![0, 0]);
+    assert!(!boss.in_transition());
+    assert_eq!(boss.timer, 0);
+}
+
+#[test]
+fn steps_loop_from_the_loop_step() {
+    let definitions = definitions();
+    let mut boss = Boss::new(0, &definitions.bosses[0]);
+    steps(&mut boss, &definitions, 2);
+    // wait 2, summon after a telegraph of 1, wait 1, then summon again
+    assert_eq!(
+        steps(&mut boss, &definitions, 9),
+        vec![0, 1, 1, 2, 1, 1, 2, 1, 1]
+    );
+    assert_eq!(boss.phase, 0);
+}
+
+#[test]
+fn steps_wait_for_their_telegraph() {
+    let definitions = definitions();
+    let def = &definitions.bosses[0];
+    let mut boss = Boss::new(0, def);
+    let mut bullets = Vec::new();
+    let mut summons = Vec::new();
+    for _ in 0..4 {
+        summons.extend(boss.update(def, &[], &mut bullets));
+    }
+    assert_eq!(boss.step, 1);
+    assert!(boss.telegraphing(def));
+    assert!(summons.is_empty());
+
+    summons.extend(boss.update(def, &[], &mut bullets));
+    assert!(!boss.telegraphing(def));
+    assert!(summons.is_empty());
+    summons.extend(boss.update(def, &[], &mut bullets));
+    assert_eq!(summons.len(), 1);
+    assert_eq!(summons[0].count, 1);
+    assert_eq!(summons[0].archetype, None);
+}
+
+#[test]
+fn phases_change_when_health_drops_to_their_fraction() {
+    let definitions = definitions();
+    let def = &definitions.bosses[0];
+    let mut boss = Boss::new(0, def);
+    steps(&mut boss, &definitions, 5);
+
+    boss.health = 51;
+    steps(&mut boss, &definitions, 1);
+    assert_eq!(boss.phase, 0);
+
+    boss.health = 50;
+    steps(&mut boss, &definitions, 1);
+    assert_eq!((boss.phase, boss.step, boss.timer), (1, 0, 0));
+    // the transition starts over, without acting
+    assert!(boss.in_transition());
+    assert_eq!(boss.transition, 2);
+    let mut bullets = Vec::new();
+    for _ in 0..2 {
+        boss.update(def, &[], &mut bullets);
+    }
+    assert!(bullets.is_empty());
+    assert!(!boss.in_transition());
+}
+
+#[test]
+fn phases_can_be_skipped() {
+    let definitions = definitions();
+    let def = &definitions.bosses[0];
+    let mut boss = Boss::new(0, def);
+    boss.health = 10;
+    let summons = boss.update(def, &[], &mut Vec::new());
+    assert_eq!(boss.phase, 2);
+    assert_eq!(summons.len(), 1);
+    assert_eq!(summons[0].archetype.as_deref(), Some("red"));
+}
+
+#[test]
+fn phases_never_go_back() {
+    let definitions = definitions();
+    let def = &definitions.bosses[0];
+    let mut boss = Boss::new(0, def);
+    boss.health = 40;
+    steps(&mut boss, &definitions, 1);
+    boss.health = 100;
+    steps(&mut boss, &definitions, 10);
+    assert_eq!(boss.phase, 1);
+}
+
+#[test]
+fn volleys_are_fired_an_interval_apart() {
+    let definitions = definitions();
+    let def = &definitions.bosses[0];
+    let mut boss = Boss::new(0, def);
+    boss.health = 50;
+    boss.update(def, &[], &mut Vec::new());
+    boss.update(def, &[], &mut Vec::new());
+    boss.update(def, &[], &mut Vec::new());
+
+    let targets = [Vec2::new(100., 150.)];
+    let mut fired = Vec::new();
+    let mut bullets = Vec::new();
+    for _ in 0..6 {
+        boss.update(def, &targets, &mut bullets);
+        fired.push(bullets.len());
+    }
+    assert_eq!(fired, vec![4, 4, 8, 8, 12, 12]);
+    // on to the wait once the last volley is out, then back to firing
+    assert_eq!(boss.step, 1);
+    for _ in 0..4 {
+        boss.update(def, &targets, &mut bullets);
+    }
+    assert_eq!(boss.step, 0);
+}
@@ -0,0 +1,161 @@
+use rust_webpack_template::engine::atlas::FrameId;
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::bullets::{Bullet, BulletSpec, Pattern, Shooter};
+use std::f64::consts::PI;
+
+fn fire(spec: &BulletSpec, spin: &mut f64) -> Vec<Bullet> {
+    let mut bullets = Vec::new();
+    spec.emit(
+        &Vec2::new(10., 10.),
+        &Vec2::new(10., 20.),
+        Shooter::Boss,
+        spin,
+        &mut bullets,
+    );
+    bullets
+}
+
+fn angles(bullets: &[Bullet]) -> Vec<f64> {
+    bullets.iter().map(|b| b.vel.y.atan2(b.vel.x)).collect()
+}
+
+fn assert_close(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+}
+
+fn spec(pattern: Pattern) -> BulletSpec {
+    BulletSpec::new(pattern, 2., FrameId::named("bullet"))
+}
+
+#[test]
+fn aimed_bullets_fly_at_the_target() {
+    let bullets = fire(&spec(Pattern::Aimed), &mut 0.);
+    assert_close(&angles(&bullets), &[PI / 2.]);
+    assert_eq!(bullets[0].pos, Vec2::new(10., 10.));
+    assert!((bullets[0].vel.length() - 2.).abs() < 1e-9);
+}
+
+#[test]
+fn spreads_fan_out_around_the_target() {
+    let spread = spec(Pattern::Spread {
+        count: 5,
+        angle: 0.8,
+    });
+    let bullets = fire(&spread, &mut 0.);
+    let aim = PI / 2.;
+    assert_close(
+        &angles(&bullets),
+        &[aim - 0.4, aim - 0.2, aim, aim + 0.2, aim + 0.4],
+    );
+
+    let single = spec(Pattern::Spread {
+        count: 1,
+        angle: 0.8,
+    });
+    assert_close(&angles(&fire(&single, &mut 0.)), &[aim]);
+}
+
+#[test]
+fn rings_go_all_around() {
+    let bullets = fire(&spec(Pattern::Ring { count: 4 }), &mut 0.);
+    assert_close(&angles(&bullets), &[PI / 2., PI, -PI / 2., 0.]);
+}
+
+#[test]
+fn spirals_turn_every_time_they_fire() {
+    let spiral = spec(Pattern::Spiral {
+        arms: 2,
+        rotation: 0.5,
+    });
+    let mut spin = 0.;
+    assert_close(&angles(&fire(&spiral, &mut spin)), &[0., PI]);
+    assert_close(&angles(&fire(&spiral, &mut spin)), &[0.5, 0.5 - PI]);
+    assert_eq!(spin, 1.);
+}
+
+#[test]
+fn bursts_are_delayed_an_interval_apart() {
+    let burst = BulletSpec {
+        delay: 5,
+        ..spec(Pattern::AimedBurst {
+            count: 3,
+            interval: 4,
+        })
+    };
+    let bullets = fire(&burst, &mut 0.);
+    assert_close(&angles(&bullets), &[PI / 2.; 3]);
+    let delays: Vec<u32> = bullets.iter().map(|b| b.delay).collect();
+    assert_eq!(delays, vec![5, 9, 13]);
+}
+
+#[test]
+fn jitter_stays_within_its_angle() {
+    let ring = BulletSpec {
+        jitter: 0.1,
+        ..spec(Pattern::Ring { count: 50 })
+    };
+    let bullets = fire(&ring, &mut 0.);
+    for (i, angle) in angles(&bullets).into_iter().enumerate() {
+        let expected = PI / 2. + 2. * PI * i as f64 / 50.;
+        let off = (angle - expected).rem_euclid(2. * PI);
+        assert!(off <= 0.1 || off >= 2. * PI - 0.1, "{} off", off);
+    }
+}
+
+#[test]
+fn piercing_bullets_wait_for_the_hit_cooldown() {
+    let mut bullet = fire(&spec(Pattern::Aimed), &mut 0.).remove(0);
+    assert!(bullet.can_hit(0));
+    assert!(bullet.can_hit(5));
+    bullet.pierce = true;
+    assert!(bullet.can_hit(0));
+    assert!(!bullet.can_hit(5));
+}
+
+#[test]
+fn delayed_bullets_wait_where_they_were_fired() {
+    let delayed = BulletSpec {
+        delay: 2,
+        lifetime: 10,
+        ..spec(Pattern::Aimed)
+    };
+    let mut bullet = fire(&delayed, &mut 0.).remove(0);
+    for _ in 0..2 {
+        assert!(bullet.waiting());
+        bullet.advance(None);
+        assert_eq!(bullet.pos, Vec2::new(10., 10.));
+        assert_eq!(bullet.lifetime, 10);
+    }
+    assert!(!bullet.waiting());
+    bullet.advance(None);
+    assert_eq!(bullet.pos, Vec2::new(10., 12.));
+    assert_eq!(bullet.lifetime, 9);
+}
+
+#[test]
+fn accelerating_bullets_stop_at_their_max_speed() {
+    let fast = BulletSpec {
+        acceleration: 1.,
+        max_speed: 4.,
+        ..spec(Pattern::Aimed)
+    };
+    let mut bullet = fire(&fast, &mut 0.).remove(0);
+    for _ in 0..5 {
+        bullet.advance(None);
+    }
+    assert!((bullet.vel.length() - 4.).abs() < 1e-9);
+}
+
+#[test]
+fn homing_bullets_turn_towards_their_target() {
+    let homing = BulletSpec {
+        homing: 0.1,
+        ..spec(Pattern::Aimed)
+    };
+    let mut bullet = fire(&homing, &mut 0.).remove(0);
+    bullet.advance(Some(&Vec2::new(100., 10.)));
+    assert_close(&angles(&[bullet]), &[PI / 2. - 0.1]);
+}
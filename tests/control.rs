@@ -0,0 +1,50 @@
+use rust_webpack_template::engine::control::{Command, Control};
+use rust_webpack_template::game::events::GameEvent;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn volume_is_clamped() {
+    let control = Control::new();
+    assert_eq!(control.volume(), 1.);
+    control.set_volume(0.25);
+    assert_eq!(control.volume(), 0.25);
+    control.set_volume(3.);
+    assert_eq!(control.volume(), 1.);
+    control.set_volume(-1.);
+    assert_eq!(control.volume(), 0.);
+}
+
+#[test]
+fn commands_are_taken_once() {
+    let control = Control::new();
+    control.send(Command::Restart);
+    control.send(Command::Restart);
+    assert_eq!(control.take_commands().len(), 2);
+    assert!(control.take_commands().is_empty());
+}
+
+#[test]
+fn events_reach_the_listener() {
+    let control = Control::new();
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let events = received.clone();
+    control.set_listener(Box::new(move |event| {
+        events.borrow_mut().push(event.clone())
+    }));
+    control.emit(&GameEvent::Score {
+        player: 1,
+        score: 100,
+    });
+    control.emit(&GameEvent::GameOver { scores: [100, 0] });
+    assert_eq!(
+        *received.borrow(),
+        vec![
+            GameEvent::Score {
+                player: 1,
+                score: 100
+            },
+            GameEvent::GameOver { scores: [100, 0] },
+        ]
+    );
+}
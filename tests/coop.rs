@@ -0,0 +1,231 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::random;
+use rust_webpack_template::game::coop::{CoopConfig, Difficulty, PlayerState};
+use rust_webpack_template::game::{pick_target, respawn_dead, Player};
+
+fn difficulty() -> Difficulty {
+    Difficulty {
+        bleed_out: 5,
+        revive_ticks: 3,
+        revive_radius: 10.,
+        revive_health: 4,
+        continues: 1,
+        respawn_health: 12,
+        ..Difficulty::default()
+    }
+}
+
+fn coop() -> CoopConfig {
+    r#"{
+        "default": "normal",
+        "respawn_points": [[0, 0], [100, 0], [50, 50]],
+        "difficulties": [{
+            "name": "normal", "bleed_out": 5, "revive_ticks": 3, "revive_radius": 10,
+            "revive_health": 4, "continues": 1, "respawn_health": 12
+        }]
+    }"#
+    .parse()
+    .unwrap()
+}
+
+fn downed(difficulty: &Difficulty) -> PlayerState {
+    PlayerState::Downed {
+        bleed_out: difficulty.bleed_out,
+        revive: 0,
+    }
+}
+
+fn player(state: PlayerState) -> Player {
+    let mut player = Player::new(Vec2::new(0., 0.), 1);
+    player.state = state;
+    player
+}
+
+#[test]
+fn the_game_difficulties_load() {
+    let coop: CoopConfig = include_str!("../static/assets/difficulty.json")
+        .parse()
+        .unwrap();
+    assert!(coop.difficulty(&coop.default).is_some());
+    assert!(!coop.respawn_points.is_empty());
+}
+
+#[test]
+fn a_downed_player_bleeds_out_alone() {
+    let difficulty = difficulty();
+    let pos = Vec2::new(0., 0.);
+    let mut state = downed(&difficulty);
+    for _ in 0..4 {
+        state.update(&pos, None, &difficulty);
+        assert!(matches!(state, PlayerState::Downed { .. }));
+    }
+    state.update(&pos, None, &difficulty);
+    assert!(state == PlayerState::Dead);
+    // The dead stay dead, even with a partner close by
+    state.update(&pos, Some(&pos), &difficulty);
+    assert!(state == PlayerState::Dead);
+}
+
+#[test]
+fn a_partner_close_by_revives() {
+    let difficulty = difficulty();
+    let pos = Vec2::new(0., 0.);
+    let partner = Vec2::new(5., 0.);
+    let mut state = downed(&difficulty);
+    for _ in 0..3 {
+        assert!(!state.revived(&difficulty));
+        state.update(&pos, Some(&partner), &difficulty);
+    }
+    assert!(state.revived(&difficulty));
+    // Reviving holds off bleeding out
+    assert!(
+        state
+            == PlayerState::Downed {
+                bleed_out: 5,
+                revive: 3
+            }
+    );
+}
+
+#[test]
+fn a_partner_too_far_away_does_not_revive() {
+    let difficulty = difficulty();
+    let pos = Vec2::new(0., 0.);
+    let mut state = downed(&difficulty);
+    for _ in 0..5 {
+        state.update(&pos, Some(&Vec2::new(20., 0.)), &difficulty);
+    }
+    assert!(state == PlayerState::Dead);
+}
+
+#[test]
+fn revive_progress_resets_when_the_partner_leaves() {
+    let difficulty = difficulty();
+    let pos = Vec2::new(0., 0.);
+    let partner = Vec2::new(5., 0.);
+    let mut state = downed(&difficulty);
+    state.update(&pos, Some(&partner), &difficulty);
+    state.update(&pos, Some(&partner), &difficulty);
+    state.update(&pos, None, &difficulty);
+    assert!(
+        state
+            == PlayerState::Downed {
+                bleed_out: 4,
+                revive: 0
+            }
+    );
+    state.update(&pos, Some(&partner), &difficulty);
+    state.update(&pos, Some(&partner), &difficulty);
+    assert!(!state.revived(&difficulty));
+    state.update(&pos, Some(&partner), &difficulty);
+    assert!(state.revived(&difficulty));
+}
+
+#[test]
+fn a_revived_player_gets_up_with_the_revive_health() {
+    let difficulty = difficulty();
+    let mut player = player(PlayerState::Alive);
+    player.health = 0;
+    let partner = Vec2::new(5., 0.);
+    player.update_state(Some(&partner), &difficulty);
+    assert!(matches!(player.state, PlayerState::Downed { .. }));
+    player.update_state(Some(&partner), &difficulty);
+    player.update_state(Some(&partner), &difficulty);
+    assert!(player.alive());
+    assert_eq!(player.health, 4);
+}
+
+#[test]
+fn respawns_are_furthest_from_the_threats() {
+    let coop = coop();
+    let safest = |threats: &[Vec2]| {
+        let p = coop.safest_respawn_point(threats).unwrap();
+        (p.x, p.y)
+    };
+    assert_eq!(safest(&[Vec2::new(0., 0.)]), (100., 0.));
+    assert_eq!(safest(&[Vec2::new(90., 0.)]), (0., 0.));
+    assert_eq!(
+        safest(&[Vec2::new(0., 0.), Vec2::new(100., 0.)]),
+        (50., 50.)
+    );
+
+    let nowhere: CoopConfig =
+        r#"{ "default": "normal", "respawn_points": [], "difficulties": [] }"#
+            .parse()
+            .unwrap();
+    assert!(nowhere.safest_respawn_point(&[]).is_none());
+}
+
+#[test]
+fn continues_are_shared_by_both_players() {
+    let coop = coop();
+    let difficulty = Difficulty {
+        continues: 3,
+        ..difficulty()
+    };
+    let mut continues = difficulty.continues;
+    let mut player_1 = player(PlayerState::Dead);
+    let mut player_2 = player(PlayerState::Dead);
+    let threats = [Vec2::new(0., 0.)];
+
+    respawn_dead(
+        [&mut player_1, &mut player_2],
+        &mut continues,
+        &coop,
+        &difficulty,
+        &threats,
+    );
+    assert_eq!(continues, 1);
+    for player in [&player_1, &player_2].iter() {
+        assert!(player.alive());
+        assert_eq!(player.health, 12);
+        assert_eq!(player.pos.x, 100.);
+    }
+
+    // The living don't use up continues
+    player_2.state = PlayerState::Dead;
+    respawn_dead(
+        [&mut player_1, &mut player_2],
+        &mut continues,
+        &coop,
+        &difficulty,
+        &threats,
+    );
+    assert_eq!(continues, 0);
+    assert!(player_2.alive());
+
+    player_1.state = PlayerState::Dead;
+    respawn_dead(
+        [&mut player_1, &mut player_2],
+        &mut continues,
+        &coop,
+        &difficulty,
+        &threats,
+    );
+    assert_eq!(continues, 0);
+    assert!(player_1.state == PlayerState::Dead);
+}
+
+#[test]
+fn enemies_only_shoot_at_the_living() {
+    random::seed(3);
+    let mut player_1 = player(PlayerState::Alive);
+    player_1.number = 1;
+    let mut player_2 = player(PlayerState::Alive);
+    player_2.number = 2;
+
+    let mut picked = [0; 2];
+    for _ in 0..100 {
+        let target = pick_target(&[&player_1, &player_2]).unwrap();
+        picked[target.number as usize - 1] += 1;
+    }
+    assert!(picked[0] > 0 && picked[1] > 0, "{:?}", picked);
+
+    player_1.state = downed(&difficulty());
+    player_2.state = PlayerState::Alive;
+    for _ in 0..100 {
+        assert_eq!(pick_target(&[&player_1, &player_2]).unwrap().number, 2);
+    }
+    player_2.state = PlayerState::Dead;
+    assert!(pick_target(&[&player_1, &player_2]).is_none());
+}
@@ -0,0 +1,119 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::bullets::Shooter;
+use rust_webpack_template::game::damage::{
+    apply_knockback, take_cooldown, DamageEvent, Target, INVULNERABILITY_TICKS, KNOCKBACK_FRICTION,
+};
+use rust_webpack_template::game::power_ups::{Buff, Effect};
+use rust_webpack_template::game::Player;
+
+fn event(cooldown: u32) -> DamageEvent {
+    DamageEvent::new(
+        Shooter::Player(1),
+        Target::Enemy(0),
+        1,
+        &Vec2::new(0., 0.),
+        &Vec2::new(1., 0.),
+        0.,
+    )
+    .with_cooldown(cooldown)
+}
+
+#[test]
+fn hits_with_a_cooldown_start_it() {
+    let mut cooldown = 0;
+    assert!(take_cooldown(&mut cooldown, &event(10)));
+    assert_eq!(cooldown, 10);
+    assert!(!take_cooldown(&mut cooldown, &event(10)));
+    assert_eq!(cooldown, 10);
+}
+
+#[test]
+fn hits_without_a_cooldown_always_get_through() {
+    let mut cooldown = 5;
+    assert!(take_cooldown(&mut cooldown, &event(0)));
+    assert_eq!(cooldown, 5);
+}
+
+#[test]
+fn knockback_points_along_the_hit() {
+    let event = DamageEvent::new(
+        Shooter::Boss,
+        Target::Player(1),
+        2,
+        &Vec2::new(5., 5.),
+        &Vec2::new(3., 4.),
+        10.,
+    );
+    assert_eq!(event.knockback, Vec2::new(6., 8.));
+    assert_eq!(event.amount, 2);
+    assert_eq!(event.cooldown, 0);
+
+    let still = DamageEvent::new(
+        Shooter::Boss,
+        Target::Boss,
+        1,
+        &Vec2::new(5., 5.),
+        &Vec2::new(0., 0.),
+        10.,
+    );
+    assert_eq!(still.knockback, Vec2::new(0., 0.));
+}
+
+#[test]
+fn knockback_wears_off() {
+    let mut pos = Vec2::new(0., 0.);
+    let mut knockback = Vec2::new(10., 0.);
+    apply_knockback(&mut pos, &mut knockback, false);
+    assert_eq!(pos, Vec2::new(10., 0.));
+    assert!((knockback.x - 10. * KNOCKBACK_FRICTION).abs() < 1e-9);
+
+    for _ in 0..100 {
+        apply_knockback(&mut pos, &mut knockback, false);
+    }
+    assert_eq!(knockback, Vec2::new(0., 0.));
+    let rest = pos.clone();
+    apply_knockback(&mut pos, &mut knockback, false);
+    assert_eq!(pos, rest);
+}
+
+#[test]
+fn walls_stop_knockback() {
+    let mut pos = Vec2::new(0., 0.);
+    let mut knockback = Vec2::new(10., 0.);
+    apply_knockback(&mut pos, &mut knockback, true);
+    assert_eq!(pos, Vec2::new(0., 0.));
+    assert!(knockback.x < 10.);
+}
+
+#[test]
+fn players_are_invulnerable_after_a_hit() {
+    let mut player = Player::new(Vec2::new(0., 0.), 1);
+    let health = player.health;
+    assert!(player.hit(2, &Vec2::new(3., 0.)));
+    assert_eq!(player.health, health - 2);
+    assert_eq!(player.invulnerable, INVULNERABILITY_TICKS);
+    assert_eq!(player.knockback, Vec2::new(3., 0.));
+
+    assert!(!player.hit(2, &Vec2::new(3., 0.)));
+    assert_eq!(player.health, health - 2);
+    assert_eq!(player.knockback, Vec2::new(3., 0.));
+
+    player.invulnerable = 0;
+    assert!(player.hit(1, &Vec2::new(0., 0.)));
+    assert_eq!(player.health, health - 3);
+}
+
+#[test]
+fn shields_block_hits() {
+    let mut player = Player::new(Vec2::new(0., 0.), 2);
+    player.buffs.push(Buff {
+        kind: 0,
+        effect: Effect::Shield,
+        remaining: 100,
+        duration: 100,
+    });
+    let health = player.health;
+    assert!(!player.hit(5, &Vec2::new(3., 0.)));
+    assert_eq!(player.health, health);
+    assert_eq!(player.invulnerable, 0);
+}
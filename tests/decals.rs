@@ -0,0 +1,78 @@
+use rust_webpack_template::engine::atlas::FrameId;
+use rust_webpack_template::engine::decals::{Decal, DecalConfig, DecalLayer};
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::transform::Color;
+
+fn decal(x: f64) -> Decal {
+    Decal::new(FrameId::named("blood_0"), &Vec2::new(x, 0.))
+}
+
+fn layer() -> DecalLayer {
+    DecalLayer::new(DecalConfig { cap: 8, fade: 10 })
+}
+
+#[test]
+fn only_new_decals_are_stamped() {
+    let mut decals = layer();
+    decals.add(decal(0.));
+    decals.add(decal(1.));
+    assert_eq!(decals.take_unstamped(), (false, 0..2));
+    assert_eq!(decals.take_unstamped(), (false, 2..2));
+    decals.add(decal(2.));
+    assert_eq!(decals.take_unstamped(), (false, 2..3));
+
+    decals.clear();
+    decals.add(decal(3.));
+    assert_eq!(decals.take_unstamped(), (true, 0..1));
+}
+
+#[test]
+fn the_oldest_decals_fade_out_over_the_cap() {
+    let mut decals = layer();
+    for x in 0..8 {
+        decals.add(decal(x as f64));
+    }
+    decals.take_unstamped();
+    assert_eq!(decals.fading().count(), 0);
+
+    decals.add(decal(8.));
+    assert_eq!(decals.len(), 7);
+    assert_eq!(decals.iter().next().unwrap().pos.x, 2.);
+    let fading: Vec<f64> = decals.fading().map(|(_, fade)| fade).collect();
+    assert_eq!(fading, vec![1., 1.]);
+    // The surface is drawn again without them
+    assert_eq!(decals.take_unstamped(), (true, 0..7));
+
+    for _ in 0..5 {
+        decals.update();
+    }
+    assert_eq!(decals.fading().next().unwrap().1, 0.5);
+    for _ in 0..5 {
+        decals.update();
+    }
+    assert_eq!(decals.fading().count(), 0);
+    assert_eq!(decals.len(), 7);
+}
+
+#[test]
+fn decals_survive_snapshots() {
+    let mut original = decal(4.);
+    original.tint = Some(Color::rgb(64, 0, 0).with_alpha(0.6));
+    original.rotation = 1.5;
+    let json = serde_json::to_string(&vec![original]).unwrap();
+    let restored: Vec<Decal> = serde_json::from_str(&json).unwrap();
+
+    let mut decals = layer();
+    decals.add(decal(0.));
+    decals.take_unstamped();
+    decals.restore(restored);
+    assert_eq!(decals.len(), 1);
+    let decal = decals.iter().next().unwrap();
+    assert_eq!(decal.frame, FrameId::named("blood_0"));
+    assert_eq!(decal.rotation, 1.5);
+    assert_eq!(
+        decal.tint,
+        Some(Color::rgb(64, 0, 0).with_alpha(153. / 255.))
+    );
+    assert_eq!(decals.take_unstamped(), (true, 0..1));
+}
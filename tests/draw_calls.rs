@@ -0,0 +1,153 @@
+//! Draw calls of a busy frame with and without the tile layer cache and
+//! sprite batching, counted from the tiles and sprites the game would draw,
+//! and the time the batching takes natively. These are not frame times, which
+//! can only be measured in the browser by pressing F3 in the game.
+//!
+//! The batching times are only meaningful in release mode:
+//!
+//! ```sh
+//! cargo test --release --test draw_calls -- --ignored --nocapture
+//! ```
+use rust_webpack_template::engine::batch::{SpriteBatch, TextureId};
+use rust_webpack_template::engine::particles::Emitters;
+use rust_webpack_template::engine::tile_cache::{visible_tiles, Chunks, CHUNK_TILES};
+use rust_webpack_template::engine::tiled::TileMap;
+use std::time::Instant;
+
+const WIDTH: f64 = 960.;
+const HEIGHT: f64 = 540.;
+const TILE_SIZE: f64 = 16.;
+
+const WHITE: TextureId = 0;
+const SHEET: TextureId = 1;
+const DIGITS: TextureId = 2;
+const TEXT: TextureId = 3;
+const DECALS: TextureId = 4;
+
+/// Sprites of a busy frame in the order the game draws them, with the
+/// texture each is drawn from. Particles and decals are at their caps. The
+/// decals are one sprite when stamped into a surface.
+fn sprites(stamped: bool) -> Vec<(u16, TextureId)> {
+    let emitters: Emitters = include_str!("../static/assets/particles.json")
+        .parse()
+        .unwrap();
+    let mut sprites = if stamped {
+        vec![(1, DECALS)]
+    } else {
+        vec![(1, SHEET); emitters.decals.cap]
+    };
+    let counts = [
+        // spawn points, enemies, players, boss, bullets, power ups
+        10, 40, 2, 1, 150, 10,
+    ];
+    sprites.extend(vec![(2, SHEET); counts.iter().sum()]);
+    sprites.extend(vec![(2, SHEET); emitters.max_particles]);
+    // score popups, three digits each
+    sprites.extend(vec![(2, DIGITS); 10 * 3]);
+    for _player in 0..2 {
+        // score, weapon icon and name, hearts, ammo, multiplier bar
+        sprites.extend(vec![(2, DIGITS); 6]);
+        sprites.push((2, SHEET));
+        sprites.extend(vec![(2, TEXT); 8]);
+        sprites.extend(vec![(2, SHEET); 10]);
+        sprites.extend(vec![(2, DIGITS); 3]);
+        sprites.push((2, WHITE));
+    }
+    sprites
+}
+
+fn map() -> TileMap {
+    include_str!("../static/assets/tilemap.json")
+        .parse()
+        .unwrap()
+}
+
+/// Visible chunks of the cached floor layer
+fn visible_chunks(map: &TileMap) -> usize {
+    let chunks = Chunks::new(map.width as usize, map.height as usize, CHUNK_TILES);
+    let columns = 0..(WIDTH / TILE_SIZE).ceil() as usize;
+    let rows = 0..(HEIGHT / TILE_SIZE).ceil() as usize;
+    chunks.overlapping(columns, rows).len()
+}
+
+/// Batches the frame the way the WebGL renderer does and returns the number
+/// of draw calls
+fn batch_frame(map: &TileMap, sprites: &[(u16, TextureId)], batch: &mut SpriteBatch) -> usize {
+    batch.set_layer(0);
+    for (src, [x, y, w, h]) in visible_tiles(map, 0, WIDTH, HEIGHT) {
+        batch.push(SHEET, src, [w / src[2], 0., 0., h / src[3], x, y], 1., None);
+    }
+    for (i, (layer, texture)) in sprites.iter().enumerate() {
+        batch.set_layer(*layer);
+        let x = (i * 7 % 960) as f64;
+        let y = (i * 13 % 540) as f64;
+        batch.push(
+            *texture,
+            [0., 0., 16., 16.],
+            [2., 0., 0., 2., x, y],
+            1.,
+            None,
+        );
+    }
+    batch.finish().1.len()
+}
+
+#[test]
+fn caching_and_batching_cut_the_draw_calls() {
+    let map = map();
+    let sprites = sprites(false);
+    let tiles = visible_tiles(&map, 0, WIDTH, HEIGHT).len();
+    let chunks = visible_chunks(&map);
+    let batched = batch_frame(&map, &sprites, &mut SpriteBatch::new());
+    let stamped = batch_frame(&map, &self::sprites(true), &mut SpriteBatch::new());
+
+    let uncached = tiles + sprites.len();
+    let cached = chunks + sprites.len();
+    println!("tiles drawn one by one: {} draw calls", uncached);
+    println!("tile layer cache:       {} draw calls", cached);
+    println!("sprite batching:        {} draw calls", batched);
+    println!(
+        "stamped decals:         {} draw calls, {} quads fewer",
+        stamped,
+        sprites.len() - self::sprites(true).len()
+    );
+    assert_eq!(tiles, 2040);
+    assert_eq!(chunks, 12);
+    assert!(batched < 20, "{} batches", batched);
+    // The surface splits the run of the sprite sheet at the decal layer
+    assert!(stamped <= batched + 2, "{} batches", stamped);
+}
+
+#[test]
+#[ignore]
+fn time_spent_batching_a_frame() {
+    let map = map();
+    let frames = 1000;
+    for stamped in [false, true].iter() {
+        let sprites = sprites(*stamped);
+        let mut batch = SpriteBatch::new();
+        for _ in 0..100 {
+            batch_frame(&map, &sprites, &mut batch);
+        }
+        let start = Instant::now();
+        for _ in 0..frames {
+            batch_frame(&map, &sprites, &mut batch);
+        }
+        let tiles_and_sprites = start.elapsed().as_secs_f64() * 1000. / frames as f64;
+        println!(
+            "batching {} tiles and {} sprites: {:.3} ms per frame",
+            visible_tiles(&map, 0, WIDTH, HEIGHT).len(),
+            sprites.len(),
+            tiles_and_sprites
+        );
+    }
+
+    let start = Instant::now();
+    for _ in 0..frames {
+        for (src, _) in visible_tiles(&map, 0, WIDTH, HEIGHT) {
+            assert!(src[2] > 0.);
+        }
+    }
+    let tile_list = start.elapsed().as_secs_f64() * 1000. / frames as f64;
+    println!("listing the tiles: {:.3} ms per frame", tile_list);
+}
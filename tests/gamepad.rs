@@ -0,0 +1,43 @@
+use rust_webpack_template::engine::gamepad::{apply_dead_zone, Bindings};
+use rust_webpack_template::engine::storage::FileStorage;
+use rust_webpack_template::game::settings::Settings;
+use std::env::temp_dir;
+use std::fs::remove_dir_all;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[test]
+fn sticks_in_the_dead_zone_are_centered() {
+    assert_eq!(apply_dead_zone(0.1, -0.1, 0.2), (0., 0.));
+    assert_eq!(apply_dead_zone(1., 0., 0.2), (1., 0.));
+    let (x, y) = apply_dead_zone(0., 0.6, 0.2);
+    assert_eq!(x, 0.);
+    assert!((y - 0.5).abs() < 1e-9);
+    assert_eq!(apply_dead_zone(0.3, 0.4, 0.), (0.3, 0.4));
+}
+
+#[test]
+fn bindings_are_kept_in_the_settings() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = temp_dir().join(format!(
+        "blobs-and-bullets-settings-{}-{}",
+        process::id(),
+        nanos
+    ));
+    let mut settings = Settings::load(Box::new(FileStorage::new(&dir)));
+    assert_eq!(settings.options.bindings, Bindings::default());
+    assert_eq!(settings.options.difficulty, None);
+
+    settings.options.bindings.shoot = 0;
+    settings.options.difficulty = Some(String::from("hard"));
+    settings.save();
+
+    let settings = Settings::load(Box::new(FileStorage::new(&dir)));
+    assert_eq!(settings.options.bindings.shoot, 0);
+    assert_eq!(settings.options.difficulty.as_deref(), Some("hard"));
+    assert_eq!(settings.options.friendly_fire, None);
+    remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,68 @@
+use rust_webpack_template::engine::storage::{FileStorage, Storage};
+use rust_webpack_template::game::high_scores::{HighScore, HighScores, MAX_ENTRIES};
+use std::env::temp_dir;
+use std::fs::remove_dir_all;
+use std::path::PathBuf;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A directory of its own for each run of a test, so leftovers of a crashed
+/// run can't get in the way
+fn test_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    temp_dir().join(format!(
+        "blobs-and-bullets-{}-{}-{}",
+        name,
+        process::id(),
+        nanos
+    ))
+}
+
+fn entry(initials: &str, score: i32) -> HighScore {
+    HighScore {
+        initials: String::from(initials),
+        score,
+    }
+}
+
+#[test]
+fn file_storage_round_trip() {
+    let dir = test_dir("storage-test");
+    let mut storage = FileStorage::new(&dir);
+    assert_eq!(storage.load("missing"), None);
+    storage.save("key", "value");
+    assert_eq!(storage.load("key"), Some(String::from("value")));
+    remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn high_scores_are_sorted_capped_and_persisted() {
+    let dir = test_dir("high-score-test");
+    let mut high_scores = HighScores::load(Box::new(FileStorage::new(&dir)));
+    assert!(high_scores.top("arena", "normal").is_empty());
+
+    for score in 1..=MAX_ENTRIES as i32 {
+        high_scores.insert("arena", "normal", entry("AAA", score * 100));
+    }
+    assert!(!high_scores.qualifies("arena", "normal", 50));
+    assert_eq!(
+        high_scores.insert("arena", "normal", entry("BOB", 550)),
+        Some(5)
+    );
+    assert_eq!(
+        high_scores.insert("arena", "hard", entry("HAX", 10)),
+        Some(0)
+    );
+
+    let reloaded = HighScores::load(Box::new(FileStorage::new(&dir)));
+    let top = reloaded.top("arena", "normal");
+    assert_eq!(top.len(), MAX_ENTRIES);
+    assert_eq!(top[0], entry("AAA", 1000));
+    assert_eq!(top[5], entry("BOB", 550));
+    assert_eq!(top[MAX_ENTRIES - 1], entry("AAA", 200));
+    assert_eq!(reloaded.top("arena", "hard"), &[entry("HAX", 10)][..]);
+    remove_dir_all(&dir).unwrap();
+}
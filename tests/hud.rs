@@ -0,0 +1,72 @@
+use rust_webpack_template::engine::bitmap_font::Align;
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::hud::{Anchor, HudLayout, Placement, WidgetKind};
+
+fn layout() -> HudLayout {
+    include_str!("../static/assets/hud.json").parse().unwrap()
+}
+
+#[test]
+fn anchors_follow_the_resolution() {
+    assert_eq!(Anchor::TopLeft.point(960., 540.), Vec2::new(0., 0.));
+    assert_eq!(Anchor::Bottom.point(960., 540.), Vec2::new(480., 540.));
+    assert_eq!(Anchor::Right.point(1280., 720.), Vec2::new(1280., 360.));
+}
+
+#[test]
+fn there_are_slots_for_up_to_four_players() {
+    let layout = layout();
+    for count in 1..=4 {
+        assert_eq!(layout.slots(count).len(), count);
+    }
+    assert_eq!(layout.slots(5).len(), 4);
+    assert!(HudLayout::default().slots(2).is_empty());
+}
+
+#[test]
+fn mirrored_slots_flip_the_widgets() {
+    let layout = layout();
+    let slots = layout.slots(2);
+    let score = layout
+        .player
+        .iter()
+        .find(|w| w.widget == WidgetKind::Score)
+        .unwrap();
+    let hearts = layout
+        .player
+        .iter()
+        .find(|w| w.widget == WidgetKind::Hearts)
+        .unwrap();
+
+    let left = score.place_in(&slots[0], 960., 540.);
+    let right = score.place_in(&slots[1], 960., 540.);
+    assert_eq!(left.align, Align::Left);
+    assert_eq!(right.align, Align::Right);
+    assert_eq!(left.x, 960. - right.x);
+    assert_eq!(left.y, right.y);
+
+    let left = hearts.place_in(&slots[0], 960., 540.);
+    let right = hearts.place_in(&slots[1], 1280., 720.);
+    assert_eq!(left.x + right.x, 1280.);
+    assert_eq!(left.left(24.) + 24., 1280. - right.left(24.));
+}
+
+#[test]
+fn shared_widgets_are_aligned_to_their_anchor() {
+    let layout = layout();
+    let banner = layout
+        .shared
+        .iter()
+        .find(|w| w.widget == WidgetKind::WaveBanner)
+        .unwrap();
+    let placement = banner.place(960., 540.);
+    assert_eq!(placement.align, Align::Center);
+    assert_eq!(placement.left(100.), 430.);
+
+    let at = Placement {
+        x: 100.,
+        y: 0.,
+        align: Align::Right,
+    };
+    assert_eq!(at.left(30.), 70.);
+}
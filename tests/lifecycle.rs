@@ -0,0 +1,60 @@
+use js_sys::Promise;
+use rust_webpack_template::engine::config::EngineConfig;
+use rust_webpack_template::engine::Engine;
+use rust_webpack_template::game::MyGame;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+use web_sys::{window, HtmlCanvasElement};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn canvases() -> u32 {
+    window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .get_elements_by_tag_name("canvas")
+        .length()
+}
+
+async fn next_frame() {
+    let promise = Promise::new(&mut |resolve, _| {
+        window().unwrap().request_animation_frame(&resolve).unwrap();
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn engine_is_launched_and_stopped_repeatedly() {
+    let before = canvases();
+    for _ in 0..5 {
+        let engine = Engine::launch(MyGame::new(), EngineConfig::default()).unwrap();
+        assert_eq!(canvases(), before + 1);
+        next_frame().await;
+        engine.stop();
+        assert!(engine.control().is_stopped());
+        assert_eq!(canvases(), before);
+        next_frame().await;
+    }
+}
+
+#[wasm_bindgen_test]
+async fn canvases_given_by_the_page_are_kept() {
+    let document = window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<HtmlCanvasElement>()
+        .unwrap();
+    document.body().unwrap().append_child(&canvas).unwrap();
+    let before = canvases();
+
+    let config = EngineConfig::default().with_canvas(canvas.clone());
+    let engine = Engine::launch(MyGame::new(), config).unwrap();
+    assert_eq!(canvases(), before);
+    next_frame().await;
+    engine.stop();
+    assert!(canvas.is_connected());
+    canvas.remove();
+}
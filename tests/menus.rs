@@ -0,0 +1,166 @@
+//! Every row of the menus fits in the menu panels, measured with the fonts
+//! the menus are drawn with. The rows follow the layout of the widgets: text
+//! is kept 8 pixels from the edges of the column, and sliders take its right
+//! half.
+use rust_webpack_template::engine::bitmap_font::{BitmapFont, TextStyle};
+use rust_webpack_template::engine::config::EngineConfig;
+use rust_webpack_template::engine::ui::Theme;
+use rust_webpack_template::game::coop::CoopConfig;
+use rust_webpack_template::game::high_scores::MAX_ENTRIES;
+use rust_webpack_template::game::menus::{theme, PANEL_TOP, PANEL_WIDTH};
+
+/// Distance of text from the edges of the column
+const INSET: f64 = 8.;
+
+/// Smallest gap between the label and the value of a row
+const GAP: f64 = 16.;
+
+const HEADINGS: [&str; 5] = [
+    "BLOBS AND BULLETS",
+    "PAUSED",
+    "OPTIONS",
+    "CONTROLS",
+    "HIGH SCORES",
+];
+
+const BUTTONS: [&str; 8] = [
+    "PLAY",
+    "HIGH SCORES",
+    "OPTIONS",
+    "RESUME",
+    "QUIT",
+    "CONTROLS",
+    "RESET",
+    "BACK",
+];
+
+struct Menus {
+    theme: Theme,
+    text: BitmapFont,
+    digits: BitmapFont,
+    /// Width of the column the widgets are stacked in
+    width: f64,
+}
+
+impl Menus {
+    fn new() -> Self {
+        let theme = theme();
+        let width = PANEL_WIDTH - 2. * theme.padding;
+        Menus {
+            theme,
+            text: include_str!("../static/assets/font.json").parse().unwrap(),
+            digits: include_str!("../static/assets/digits.json")
+                .parse()
+                .unwrap(),
+            width,
+        }
+    }
+
+    /// Width of a label or value, in the font the widgets pick for it
+    fn measure(&self, text: &str, scale: f64) -> f64 {
+        if text.starts_with(|c: char| c.is_ascii_digit()) {
+            let style = TextStyle::scaled(self.theme.digit_scale);
+            self.digits.measure(text, &style).0
+        } else {
+            self.text.measure(text, &TextStyle::scaled(scale)).0
+        }
+    }
+
+    fn assert_centered_fits(&self, text: &str, scale: f64, width: f64) {
+        let measured = self.measure(text, scale);
+        assert!(
+            measured <= width - 2. * INSET,
+            "{} is {} wide, the room is {}",
+            text,
+            measured,
+            width - 2. * INSET
+        );
+    }
+
+    fn assert_row_fits(&self, label: &str, value: &str) {
+        let scale = self.theme.text_scale;
+        let measured = self.measure(label, scale) + GAP + self.measure(value, scale);
+        assert!(
+            measured <= self.width - 2. * INSET,
+            "{} {} is {} wide, the room is {}",
+            label,
+            value,
+            measured,
+            self.width - 2. * INSET
+        );
+    }
+}
+
+fn difficulties() -> Vec<String> {
+    let coop: CoopConfig = include_str!("../static/assets/difficulty.json")
+        .parse()
+        .unwrap();
+    coop.difficulties
+        .iter()
+        .map(|d| d.name.to_uppercase())
+        .collect()
+}
+
+#[test]
+fn headings_buttons_and_labels_fit() {
+    let menus = Menus::new();
+    for heading in HEADINGS.iter() {
+        menus.assert_centered_fits(heading, menus.theme.heading_scale, menus.width);
+    }
+    for button in BUTTONS.iter() {
+        menus.assert_centered_fits(button, menus.theme.text_scale, menus.width);
+    }
+    for label in difficulties()
+        .iter()
+        .map(String::as_str)
+        .chain(Some("NO SCORES"))
+    {
+        menus.assert_centered_fits(label, menus.theme.text_scale, menus.width);
+    }
+}
+
+#[test]
+fn labels_and_values_fit_side_by_side() {
+    let menus = Menus::new();
+    for difficulty in difficulties() {
+        menus.assert_row_fits("DIFFICULTY", &difficulty);
+        menus.assert_row_fits("HIGH SCORES", &difficulty);
+    }
+    for state in ["ON", "OFF"].iter() {
+        menus.assert_row_fits("FRIENDLY FIRE", state);
+    }
+    for action in ["SHOOT", "NEXT WEAPON"].iter() {
+        menus.assert_row_fits(action, "PRESS");
+        for button in 0..17 {
+            menus.assert_row_fits(action, &button.to_string());
+        }
+    }
+}
+
+#[test]
+fn slider_labels_fit_left_of_the_track() {
+    let menus = Menus::new();
+    let label = menus.measure("DEAD ZONE", menus.theme.text_scale);
+    assert!(label + INSET + GAP <= menus.width / 2., "{}", label);
+}
+
+#[test]
+fn high_score_columns_fit() {
+    let menus = Menus::new();
+    let cell = menus.width / 3.;
+    for text in ["10", "WWW", "99999999"].iter() {
+        menus.assert_centered_fits(text, menus.theme.text_scale, cell);
+    }
+}
+
+#[test]
+fn the_title_screen_fits_a_full_score_table() {
+    let theme = theme();
+    let heading = theme.row_height * theme.heading_scale / theme.text_scale;
+    // The heading, a gap, the buttons and the table
+    let content = heading + 16. + 3. * theme.row_height + MAX_ENTRIES as f64 * theme.cell_height;
+    // The border sticks out 2 pixels
+    let bottom = PANEL_TOP + 2. * theme.padding + content + 2.;
+    let height = EngineConfig::default().height() as f64;
+    assert!(bottom <= height, "{} is below {}", bottom, height);
+}
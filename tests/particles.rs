@@ -0,0 +1,127 @@
+use rust_webpack_template::engine::decals::DecalLayer;
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::particles::{color_at, value_at, Emitters, Particles};
+use rust_webpack_template::engine::transform::Color;
+
+fn emitters() -> Emitters {
+    r##"{
+        "max_particles": 10,
+        "emitters": {
+            "burst": {"burst": 4, "speed": [1, 1], "lifetime": [3, 3],
+                      "decal": {"frames": ["blood_0", "blood_1"], "chance": 1, "tint": "#400"}},
+            "fountain": {"rate": 0.5, "duration": 4, "speed": [2, 2], "lifetime": [10, 10],
+                         "direction": 90, "spread": 0, "gravity": [0, -1], "drag": 0.5,
+                         "colors": ["#f00", "#00f"], "alpha": [1, 0]}
+        }
+    }"##
+    .parse()
+    .unwrap()
+}
+
+#[test]
+fn the_game_effects_load() {
+    let emitters: Emitters = include_str!("../static/assets/particles.json")
+        .parse()
+        .unwrap();
+    for name in ["sparks", "blood", "water", "explosion", "enemy_splat_0"].iter() {
+        assert!(emitters.get(name).is_some(), "{}", name);
+    }
+}
+
+#[test]
+fn bursts_die_at_the_end_of_their_lifetime() {
+    let emitters = emitters();
+    let mut particles = Particles::default();
+    let mut decals = DecalLayer::default();
+    particles.emit(&emitters, "burst", &Vec2::new(10., 10.));
+    particles.emit(&emitters, "unknown", &Vec2::new(10., 10.));
+    assert_eq!(particles.len(), 4);
+    for p in particles.iter() {
+        assert!((p.vel.length() - 1.).abs() < 1e-9);
+    }
+    particles.update(&emitters, &mut decals);
+    particles.update(&emitters, &mut decals);
+    assert_eq!(particles.len(), 4);
+    particles.update(&emitters, &mut decals);
+    assert!(particles.is_empty());
+
+    // Each of them landed
+    assert_eq!(decals.len(), 4);
+    for decal in decals.iter() {
+        assert!(decal.frame.name().starts_with("blood_"));
+        assert!((decal.pos.x - 10.).abs() <= 3.);
+        assert_eq!(decal.tint, Some(Color::rgb(68, 0, 0)));
+    }
+}
+
+#[test]
+fn the_pool_has_a_maximum() {
+    let emitters = emitters();
+    let mut particles = Particles::default();
+    for _ in 0..3 {
+        particles.emit(&emitters, "burst", &Vec2::new(0., 0.));
+    }
+    assert_eq!(particles.len(), 10);
+    particles.burst(&emitters, "burst", &Vec2::new(0., 0.), 1);
+    assert_eq!(particles.len(), 10);
+}
+
+#[test]
+fn emitters_with_a_rate_keep_emitting() {
+    let emitters = emitters();
+    let mut particles = Particles::default();
+    let mut decals = DecalLayer::default();
+    particles.emit(&emitters, "fountain", &Vec2::new(0., 0.));
+    assert!(particles.is_empty());
+    for _ in 0..10 {
+        particles.update(&emitters, &mut decals);
+    }
+    assert_eq!(particles.len(), 2);
+
+    // Straight down, slowed by drag and pulled up by gravity
+    let first = particles.iter().next().unwrap();
+    assert!(first.vel.x.abs() < 1e-9);
+    assert!(first.vel.y < 0.);
+}
+
+#[test]
+fn colors_and_alpha_change_over_life() {
+    let red = Color::rgb(255, 0, 0);
+    let blue = Color::rgb(0, 0, 255);
+    assert_eq!(color_at(&[], 0.5), None);
+    assert_eq!(color_at(&[red, blue], 0.), Some(red));
+    assert_eq!(color_at(&[red, blue], 1.), Some(blue));
+    assert_eq!(color_at(&[red, blue], 0.5), Some(Color::rgb(128, 0, 128)));
+    assert_eq!(value_at(&[1., 0., 1.], 0.25), Some(0.5));
+    assert_eq!(value_at(&[0.5], 0.9), Some(0.5));
+}
+
+#[test]
+fn particles_survive_snapshots() {
+    let emitters = emitters();
+    let mut particles = Particles::default();
+    particles.emit(&emitters, "burst", &Vec2::new(0., 0.));
+    let json = serde_json::to_string(&particles).unwrap();
+    let mut restored: Particles = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.len(), 4);
+
+    // Particles of emitters that are gone are dropped
+    restored.update(&Emitters::default(), &mut DecalLayer::default());
+    assert!(restored.is_empty());
+}
+
+#[test]
+fn restored_pools_over_the_maximum_stop_spawning() {
+    let mut bigger = emitters();
+    bigger.max_particles = 20;
+    let mut particles = Particles::default();
+    for _ in 0..3 {
+        particles.emit(&bigger, "burst", &Vec2::new(0., 0.));
+    }
+    let json = serde_json::to_string(&particles).unwrap();
+    let mut restored: Particles = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.len(), 12);
+
+    restored.burst(&emitters(), "burst", &Vec2::new(0., 0.), 1);
+    assert_eq!(restored.len(), 12);
+}
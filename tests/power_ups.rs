@@ -0,0 +1,154 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::random;
+use rust_webpack_template::game::power_ups::{DropTable, PowerUpRegistry};
+use rust_webpack_template::game::Player;
+
+fn registry() -> PowerUpRegistry {
+    r#"{
+        "despawn": 10,
+        "blink": 4,
+        "max_health": 30,
+        "max_ammo": 100,
+        "power_ups": [
+            { "name": "ammo", "sprite": "icon_ammo", "effect": { "type": "ammo", "amount": 25 } },
+            { "name": "health", "sprite": "icon_health", "effect": { "type": "health", "amount": 5 } },
+            {
+                "name": "shield",
+                "sprite": "icon_shield",
+                "effect": { "type": "shield" },
+                "duration": 3
+            }
+        ],
+        "spawn_point_drops": [[
+            { "power_up": "ammo", "chance": 0.6 },
+            { "power_up": "health", "chance": 0.2 }
+        ]]
+    }"#
+    .parse()
+    .unwrap()
+}
+
+fn player() -> Player {
+    Player::new(Vec2::new(0., 0.), 1)
+}
+
+#[test]
+fn the_game_power_ups_load() {
+    let registry: PowerUpRegistry = include_str!("../static/assets/powerups.json")
+        .parse()
+        .unwrap();
+    assert!(registry.blink < registry.despawn);
+    for table in registry.spawn_point_drops.iter() {
+        for drop in table.0.iter() {
+            assert!(registry.find(&drop.power_up).is_some(), "{}", drop.power_up);
+        }
+    }
+}
+
+#[test]
+fn an_empty_drop_table_drops_nothing() {
+    random::seed(1);
+    let table = DropTable::default();
+    for _ in 0..100 {
+        assert_eq!(table.roll(), None);
+    }
+}
+
+#[test]
+fn drops_follow_the_chances() {
+    let registry = registry();
+    let table = &registry.spawn_point_drops[0];
+    random::seed(42);
+    let rolls = 4000;
+    let (mut ammo, mut health, mut nothing) = (0, 0, 0);
+    for _ in 0..rolls {
+        match table.roll() {
+            Some("ammo") => ammo += 1,
+            Some("health") => health += 1,
+            None => nothing += 1,
+            Some(other) => panic!("{}", other),
+        }
+    }
+    let share = |count: i32| count as f64 / rolls as f64;
+    assert!((share(ammo) - 0.6).abs() < 0.03, "{}", share(ammo));
+    assert!((share(health) - 0.2).abs() < 0.03, "{}", share(health));
+    assert!((share(nothing) - 0.2).abs() < 0.03, "{}", share(nothing));
+}
+
+#[test]
+fn health_is_capped() {
+    let registry = registry();
+    let health = registry.find("health").unwrap();
+    let mut player = player();
+    player.health = 20;
+    player.collect(health, &registry);
+    assert_eq!(player.health, 25);
+    player.health = 28;
+    player.collect(health, &registry);
+    assert_eq!(player.health, 30);
+    // Health above the cap is kept, never taken away
+    player.health = 40;
+    player.collect(health, &registry);
+    assert_eq!(player.health, 40);
+}
+
+#[test]
+fn ammo_is_capped() {
+    let registry = registry();
+    let ammo = registry.find("ammo").unwrap();
+    let mut player = player();
+    player.ammo = 50;
+    player.collect(ammo, &registry);
+    assert_eq!(player.ammo, 75);
+    player.collect(ammo, &registry);
+    player.collect(ammo, &registry);
+    assert_eq!(player.ammo, 100);
+    player.ammo = 150;
+    player.collect(ammo, &registry);
+    assert_eq!(player.ammo, 150);
+}
+
+#[test]
+fn buffs_expire_after_their_duration() {
+    let registry = registry();
+    let shield = registry.find("shield").unwrap();
+    let mut player = player();
+    player.collect(shield, &registry);
+    assert_eq!(player.buffs.len(), 1);
+    player.update_buffs();
+    player.update_buffs();
+    assert_eq!(player.buffs.len(), 1);
+    player.update_buffs();
+    assert!(player.buffs.is_empty());
+}
+
+#[test]
+fn collecting_an_active_buff_restarts_it() {
+    let registry = registry();
+    let shield = registry.find("shield").unwrap();
+    let mut player = player();
+    player.collect(shield, &registry);
+    player.update_buffs();
+    player.update_buffs();
+    player.collect(shield, &registry);
+    assert_eq!(player.buffs.len(), 1);
+    assert_eq!(player.buffs[0].remaining, 3);
+}
+
+#[test]
+fn power_ups_blink_then_despawn() {
+    let registry = registry();
+    assert!(registry.spawn("rocket", &Vec2::new(0., 0.)).is_none());
+    let mut power_up = registry.spawn("ammo", &Vec2::new(0., 0.)).unwrap();
+    assert_eq!(power_up.ttl, 10);
+    let mut ticks = 0;
+    while !registry.blinking(&power_up) {
+        assert!(power_up.update());
+        ticks += 1;
+    }
+    assert_eq!(ticks, 7);
+    for _ in 0..2 {
+        assert!(power_up.update());
+    }
+    assert!(!power_up.update());
+}
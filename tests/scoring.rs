@@ -0,0 +1,116 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::scoring::{
+    ScorePopup, Scoring, Stats, COMBO_WINDOW, KILLS_PER_LEVEL, MAX_MULTIPLIER,
+};
+
+fn kills(scoring: &mut Scoring, count: u32) {
+    for _ in 0..count {
+        scoring.kill(0, 10);
+    }
+}
+
+fn tick(scoring: &mut Scoring, ticks: u32) {
+    for _ in 0..ticks {
+        scoring.update();
+    }
+}
+
+#[test]
+fn the_multiplier_rises_with_the_streak() {
+    let mut scoring = Scoring::default();
+    assert_eq!(scoring.multiplier(), 1);
+    kills(&mut scoring, KILLS_PER_LEVEL - 1);
+    assert_eq!(scoring.multiplier(), 1);
+    kills(&mut scoring, 1);
+    assert_eq!(scoring.multiplier(), 2);
+    kills(&mut scoring, KILLS_PER_LEVEL * 20);
+    assert_eq!(scoring.multiplier(), MAX_MULTIPLIER as i32);
+}
+
+#[test]
+fn kills_score_with_the_multiplier() {
+    let mut scoring = Scoring::default();
+    assert_eq!(scoring.kill(2, 100), 100);
+    kills(&mut scoring, KILLS_PER_LEVEL - 2);
+    // the kill counts towards the streak before it is scored
+    assert_eq!(scoring.kill(1, 100), 200);
+    assert_eq!(scoring.score, 100 + 10 * (KILLS_PER_LEVEL as i32 - 2) + 200);
+    assert_eq!(scoring.award(50), 100);
+    assert_eq!(scoring.stats.kills, vec![KILLS_PER_LEVEL - 2, 1, 1]);
+}
+
+#[test]
+fn the_combo_drops_a_level_at_a_time() {
+    let mut scoring = Scoring::default();
+    kills(&mut scoring, KILLS_PER_LEVEL * 2 + 2);
+    assert_eq!(scoring.multiplier(), 3);
+
+    tick(&mut scoring, COMBO_WINDOW);
+    assert_eq!(scoring.multiplier(), 3);
+    tick(&mut scoring, 1);
+    assert_eq!(scoring.multiplier(), 2);
+    assert_eq!(scoring.combo_timer, COMBO_WINDOW);
+
+    tick(&mut scoring, COMBO_WINDOW + 1);
+    assert_eq!(scoring.multiplier(), 1);
+    assert_eq!(scoring.streak, 0);
+}
+
+#[test]
+fn kills_keep_the_combo_going() {
+    let mut scoring = Scoring::default();
+    kills(&mut scoring, KILLS_PER_LEVEL);
+    for _ in 0..5 {
+        tick(&mut scoring, COMBO_WINDOW - 1);
+        kills(&mut scoring, 1);
+    }
+    assert_eq!(scoring.multiplier(), 3);
+}
+
+#[test]
+fn short_streaks_end_after_the_window() {
+    let mut scoring = Scoring::default();
+    kills(&mut scoring, 2);
+    tick(&mut scoring, COMBO_WINDOW + 1);
+    assert_eq!(scoring.streak, 0);
+    assert_eq!(scoring.combo_timer, 0);
+}
+
+#[test]
+fn the_displayed_score_counts_up() {
+    let mut scoring = Scoring::default();
+    scoring.award(100);
+    tick(&mut scoring, 1);
+    assert_eq!(scoring.displayed, 25);
+    tick(&mut scoring, 1);
+    assert_eq!(scoring.displayed, 50);
+    // slower once close
+    tick(&mut scoring, 1);
+    assert_eq!(scoring.displayed, 55);
+    tick(&mut scoring, 20);
+    assert_eq!(scoring.displayed, 100);
+}
+
+#[test]
+fn accuracy_is_a_percentage_of_shots() {
+    let mut stats = Stats::default();
+    assert_eq!(stats.accuracy(), 0);
+    stats.shots_fired = 8;
+    stats.shots_hit = 6;
+    assert_eq!(stats.accuracy(), 75);
+    // piercing bullets can hit more than once
+    stats.shots_hit = 12;
+    assert_eq!(stats.accuracy(), 100);
+}
+
+#[test]
+fn popups_float_up_and_expire() {
+    let mut popup = ScorePopup::new(&Vec2::new(10., 10.), 50);
+    let ttl = popup.ttl;
+    popup.update();
+    assert_eq!(popup.pos, Vec2::new(10., 9.5));
+    for _ in 0..ttl {
+        popup.update();
+    }
+    assert_eq!(popup.ttl, 0);
+}
@@ -0,0 +1,160 @@
+use rust_webpack_template::engine::animation::Animator;
+use rust_webpack_template::engine::atlas::FrameId;
+use rust_webpack_template::engine::decals::Decal;
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::particles::{Emitters, Particles};
+use rust_webpack_template::engine::random;
+use rust_webpack_template::game::boss::{Boss, BossDefinitions};
+use rust_webpack_template::game::bullets::{BulletSpec, Pattern, Shooter};
+use rust_webpack_template::game::coop::Difficulty;
+use rust_webpack_template::game::power_ups::PowerUp;
+use rust_webpack_template::game::scoring::ScorePopup;
+use rust_webpack_template::game::snapshot::{Snapshot, SnapshotError, SNAPSHOT_VERSION};
+use rust_webpack_template::game::waves::{WaveConfig, WaveDirector};
+use rust_webpack_template::game::{Enemy, Player};
+
+/// A snapshot with a bit of everything in it
+fn snapshot() -> Snapshot {
+    random::seed(7);
+    let mut player_1 = Player::new(Vec2::new(100., 100.), 1);
+    player_1.hit(3, &Vec2::new(2., 0.));
+    player_1.scoring.kill(1, 100);
+    let player_2 = Player::new(Vec2::new(170., 90.), 2);
+
+    let mut bullets = Vec::new();
+    BulletSpec::new(
+        Pattern::Spread {
+            count: 3,
+            angle: 0.5,
+        },
+        4.,
+        FrameId::named("bullet"),
+    )
+    .emit(
+        &player_1.pos,
+        &Vec2::new(200., 100.),
+        Shooter::Player(1),
+        &mut 0.,
+        &mut bullets,
+    );
+
+    let emitters: Emitters = include_str!("../static/assets/particles.json")
+        .parse()
+        .unwrap();
+    let mut particles = Particles::default();
+    particles.emit(&emitters, "blood", &Vec2::new(50., 50.));
+
+    let mut waves = WaveDirector::new(
+        include_str!("../static/assets/waves.json")
+            .parse::<WaveConfig>()
+            .unwrap(),
+    );
+    for _ in 0..200 {
+        waves.update(0, 1.0);
+    }
+    waves.record_kill();
+
+    let bosses: BossDefinitions = include_str!("../static/assets/bosses.json")
+        .parse()
+        .unwrap();
+    let mut boss = Boss::new(0, &bosses.bosses[0]);
+    boss.health = 10;
+
+    Snapshot {
+        version: SNAPSHOT_VERSION,
+        rng: random::state(),
+        player_1,
+        player_2,
+        bullets,
+        particles,
+        decals: vec![Decal::new(FrameId::named("blood_0"), &Vec2::new(5., 6.))],
+        spawn_points: vec![(Vec2::new(10., 10.), 0, 2), (Vec2::new(20., 10.), 1, 0)],
+        next_spawn_point: 3,
+        enemies: vec![Enemy {
+            pos: Vec2::new(300., 200.),
+            dir: 1.5,
+            kind: 2,
+            health: 4,
+            spin: 0.,
+            knockback: Vec2::new(1., 0.),
+            animator: Animator::new("green_walk_right"),
+            hit_cooldown: 3,
+        }],
+        waves,
+        boss: Some(boss),
+        power_ups: vec![PowerUp {
+            pos: Vec2::new(40., 40.),
+            kind: 1,
+            ttl: 300,
+        }],
+        popups: vec![ScorePopup::new(&Vec2::new(40., 40.), 200)],
+        difficulty: Difficulty {
+            name: String::from("normal"),
+            bleed_out: 600,
+            revive_ticks: 120,
+            revive_radius: 32.,
+            revive_health: 3,
+            continues: 2,
+            respawn_health: 5,
+        },
+        continues: 1,
+    }
+}
+
+fn assert_same(restored: &Snapshot, original: &Snapshot) {
+    assert_eq!(restored.to_json().unwrap(), original.to_json().unwrap());
+    assert_eq!(restored.rng, original.rng);
+    assert_eq!(restored.player_1.health, original.player_1.health);
+    assert_eq!(restored.player_1.scoring.score, 100);
+    assert_eq!(restored.bullets.len(), 3);
+    assert_eq!(restored.particles.len(), original.particles.len());
+    assert!(!restored.particles.is_empty());
+    assert_eq!(restored.decals[0].pos, Vec2::new(5., 6.));
+    assert_eq!(restored.enemies[0].hit_cooldown, 3);
+    assert_eq!(restored.waves.wave, original.waves.wave);
+    assert_eq!(restored.boss.as_ref().unwrap().health, 10);
+    assert_eq!(restored.difficulty.name, "normal");
+    assert_eq!(restored.continues, 1);
+}
+
+#[test]
+fn snapshots_round_trip_through_json() {
+    let original = snapshot();
+    let json = original.to_json().unwrap();
+    let restored = Snapshot::from_json(&json).unwrap();
+    assert_same(&restored, &original);
+}
+
+#[test]
+fn snapshots_round_trip_through_bytes() {
+    let original = snapshot();
+    let bytes = original.to_bytes().unwrap();
+    let restored = Snapshot::from_bytes(&bytes).unwrap();
+    assert_same(&restored, &original);
+    assert!(bytes.len() < original.to_json().unwrap().len());
+}
+
+#[test]
+fn restoring_random_state_repeats_the_sequence() {
+    random::seed(42);
+    let state = random::state();
+    let first: Vec<f64> = (0..10).map(|_| random::random()).collect();
+    assert!(first.iter().all(|r| (0. ..1.).contains(r)));
+
+    random::set_state(state);
+    let second: Vec<f64> = (0..10).map(|_| random::random()).collect();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn snapshots_from_other_versions_are_rejected() {
+    match Snapshot::from_json(r#"{"version": 0}"#) {
+        Err(SnapshotError::Version(0)) => {}
+        _ => panic!("expected a version error"),
+    }
+    let bytes = rmp_serde::to_vec_named(&serde_json::json!({ "version": 99 })).unwrap();
+    match Snapshot::from_bytes(&bytes) {
+        Err(SnapshotError::Version(99)) => {}
+        _ => panic!("expected a version error"),
+    }
+}
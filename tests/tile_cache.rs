@@ -0,0 +1,45 @@
+use rust_webpack_template::engine::frame_timer::{FrameTimer, FRAME_SAMPLES};
+use rust_webpack_template::engine::tile_cache::Chunks;
+
+#[test]
+fn layers_are_split_into_chunks() {
+    let chunks = Chunks::new(60, 34, 16);
+    assert_eq!((chunks.columns(), chunks.rows(), chunks.len()), (4, 3, 12));
+    assert_eq!(chunks.chunk_at(0, 0), Some(0));
+    assert_eq!(chunks.chunk_at(59, 33), Some(11));
+    assert_eq!(chunks.chunk_at(60, 0), None);
+    assert_eq!(chunks.tiles(0), (0..16, 0..16));
+    assert_eq!(chunks.tiles(11), (48..60, 32..34));
+    assert_eq!(chunks.overlapping(0..60, 0..34).len(), 12);
+    assert_eq!(chunks.overlapping(10..20, 0..10), vec![0, 1]);
+    assert_eq!(chunks.overlapping(0..100, 33..40), vec![8, 9, 10, 11]);
+}
+
+#[test]
+fn only_changed_chunks_are_redrawn() {
+    let mut chunks = Chunks::new(60, 34, 16);
+    assert_eq!(chunks.take_dirty().len(), 12);
+    assert!(chunks.take_dirty().is_empty());
+
+    chunks.invalidate(17, 1);
+    chunks.invalidate(18, 2);
+    chunks.invalidate(59, 33);
+    chunks.invalidate(99, 99);
+    assert_eq!(chunks.take_dirty(), vec![1, 11]);
+
+    chunks.invalidate_all();
+    assert_eq!(chunks.take_dirty().len(), 12);
+}
+
+#[test]
+fn frame_timer_keeps_the_last_frames() {
+    let mut timer = FrameTimer::new();
+    assert_eq!(timer.average(), None);
+    for _ in 0..FRAME_SAMPLES {
+        timer.record(10.);
+    }
+    timer.record(40.);
+    assert_eq!(timer.max(), Some(40.));
+    let expected = (10. * (FRAME_SAMPLES - 1) as f64 + 40.) / FRAME_SAMPLES as f64;
+    assert!((timer.average().unwrap() - expected).abs() < 1e-9);
+}
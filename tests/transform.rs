@@ -0,0 +1,74 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::transform::{Color, Transform};
+use std::f64::consts::PI;
+
+fn apply(m: &[f64; 6], x: f64, y: f64) -> (f64, f64) {
+    let px = m[0] * x + m[2] * y + m[4];
+    let py = m[1] * x + m[3] * y + m[5];
+    ((px * 1e9).round() / 1e9, (py * 1e9).round() / 1e9)
+}
+
+#[test]
+fn pivot_ends_up_at_the_position() {
+    let pivot = Vec2::new(4., 4.);
+    let transforms = [
+        Transform::default(),
+        Transform::rotated(1.),
+        Transform {
+            flip_x: true,
+            flip_y: true,
+            scale: Vec2::new(3., 0.5),
+            ..Transform::default()
+        },
+    ];
+    for transform in transforms.iter() {
+        let m = transform.matrix(100., 50., &pivot, 2.);
+        assert_eq!(apply(&m, 4., 4.), (100., 50.));
+    }
+}
+
+#[test]
+fn frames_are_scaled_flipped_and_rotated_around_the_pivot() {
+    let pivot = Vec2::new(0., 0.);
+    let m = Transform::default().matrix(10., 10., &pivot, 2.);
+    assert_eq!(apply(&m, 8., 8.), (26., 26.));
+
+    let flipped = Transform {
+        flip_x: true,
+        ..Transform::default()
+    };
+    let m = flipped.matrix(10., 10., &pivot, 2.);
+    assert_eq!(apply(&m, 8., 8.), (-6., 26.));
+
+    let m = Transform::rotated(PI / 2.).matrix(10., 10., &pivot, 2.);
+    assert_eq!(apply(&m, 8., 0.), (10., 26.));
+}
+
+#[test]
+fn only_the_default_transform_is_identity() {
+    assert!(Transform::default().is_identity());
+    assert!(!Transform::rotated(0.1).is_identity());
+    let tinted = Transform {
+        tint: Some(Color::RED.with_alpha(0.5)),
+        ..Transform::default()
+    };
+    assert!(!tinted.is_identity());
+    assert_eq!(Color::RED.with_alpha(0.5).to_css(), "rgba(255,0,0,0.5)");
+}
+
+#[test]
+fn css_colors_are_read() {
+    assert_eq!(Color::from_css("red"), Some(Color::RED));
+    assert_eq!(Color::from_css("#fff"), Some(Color::WHITE));
+    assert_eq!(Color::from_css("#00ff80"), Some(Color::rgb(0, 255, 128)));
+    assert_eq!(
+        Color::from_css("#000a"),
+        Some(Color::rgb(0, 0, 0).with_alpha(170. / 255.))
+    );
+    assert_eq!(
+        Color::from_css("#10203040"),
+        Some(Color::rgb(16, 32, 48).with_alpha(64. / 255.))
+    );
+    assert_eq!(Color::from_css("#12"), None);
+    assert_eq!(Color::from_css("rebeccapurple"), None);
+}
@@ -0,0 +1,162 @@
+use rust_webpack_template::engine::atlas::FrameId;
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::transform::Color;
+use rust_webpack_template::engine::ui::{MenuInput, Theme, Ui};
+
+fn ui() -> Ui {
+    Ui::new(Theme {
+        cursor: FrameId::named("aim"),
+        knob: FrameId::named("bullet"),
+        panel: Color::BLACK,
+        border: Color::WHITE,
+        highlight: Color::WHITE,
+        focus: Color::WHITE,
+        off: Color::BLACK,
+        track: Color::BLACK,
+        fill: Color::WHITE,
+        heading_scale: 3.,
+        text_scale: 2.,
+        digit_scale: 4.,
+        row_height: 40.,
+        cell_height: 28.,
+        padding: 20.,
+    })
+}
+
+#[derive(Default)]
+struct Options {
+    sound: bool,
+    volume: f64,
+    difficulty: usize,
+}
+
+/// A menu with a button on top, a toggle, a slider, a list and a button
+/// below, in a column from x = 100 to 500 starting at y = 0. Returns
+/// whether the buttons were pressed.
+fn menu(ui: &mut Ui, input: MenuInput, options: &mut Options) -> (bool, bool) {
+    ui.begin("options", input);
+    ui.column(300., 0., 400.);
+    ui.label("OPTIONS");
+    let play = ui.button("PLAY");
+    ui.toggle("SOUND", &mut options.sound);
+    ui.slider("VOLUME", &mut options.volume, 0., 1., 0.25);
+    ui.list(
+        "DIFFICULTY",
+        &["EASY", "NORMAL", "HARD"],
+        &mut options.difficulty,
+    );
+    let back = ui.button("BACK");
+    ui.end();
+    (play, back)
+}
+
+fn press(f: fn(&mut MenuInput)) -> MenuInput {
+    let mut input = MenuInput::default();
+    f(&mut input);
+    input
+}
+
+#[test]
+fn focus_moves_with_up_and_down_and_wraps() {
+    let mut ui = ui();
+    let mut options = Options::default();
+    menu(&mut ui, MenuInput::default(), &mut options);
+    assert_eq!(ui.focus(), 0);
+
+    menu(&mut ui, press(|i| i.down = true), &mut options);
+    assert_eq!(ui.focus(), 1);
+    // Held down, it only moves again after a delay
+    for _ in 0..10 {
+        menu(&mut ui, press(|i| i.down = true), &mut options);
+    }
+    assert_eq!(ui.focus(), 1);
+    menu(&mut ui, MenuInput::default(), &mut options);
+    menu(&mut ui, press(|i| i.up = true), &mut options);
+    menu(&mut ui, MenuInput::default(), &mut options);
+    menu(&mut ui, press(|i| i.up = true), &mut options);
+    assert_eq!(ui.focus(), 4);
+
+    // Another menu starts at the top
+    ui.begin("other", MenuInput::default());
+    ui.button("ONE");
+    ui.end();
+    assert_eq!(ui.focus(), 0);
+}
+
+#[test]
+fn buttons_are_pressed_once_per_confirm() {
+    let mut ui = ui();
+    let mut options = Options::default();
+    // Confirm held while the menu opens doesn't press anything
+    let confirm = press(|i| i.confirm = true);
+    assert_eq!(menu(&mut ui, confirm.clone(), &mut options), (false, false));
+    menu(&mut ui, MenuInput::default(), &mut options);
+    assert_eq!(menu(&mut ui, confirm.clone(), &mut options), (true, false));
+    assert_eq!(menu(&mut ui, confirm, &mut options), (false, false));
+    assert!(!ui.back_pressed());
+    menu(&mut ui, press(|i| i.back = true), &mut options);
+    assert!(ui.back_pressed());
+}
+
+#[test]
+fn left_and_right_change_the_focused_widget() {
+    let mut ui = ui();
+    let mut options = Options::default();
+    let step = |ui: &mut Ui, options: &mut Options, f: fn(&mut MenuInput)| {
+        menu(ui, press(f), options);
+        menu(ui, MenuInput::default(), options);
+    };
+    step(&mut ui, &mut options, |i| i.right = true);
+    assert!(!options.sound);
+
+    step(&mut ui, &mut options, |i| i.down = true);
+    step(&mut ui, &mut options, |i| i.right = true);
+    assert!(options.sound);
+    step(&mut ui, &mut options, |i| i.confirm = true);
+    assert!(!options.sound);
+
+    step(&mut ui, &mut options, |i| i.down = true);
+    step(&mut ui, &mut options, |i| i.right = true);
+    step(&mut ui, &mut options, |i| i.right = true);
+    assert_eq!(options.volume, 0.5);
+    for _ in 0..5 {
+        step(&mut ui, &mut options, |i| i.right = true);
+    }
+    assert_eq!(options.volume, 1.);
+
+    step(&mut ui, &mut options, |i| i.down = true);
+    step(&mut ui, &mut options, |i| i.left = true);
+    assert_eq!(options.difficulty, 2);
+    step(&mut ui, &mut options, |i| i.confirm = true);
+    assert_eq!(options.difficulty, 0);
+}
+
+#[test]
+fn pointing_focuses_and_clicking_presses() {
+    let mut ui = ui();
+    let mut options = Options::default();
+    let at = |x: f64, y: f64, pressed: bool| MenuInput {
+        pointer: Some(Vec2::new(x, y)),
+        pointer_pressed: pressed,
+        ..MenuInput::default()
+    };
+    // Rows are 40 high below the label: play, sound, volume, difficulty, back
+    menu(&mut ui, at(150., 10., false), &mut options);
+    menu(&mut ui, at(150., 210., false), &mut options);
+    assert_eq!(ui.focus(), 4);
+    assert_eq!(
+        menu(&mut ui, at(150., 210., true), &mut options),
+        (false, true)
+    );
+
+    menu(&mut ui, at(150., 90., false), &mut options);
+    menu(&mut ui, at(150., 90., true), &mut options);
+    assert!(options.sound);
+    assert_eq!(ui.focus(), 1);
+
+    // The track of the slider is the right half of its row, less 8 pixels
+    menu(&mut ui, at(300. + 96., 130., false), &mut options);
+    menu(&mut ui, at(300. + 96., 130., true), &mut options);
+    assert_eq!(options.volume, 0.5);
+    assert_eq!(ui.focus(), 2);
+}
@@ -0,0 +1,33 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::engine::viewport::{ScaleMode, Viewport};
+
+#[test]
+fn integer_mode_scales_by_whole_numbers_with_letterbox_bars() {
+    let viewport = Viewport::new(960., 540., 2560., 1440., 1., ScaleMode::Integer);
+    assert_eq!(viewport.scale, 2.);
+    assert_eq!((viewport.x, viewport.y), (320., 180.));
+
+    let fit = Viewport::new(960., 540., 2560., 1440., 1., ScaleMode::Fit);
+    assert!((fit.scale - 8. / 3.).abs() < 1e-9);
+    assert_eq!((fit.x, fit.y), (0., 0.));
+}
+
+#[test]
+fn small_screens_are_fitted_in_integer_mode() {
+    let viewport = Viewport::new(960., 540., 480., 400., 1., ScaleMode::Integer);
+    assert_eq!(viewport.scale, 0.5);
+    assert_eq!((viewport.x, viewport.y), (0., 65.));
+}
+
+#[test]
+fn points_are_converted_to_virtual_coordinates() {
+    // a 1280x800 CSS pixel window on a screen with two device pixels each
+    let viewport = Viewport::new(960., 540., 2560., 1600., 2., ScaleMode::Integer);
+    assert_eq!(viewport.scale, 2.);
+    assert_eq!((viewport.x, viewport.y), (320., 260.));
+    assert_eq!(viewport.to_virtual(160., 130.), Some(Vec2::new(0., 0.)));
+    assert_eq!(viewport.to_virtual(640., 400.), Some(Vec2::new(480., 270.)));
+    // on the letterbox bars
+    assert_eq!(viewport.to_virtual(100., 400.), None);
+    assert_eq!(viewport.to_virtual(640., 690.), None);
+}
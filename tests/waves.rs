@@ -0,0 +1,278 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::waves::{SpawnPoint, SpawnSelection, WaveConfig, WaveDirector};
+
+fn config(adaptive: bool) -> WaveConfig {
+    let json = r#"{
+        "break_ticks": 2,
+        "alive_cap": 3,
+        "waves": [
+            { "groups": [{ "count": 2, "interval": 1 }] },
+            {
+                "groups": [
+                    { "archetype": "green", "count": 4, "interval": 2, "spawn_points": "nearest" },
+                    { "archetype": "red", "count": 1, "interval": 1, "delay": 3 }
+                ]
+            }
+        ],
+        "escalation": { "count_scale": 1.5, "interval_scale": 0.5 },
+        "adaptive": {
+            "enabled": ADAPTIVE,
+            "min_intensity": 0.5,
+            "max_intensity": 2.0,
+            "target_kills_per_minute": 10.0
+        }
+    }"#;
+    json.replace("ADAPTIVE", &adaptive.to_string())
+        .parse()
+        .unwrap()
+}
+
+/// Runs the director for `ticks` ticks with the spawned enemies staying
+/// alive, returning the archetypes spawned on each tick
+fn run(director: &mut WaveDirector, alive: &mut usize, ticks: u32) -> Vec<Vec<Option<String>>> {
+    (0..ticks)
+        .map(|_| {
+            let orders = director.update(*alive, 1.0);
+            *alive += orders.len();
+            orders.into_iter().map(|o| o.archetype).collect()
+        })
+        .collect()
+}
+
+/// Runs the director until the current wave ends, killing every enemy
+/// right away, and returns how many spawned
+fn finish_wave(director: &mut WaveDirector) -> usize {
+    let wave = director.wave;
+    let mut spawned = 0;
+    while director.wave == wave {
+        spawned += director.update(0, 1.0).len();
+    }
+    spawned
+}
+
+#[test]
+fn waves_start_after_a_break() {
+    let mut director = WaveDirector::new(config(false));
+    assert_eq!(director.announcement(), Some(1));
+    let mut alive = 0;
+    let spawns = run(&mut director, &mut alive, 5);
+    assert!(spawns[..3].iter().all(|s| s.is_empty()));
+    assert_eq!(director.announcement(), None);
+    assert_eq!(spawns[4], vec![None]);
+}
+
+#[test]
+fn waves_end_once_everything_spawned_is_dead() {
+    let mut director = WaveDirector::new(config(false));
+    let mut alive = 0;
+    run(&mut director, &mut alive, 20);
+    assert_eq!(alive, 2);
+    assert_eq!(director.wave, 1);
+
+    director.update(0, 1.0);
+    assert_eq!(director.wave, 2);
+    assert_eq!(director.announcement(), Some(2));
+}
+
+#[test]
+fn groups_keep_their_delay_and_interval() {
+    let mut director = WaveDirector::new(config(false));
+    finish_wave(&mut director);
+    let mut alive = 0;
+    let spawns: Vec<Vec<Option<String>>> = run(&mut director, &mut alive, 3)
+        .into_iter()
+        .chain((0..8).map(|_| {
+            // kill everything so the alive cap doesn't get in the way
+            director
+                .update(0, 1.0)
+                .into_iter()
+                .map(|o| o.archetype)
+                .collect()
+        }))
+        .collect();
+    let first = |name: &str| {
+        spawns
+            .iter()
+            .position(|s| s.iter().any(|a| a.as_deref() == Some(name)))
+            .unwrap()
+    };
+    let green: Vec<usize> = (0..spawns.len())
+        .filter(|t| spawns[*t].iter().any(|a| a.as_deref() == Some("green")))
+        .collect();
+    assert_eq!(green.len(), 4);
+    assert!(green.windows(2).all(|w| w[1] - w[0] == 2));
+    assert_eq!(first("red"), first("green") + 2);
+}
+
+#[test]
+fn the_alive_cap_holds_back_spawns() {
+    let mut director = WaveDirector::new(config(false));
+    finish_wave(&mut director);
+    let mut alive = 0;
+    run(&mut director, &mut alive, 40);
+    assert_eq!(alive, 3);
+}
+
+#[test]
+fn the_last_wave_escalates_once_the_script_runs_out() {
+    let mut director = WaveDirector::new(config(false));
+    assert_eq!(finish_wave(&mut director), 2);
+    assert_eq!(finish_wave(&mut director), 5);
+    // 4 * 1.5 green and 1 * 1.5 red, rounded
+    assert_eq!(finish_wave(&mut director), 8);
+    // 4 * 2.25 green and 1 * 2.25 red, rounded
+    assert_eq!(finish_wave(&mut director), 11);
+    assert_eq!(director.wave, 5);
+}
+
+#[test]
+fn orders_keep_the_spawn_selection_of_their_group() {
+    let mut director = WaveDirector::new(config(false));
+    let orders = (0..5)
+        .flat_map(|_| director.update(0, 1.0))
+        .collect::<Vec<_>>();
+    assert_eq!(orders[0].spawn_points, SpawnSelection::Matching);
+    finish_wave(&mut director);
+    let orders = (0..5)
+        .flat_map(|_| director.update(0, 1.0))
+        .collect::<Vec<_>>();
+    let green = orders
+        .iter()
+        .find(|o| o.archetype.as_deref() == Some("green"))
+        .unwrap();
+    assert_eq!(green.spawn_points, SpawnSelection::Nearest);
+}
+
+fn points() -> Vec<SpawnPoint> {
+    vec![
+        (Vec2::new(0., 0.), 0, 10),
+        (Vec2::new(100., 0.), 1, 10),
+        (Vec2::new(200., 0.), 0, 10),
+        (Vec2::new(300., 0.), 1, 0),
+    ]
+}
+
+#[test]
+fn spawn_points_match_the_color_of_the_archetype() {
+    let mut next = 0;
+    let matching = SpawnSelection::Matching;
+    assert_eq!(
+        matching.candidates(&points(), Some(0), &[], &mut next),
+        vec![0, 2]
+    );
+    assert_eq!(
+        matching.candidates(&points(), Some(1), &[], &mut next),
+        vec![1]
+    );
+    // without a matching spawn point, or an archetype, any will do
+    assert_eq!(
+        matching.candidates(&points(), Some(2), &[], &mut next),
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        matching.candidates(&points(), None, &[], &mut next),
+        vec![0, 1, 2]
+    );
+}
+
+#[test]
+fn destroyed_spawn_points_are_never_picked() {
+    let mut next = 0;
+    let players = [Vec2::new(400., 0.)];
+    let nearest = SpawnSelection::Nearest;
+    assert_eq!(
+        nearest.candidates(&points(), None, &players, &mut next),
+        vec![2]
+    );
+    let fixed = SpawnSelection::Fixed(vec![1, 3]);
+    assert_eq!(
+        fixed.candidates(&points(), None, &players, &mut next),
+        vec![1]
+    );
+
+    let mut destroyed = points();
+    for p in destroyed.iter_mut() {
+        p.2 = 0;
+    }
+    assert!(SpawnSelection::Random
+        .candidates(&destroyed, None, &players, &mut next)
+        .is_empty());
+}
+
+#[test]
+fn spawn_points_are_picked_by_distance_to_the_closest_player() {
+    let mut next = 0;
+    let players = [Vec2::new(10., 0.), Vec2::new(170., 0.)];
+    let nearest = SpawnSelection::Nearest.candidates(&points(), None, &players, &mut next);
+    let farthest = SpawnSelection::Farthest.candidates(&points(), None, &players, &mut next);
+    assert_eq!(nearest, vec![0]);
+    // 90 from the first player and 70 from the second
+    assert_eq!(farthest, vec![1]);
+}
+
+#[test]
+fn round_robin_goes_through_the_spawn_points_in_turn() {
+    let mut next = 0;
+    let first = SpawnSelection::RoundRobin.candidates(&points(), None, &[], &mut next);
+    assert_eq!(first, vec![0]);
+    let picks: Vec<usize> = (0..4)
+        .flat_map(|_| SpawnSelection::RoundRobin.candidates(&points(), None, &[], &mut next))
+        .collect();
+    assert_eq!(picks, vec![1, 2, 0, 1]);
+}
+
+/// Intensity after `ticks` ticks with the players at `health`, killing
+/// `kills` enemies first
+fn intensity(adaptive: bool, health: f64, kills: usize, ticks: u32) -> f64 {
+    let mut director = WaveDirector::new(config(adaptive));
+    for _ in 0..kills {
+        director.record_kill();
+    }
+    for _ in 0..ticks {
+        director.update(0, health);
+    }
+    director.intensity
+}
+
+#[test]
+fn full_health_without_kills_keeps_the_intensity() {
+    assert_eq!(intensity(true, 1.0, 0, 1000), 1.0);
+}
+
+#[test]
+fn losing_health_lowers_the_intensity() {
+    let hurt = intensity(true, 0.5, 0, 1000);
+    assert!(hurt < 1.0 && hurt > 0.75 - 0.01, "{}", hurt);
+    let down = intensity(true, 0.0, 0, 1000);
+    assert!(down < hurt && down >= 0.5, "{}", down);
+}
+
+#[test]
+fn killing_fast_raises_the_intensity_up_to_the_maximum() {
+    let fast = intensity(true, 1.0, 15, 1000);
+    assert!(fast > 1.4 && fast <= 1.5, "{}", fast);
+    let faster = intensity(true, 1.0, 100, 1000);
+    assert!(faster > 1.9 && faster <= 2.0, "{}", faster);
+}
+
+#[test]
+fn kills_count_for_a_minute() {
+    let mut director = WaveDirector::new(config(true));
+    for _ in 0..100 {
+        director.record_kill();
+    }
+    for _ in 0..3600 {
+        director.update(0, 1.0);
+    }
+    let raised = director.intensity;
+    for _ in 0..1000 {
+        director.update(0, 1.0);
+    }
+    assert!(raised > 1.9);
+    assert!(director.intensity < 1.01, "{}", director.intensity);
+}
+
+#[test]
+fn intensity_stays_put_when_not_adaptive() {
+    assert_eq!(intensity(false, 0.0, 100, 1000), 1.0);
+}
@@ -0,0 +1,72 @@
+use rust_webpack_template::engine::math::Vec2;
+use rust_webpack_template::game::bullets::{Bullet, Pattern, Shooter};
+use rust_webpack_template::game::weapons::Weapon;
+
+const WEAPONS: [Weapon; 5] = [
+    Weapon::Pistol,
+    Weapon::Shotgun,
+    Weapon::MachineGun,
+    Weapon::Laser,
+    Weapon::Grenade,
+];
+
+fn fire(weapon: Weapon) -> Vec<Bullet> {
+    let mut bullets = Vec::new();
+    weapon.bullets().emit(
+        &Vec2::new(0., 0.),
+        &Vec2::new(10., 0.),
+        Shooter::Player(1),
+        &mut 0.,
+        &mut bullets,
+    );
+    bullets
+}
+
+#[test]
+fn every_weapon_fires_something() {
+    for weapon in WEAPONS.iter() {
+        assert!(!fire(*weapon).is_empty(), "{:?}", weapon);
+        assert!(weapon.heat() > 0.0);
+        assert!(weapon.ammo_cost() > 0);
+    }
+}
+
+#[test]
+fn the_shotgun_fires_a_short_spread() {
+    let bullets = fire(Weapon::Shotgun);
+    assert_eq!(bullets.len(), 5);
+    assert!(bullets.iter().all(|b| b.lifetime == 20));
+}
+
+#[test]
+fn the_machine_gun_fires_single_jittered_bullets() {
+    let spec = Weapon::MachineGun.bullets();
+    assert!(matches!(spec.pattern, Pattern::Aimed));
+    assert!(spec.jitter > 0.0);
+    for _ in 0..50 {
+        let bullets = fire(Weapon::MachineGun);
+        assert_eq!(bullets.len(), 1);
+        assert_eq!(bullets[0].delay, 0);
+        assert!(bullets[0].vel.y.atan2(bullets[0].vel.x).abs() <= spec.jitter);
+    }
+}
+
+#[test]
+fn the_laser_pierces_with_a_hit_cooldown() {
+    let bullets = fire(Weapon::Laser);
+    assert_eq!(bullets.len(), 1);
+    let laser = &bullets[0];
+    assert!(laser.pierce);
+    assert!(laser.hit_cooldown > 0);
+    // a new beam fires every tick, but can't hurt the same target until
+    // the cooldown is over
+    assert!(Weapon::Laser.heat() <= 1.0);
+    assert!(!laser.can_hit(laser.hit_cooldown));
+}
+
+#[test]
+fn grenades_explode() {
+    let grenade = &fire(Weapon::Grenade)[0];
+    assert!(grenade.splash > 0.0);
+    assert!(!grenade.pierce);
+}